@@ -1,8 +1,8 @@
 #version 430
 layout(location = 0) out vec4 out_color;
 
-uniform vec3 color;
+uniform vec4 color;
 
 void main() {
-    out_color = vec4(color, 1.0);
+    out_color = color;
 }
\ No newline at end of file
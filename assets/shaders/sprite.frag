@@ -0,0 +1,11 @@
+#version 430
+layout(location = 0) in vec2 in_tex_coords;
+layout(location = 1) in vec4 in_tint;
+
+layout(location = 0) out vec4 out_color;
+
+layout(binding = 0) uniform sampler2D atlas;
+
+void main() {
+    out_color = in_tint * texture(atlas, in_tex_coords);
+}
@@ -0,0 +1,24 @@
+#version 430
+layout(location = 0) in vec2 in_position;
+layout(location = 1) in vec2 in_tex_coords;
+// Per instance
+layout(location = 2) in vec2 in_center;
+layout(location = 3) in vec2 in_size;
+layout(location = 4) in float in_rotation;
+layout(location = 5) in vec4 in_uv;
+layout(location = 6) in vec4 in_tint;
+
+layout(location = 0) out vec2 out_tex_coords;
+layout(location = 1) out vec4 out_tint;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+void main() {
+    mat2 tex_rot = mat2(cos(in_rotation), sin(in_rotation), -sin(in_rotation), cos(in_rotation));
+    vec2 rot_tex_coords = (tex_rot * (in_tex_coords - vec2(0.5))) + vec2(0.5);
+    // The atlas has its top row first while quads have y going up
+    out_tex_coords = mix(in_uv.xw, in_uv.zy, rot_tex_coords);
+    out_tint = in_tint;
+    gl_Position = projection * view * vec4(in_center + in_position * in_size, 0.0, 1.0);
+}
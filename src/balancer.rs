@@ -0,0 +1,393 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::grid::{Grid, LEFT_LANE};
+
+// Most inputs or outputs a balancer may have, every combination of them is checked
+pub const MAX_PORTS: usize = 8;
+
+const EPSILON: f64 = 0.0001;
+
+type Tile = (isize, isize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Counterexample {
+    // Items entering at `input` leave through each output in these shares instead of
+    // evenly, shares not adding up to one means items get stuck
+    Unbalanced {
+        input: Tile,
+        shares: Vec<(Tile, f64)>,
+    },
+    // Full belts going into `inputs` only get `throughput` full belts out of `outputs`
+    Limited {
+        inputs: Vec<Tile>,
+        outputs: Vec<Tile>,
+        throughput: usize,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Verification {
+    pub imbalance: Option<Counterexample>,
+    pub limit: Option<Counterexample>,
+}
+
+impl Verification {
+    pub fn is_balanced(&self) -> bool {
+        self.imbalance.is_none()
+    }
+
+    pub fn is_throughput_unlimited(&self) -> bool {
+        self.limit.is_none()
+    }
+
+    pub fn describe(&self) -> String {
+        let mut lines = vec![
+            format!("balanced: {}", self.is_balanced()),
+            format!("throughput unlimited: {}", self.is_throughput_unlimited()),
+        ];
+        if let Some(Counterexample::Unbalanced { input, shares }) = &self.imbalance {
+            let shares: Vec<String> = shares
+                .iter()
+                .map(|(output, share)| format!("{:?}: {:.3}", output, share))
+                .collect();
+            lines.push(format!(
+                "  items from {:?} leave as {}",
+                input,
+                shares.join(", ")
+            ));
+        }
+        if let Some(Counterexample::Limited {
+            inputs,
+            outputs,
+            throughput,
+        }) = &self.limit
+        {
+            lines.push(format!(
+                "  full inputs {:?} only move {} of {} belts to outputs {:?}",
+                inputs,
+                throughput,
+                inputs.len(),
+                outputs
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+// The belts of a region and where each of them hands items to within the region
+struct Network {
+    tiles: Vec<Tile>,
+    index: HashMap<Tile, usize>,
+    edges: Vec<Vec<usize>>,
+    // Whether the belt's own front is outside the region
+    leaves: Vec<bool>,
+}
+
+impl Network {
+    fn new(grid: &Grid, min: Tile, max: Tile) -> Self {
+        let mut tiles = Vec::new();
+        let mut index = HashMap::new();
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                if grid.get_belt(x, y).is_some() {
+                    index.insert((x, y), tiles.len());
+                    tiles.push((x, y));
+                }
+            }
+        }
+
+        let mut edges = Vec::with_capacity(tiles.len());
+        let mut leaves = Vec::with_capacity(tiles.len());
+        for &(x, y) in tiles.iter() {
+            let belt = grid.get_belt(x, y).unwrap();
+            let targets = grid.belt_targets(x, y, belt);
+            leaves.push(!index.contains_key(&targets[0]));
+            edges.push(
+                targets
+                    .into_iter()
+                    .filter(|&(front_x, front_y)| {
+                        (front_x, front_y) != (x, y)
+                            && grid.get_belt(front_x, front_y).is_some_and(|front_belt| {
+                                belt.target_lane(&front_belt, LEFT_LANE).is_some()
+                            })
+                    })
+                    .filter_map(|target| index.get(&target).copied())
+                    .collect(),
+            );
+        }
+
+        Self {
+            tiles,
+            index,
+            edges,
+            leaves,
+        }
+    }
+}
+
+// Belts of the region that aren't fed by another belt in it, and belts that output
+// out of it
+pub fn boundary_belts(grid: &Grid, min: Tile, max: Tile) -> (Vec<Tile>, Vec<Tile>) {
+    let network = Network::new(grid, min, max);
+    let mut fed = vec![false; network.tiles.len()];
+    for &next in network.edges.iter().flatten() {
+        fed[next] = true;
+    }
+
+    let inputs = (0..network.tiles.len())
+        .filter(|&i| !fed[i])
+        .map(|i| network.tiles[i])
+        .collect();
+    let outputs = (0..network.tiles.len())
+        .filter(|&i| network.leaves[i])
+        .map(|i| network.tiles[i])
+        .collect();
+    (inputs, outputs)
+}
+
+// Checks whether the belts between `min` and `max` (inclusive) split items from every
+// input evenly across all outputs, and whether any k full inputs can fill any k
+// outputs. Splitters are modeled as sending half of their items to each side.
+pub fn verify(
+    grid: &Grid,
+    min: Tile,
+    max: Tile,
+    inputs: &[Tile],
+    outputs: &[Tile],
+) -> Result<Verification, String> {
+    if inputs.len() > MAX_PORTS || outputs.len() > MAX_PORTS {
+        return Err(format!("At most {} inputs and outputs", MAX_PORTS));
+    }
+
+    let network = Network::new(grid, min, max);
+    let nodes = |tiles: &[Tile]| {
+        tiles
+            .iter()
+            .map(|tile| {
+                network
+                    .index
+                    .get(tile)
+                    .copied()
+                    .ok_or_else(|| format!("No belt at {:?} in the selection", tile))
+            })
+            .collect::<Result<Vec<usize>, String>>()
+    };
+    let input_nodes = nodes(inputs)?;
+    let output_nodes = nodes(outputs)?;
+
+    Ok(Verification {
+        imbalance: find_imbalance(&network, &input_nodes, &output_nodes),
+        limit: find_limit(&network, &input_nodes, &output_nodes),
+    })
+}
+
+fn find_imbalance(
+    network: &Network,
+    inputs: &[usize],
+    outputs: &[usize],
+) -> Option<Counterexample> {
+    let even_share = 1.0 / outputs.len() as f64;
+    for &input in inputs {
+        let shares = propagate(network, input, outputs);
+        if shares
+            .iter()
+            .any(|share| (share - even_share).abs() > EPSILON)
+        {
+            return Some(Counterexample::Unbalanced {
+                input: network.tiles[input],
+                shares: outputs
+                    .iter()
+                    .map(|&output| network.tiles[output])
+                    .zip(shares)
+                    .collect(),
+            });
+        }
+    }
+    None
+}
+
+// Follows a single unit of items from `input` until it has reached the outputs, is
+// stuck or keeps circling in loops. Returns the share reaching each output.
+fn propagate(network: &Network, input: usize, outputs: &[usize]) -> Vec<f64> {
+    let mut shares = vec![0.0; outputs.len()];
+    let mut amounts = vec![0.0; network.tiles.len()];
+    amounts[input] = 1.0;
+    for _ in 0..100_000 {
+        let mut next = vec![0.0; network.tiles.len()];
+        let mut moving = 0.0;
+        for (node, &amount) in amounts.iter().enumerate() {
+            if amount == 0.0 {
+                continue;
+            }
+            if let Some(output) = outputs.iter().position(|&output| output == node) {
+                shares[output] += amount;
+            } else if !network.edges[node].is_empty() {
+                let share = amount / network.edges[node].len() as f64;
+                for &target in network.edges[node].iter() {
+                    next[target] += share;
+                }
+                moving += amount;
+            }
+        }
+        if moving < EPSILON * EPSILON {
+            break;
+        }
+        amounts = next;
+    }
+    shares
+}
+
+fn find_limit(network: &Network, inputs: &[usize], outputs: &[usize]) -> Option<Counterexample> {
+    for count in 1..=inputs.len().min(outputs.len()) {
+        for input_set in subsets(inputs, count) {
+            for output_set in subsets(outputs, count) {
+                let throughput = max_flow(network, &input_set, &output_set);
+                if throughput < count {
+                    return Some(Counterexample::Limited {
+                        inputs: input_set.iter().map(|&node| network.tiles[node]).collect(),
+                        outputs: output_set.iter().map(|&node| network.tiles[node]).collect(),
+                        throughput,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn subsets(nodes: &[usize], count: usize) -> Vec<Vec<usize>> {
+    (0u32..1 << nodes.len())
+        .filter(|mask| mask.count_ones() as usize == count)
+        .map(|mask| {
+            (0..nodes.len())
+                .filter(|&i| mask & (1 << i) != 0)
+                .map(|i| nodes[i])
+                .collect()
+        })
+        .collect()
+}
+
+// Most full belts that can get from the inputs to the outputs, every belt carrying at
+// most one full belt
+fn max_flow(network: &Network, inputs: &[usize], outputs: &[usize]) -> usize {
+    // Each belt is split into an entry and an exit node joined by an edge of capacity
+    // one, followed by the source and the sink. Edges are stored in pairs with their
+    // reverse edge next to them.
+    let node_count = network.tiles.len() * 2 + 2;
+    let source = node_count - 2;
+    let sink = node_count - 1;
+    let mut adjacent = vec![Vec::new(); node_count];
+    let mut edges: Vec<(usize, u32)> = Vec::new();
+    let mut add_edge = |from: usize, to: usize| {
+        adjacent[from].push(edges.len());
+        edges.push((to, 1));
+        adjacent[to].push(edges.len());
+        edges.push((from, 0));
+    };
+    for node in 0..network.tiles.len() {
+        add_edge(node * 2, node * 2 + 1);
+        if !outputs.contains(&node) {
+            for &target in network.edges[node].iter() {
+                add_edge(node * 2 + 1, target * 2);
+            }
+        }
+    }
+    for &input in inputs {
+        add_edge(source, input * 2);
+    }
+    for &output in outputs {
+        add_edge(output * 2 + 1, sink);
+    }
+
+    let mut flow = 0;
+    loop {
+        // Edge used to reach each node on the shortest augmenting path
+        let mut reached_by = vec![None; node_count];
+        let mut queue = VecDeque::new();
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for &edge in adjacent[node].iter() {
+                let (next, capacity) = edges[edge];
+                if capacity > 0 && next != source && reached_by[next].is_none() {
+                    reached_by[next] = Some(edge);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if reached_by[sink].is_none() {
+            return flow;
+        }
+        let mut node = sink;
+        while let Some(edge) = reached_by[node] {
+            edges[edge].1 -= 1;
+            edges[edge ^ 1].1 += 1;
+            node = edges[edge ^ 1].0;
+        }
+        flow += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Belt, Direction};
+
+    // Belts going north from y = 0 to y = 2 with splitters over the given columns on y = 1
+    fn build(width: isize, splitters: &[isize]) -> Grid {
+        let mut grid = Grid::new();
+        for &x in splitters {
+            assert!(grid.place_splitter(x, 1, Direction::North));
+        }
+        for x in 0..width {
+            for y in 0..3 {
+                if grid.get_entity(x, y).is_none() {
+                    grid.place_belt(x, y, Belt::straight(Direction::North));
+                }
+            }
+        }
+        grid
+    }
+
+    fn check(grid: &Grid, width: isize) -> Verification {
+        let (inputs, outputs) = boundary_belts(grid, (0, 0), (width - 1, 2));
+        assert_eq!(inputs.len(), width as usize);
+        assert_eq!(outputs.len(), width as usize);
+        verify(grid, (0, 0), (width - 1, 2), &inputs, &outputs).unwrap()
+    }
+
+    #[test]
+    fn single_splitter_is_a_two_to_two_balancer() {
+        let verification = check(&build(2, &[0]), 2);
+        assert!(verification.is_balanced());
+        assert!(verification.is_throughput_unlimited());
+    }
+
+    #[test]
+    fn bypassing_belt_is_reported() {
+        let verification = check(&build(3, &[0]), 3);
+        assert_eq!(
+            verification.imbalance,
+            Some(Counterexample::Unbalanced {
+                input: (0, 0),
+                shares: vec![((0, 2), 0.5), ((1, 2), 0.5), ((2, 2), 0.0)],
+            })
+        );
+        assert_eq!(
+            verification.limit,
+            Some(Counterexample::Limited {
+                inputs: vec![(0, 0)],
+                outputs: vec![(2, 2)],
+                throughput: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn ports_outside_the_selection_are_an_error() {
+        let grid = build(2, &[0]);
+        let outputs = [(0, 2), (1, 2)];
+        assert!(verify(&grid, (0, 0), (1, 2), &[(5, 5)], &outputs).is_err());
+        assert!(verify(&grid, (0, 0), (1, 2), &[(0, 0)], &outputs).is_ok());
+    }
+}
@@ -1,5 +1,23 @@
 use cgmath::{ortho, point2, point3, vec3, Matrix4, Point2, Vector3};
 
+// Tiles from min to max, excluding max
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileRect {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl TileRect {
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        x >= self.min_x as isize
+            && y >= self.min_y as isize
+            && x < self.max_x as isize
+            && y < self.max_y as isize
+    }
+}
+
 pub struct Camera {
     pub position: Point2<f32>,
 }
@@ -14,14 +32,6 @@ impl Camera {
         }
     }
 
-    pub fn move_horizontal(&mut self, amount: f32) {
-        self.position.x += amount;
-    }
-
-    pub fn move_vertical(&mut self, amount: f32) {
-        self.position.y += amount;
-    }
-
     pub fn view_matrix(&self) -> Matrix4<f32> {
         Matrix4::look_to_rh(
             point3(self.position.x, self.position.y, 1.0),
@@ -40,4 +50,53 @@ impl Camera {
             -1.0,
         )
     }
+    // Tiles of a `columns` by `rows` grid that are at least partly on screen
+    pub fn visible_tiles(
+        &self,
+        width: f32,
+        height: f32,
+        zoom: f32,
+        tile_size: f32,
+        columns: usize,
+        rows: usize,
+    ) -> TileRect {
+        let half_width = width / zoom / 2.0;
+        let half_height = height / zoom / 2.0;
+        let tile = |position: f32, limit: usize| (position / tile_size).clamp(0.0, limit as f32);
+        TileRect {
+            min_x: tile(self.position.x - half_width, columns).floor() as usize,
+            min_y: tile(self.position.y - half_height, rows).floor() as usize,
+            max_x: tile(self.position.x + half_width, columns).ceil() as usize,
+            max_y: tile(self.position.y + half_height, rows).ceil() as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_tiles_cover_the_screen_and_stay_on_the_grid() {
+        let mut camera = Camera::new();
+        camera.position = point2(320.0, 160.0);
+        let visible = camera.visible_tiles(200.0, 100.0, 2.0, 32.0, 128, 128);
+        // 100 by 50 world units around the camera
+        assert_eq!(
+            visible,
+            TileRect {
+                min_x: 8,
+                min_y: 4,
+                max_x: 12,
+                max_y: 6
+            }
+        );
+        assert!(visible.contains(11, 5));
+        assert!(!visible.contains(12, 5));
+
+        camera.position = point2(-1000.0, 5000.0);
+        let visible = camera.visible_tiles(200.0, 100.0, 1.0, 32.0, 128, 128);
+        assert_eq!((visible.min_x, visible.max_x), (0, 0));
+        assert_eq!((visible.min_y, visible.max_y), (128, 128));
+    }
 }
@@ -0,0 +1,432 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+
+use crate::item::Item;
+
+// Maximum distance between two entities for a circuit wire to connect them
+pub const CIRCUIT_WIRE_REACH: f32 = 9.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Signal {
+    Item(Item),
+    // Virtual signals not tied to an item, 'A' to 'Z'
+    Letter(char),
+}
+
+impl Signal {
+    pub fn name(&self) -> String {
+        match *self {
+            Self::Item(item) => item.name().to_string(),
+            Self::Letter(letter) => format!("signal-{}", letter),
+        }
+    }
+}
+
+// Signal values on a network, signals with a value of zero are left out
+pub type Signals = BTreeMap<Signal, i32>;
+
+pub fn add_signal(signals: &mut Signals, signal: Signal, value: i32) {
+    let total = signals
+        .get(&signal)
+        .copied()
+        .unwrap_or(0)
+        .wrapping_add(value);
+    if total == 0 {
+        signals.remove(&signal);
+    } else {
+        signals.insert(signal, total);
+    }
+}
+
+pub fn add_signals(signals: &mut Signals, other: &Signals) {
+    for (&signal, &value) in other.iter() {
+        add_signal(signals, signal, value);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WireColor {
+    Red,
+    Green,
+}
+
+// Connection point of an entity. Combinators read from their input and write to their
+// output, every other entity only has an input which it both reads and writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Terminal {
+    Input,
+    Output,
+}
+
+pub type Port = (isize, isize, Terminal);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Signal(Signal),
+    Constant(i32),
+}
+
+impl Operand {
+    pub fn value(&self, signals: &Signals) -> i32 {
+        match *self {
+            Self::Signal(signal) => signals.get(&signal).copied().unwrap_or(0),
+            Self::Constant(value) => value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparator {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparator {
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::Less => Self::LessOrEqual,
+            Self::LessOrEqual => Self::Equal,
+            Self::Equal => Self::NotEqual,
+            Self::NotEqual => Self::GreaterOrEqual,
+            Self::GreaterOrEqual => Self::Greater,
+            Self::Greater => Self::Less,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Condition {
+    pub left: Signal,
+    pub comparator: Comparator,
+    pub right: Operand,
+}
+
+impl Condition {
+    pub fn new(left: Signal, comparator: Comparator, right: Operand) -> Self {
+        Self {
+            left,
+            comparator,
+            right,
+        }
+    }
+
+    pub fn evaluate(&self, signals: &Signals) -> bool {
+        let left = Operand::Signal(self.left).value(signals);
+        let right = self.right.value(signals);
+        match self.comparator {
+            Comparator::Less => left < right,
+            Comparator::LessOrEqual => left <= right,
+            Comparator::Equal => left == right,
+            Comparator::NotEqual => left != right,
+            Comparator::GreaterOrEqual => left >= right,
+            Comparator::Greater => left > right,
+        }
+    }
+}
+
+// Circuit behaviour of a belt or inserter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircuitControl {
+    // Puts the items on the entity onto its networks
+    pub read_contents: bool,
+    // Only runs while the condition holds
+    pub condition: Option<Condition>,
+    // Result of the condition on the last circuit update
+    pub enabled: bool,
+}
+
+impl CircuitControl {
+    pub fn new() -> Self {
+        Self {
+            read_contents: false,
+            condition: None,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CircuitNetwork {
+    pub ports: Vec<Port>,
+    // Sum of everything put on the network during the last update
+    pub signals: Signals,
+}
+
+pub struct CircuitGrid {
+    pub wires: Vec<(Port, Port, WireColor)>,
+    pub networks: Vec<CircuitNetwork>,
+    network_of: HashMap<(Port, WireColor), usize>,
+}
+
+impl CircuitGrid {
+    pub fn new() -> Self {
+        Self {
+            wires: Vec::new(),
+            networks: Vec::new(),
+            network_of: HashMap::new(),
+        }
+    }
+
+    pub fn in_reach(a: Port, b: Port) -> bool {
+        let dx = (a.0 - b.0) as f32;
+        let dy = (a.1 - b.1) as f32;
+        dx * dx + dy * dy <= CIRCUIT_WIRE_REACH * CIRCUIT_WIRE_REACH
+    }
+
+    // Adds a wire unless the same one already exists, returns whether it was added
+    pub fn connect(&mut self, from: Port, to: Port, color: WireColor) -> bool {
+        let exists = self.wires.iter().any(|&(a, b, wire_color)| {
+            wire_color == color && ((a, b) == (from, to) || (a, b) == (to, from))
+        });
+        if from == to || exists {
+            return false;
+        }
+
+        self.wires.push((from, to, color));
+        self.rebuild();
+        true
+    }
+
+    // Removes all wires of the entity on (x, y)
+    pub fn disconnect_tile(&mut self, x: isize, y: isize) {
+        let count = self.wires.len();
+        self.wires
+            .retain(|&(a, b, _)| (a.0, a.1) != (x, y) && (b.0, b.1) != (x, y));
+        if self.wires.len() != count {
+            self.rebuild();
+        }
+    }
+
+    pub fn network_at(&self, port: Port, color: WireColor) -> Option<usize> {
+        self.network_of.get(&(port, color)).copied()
+    }
+
+    // Sum of the signals of the red and green network connected to a port
+    pub fn signals_at(&self, port: Port) -> Signals {
+        let mut signals = Signals::new();
+        for color in [WireColor::Red, WireColor::Green] {
+            if let Some(network) = self.network_at(port, color) {
+                add_signals(&mut signals, &self.networks[network].signals);
+            }
+        }
+        signals
+    }
+
+    // Groups ports joined by wires of the same color into networks, in the order the
+    // wires were placed
+    fn rebuild(&mut self) {
+        self.networks.clear();
+        self.network_of.clear();
+        for &(start, _, color) in self.wires.iter() {
+            if self.network_of.contains_key(&(start, color)) {
+                continue;
+            }
+
+            let id = self.networks.len();
+            let mut network = CircuitNetwork {
+                ports: Vec::new(),
+                signals: Signals::new(),
+            };
+            let mut queue = VecDeque::new();
+            self.network_of.insert((start, color), id);
+            queue.push_back(start);
+            while let Some(port) = queue.pop_front() {
+                network.ports.push(port);
+                for &(a, b, wire_color) in self.wires.iter() {
+                    if wire_color != color {
+                        continue;
+                    }
+                    let next = if a == port {
+                        b
+                    } else if b == port {
+                        a
+                    } else {
+                        continue;
+                    };
+                    if let Entry::Vacant(entry) = self.network_of.entry((next, color)) {
+                        entry.insert(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            self.networks.push(network);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{
+            combinator::{ArithmeticCombinator, ArithmeticOperation, ConstantCombinator},
+            Entity,
+        },
+        grid::{Belt, Direction, Grid},
+    };
+
+    const A: Signal = Signal::Letter('A');
+
+    fn constant(grid: &mut Grid, x: isize, y: isize, signal: Signal, value: i32) {
+        let combinator = ConstantCombinator::new().with_signal(signal, value);
+        grid.place_entity(x, y, Entity::ConstantCombinator(combinator));
+    }
+
+    #[test]
+    fn networks_sum_what_is_put_on_them() {
+        let mut grid = Grid::new();
+        constant(&mut grid, 0, 0, A, 2);
+        constant(&mut grid, 1, 0, A, 3);
+        constant(&mut grid, 2, 0, Signal::Item(Item::Coal), 5);
+        constant(&mut grid, 3, 0, A, 0);
+        assert!(grid.connect_wire(
+            (0, 0, Terminal::Input),
+            (3, 0, Terminal::Input),
+            WireColor::Red
+        ));
+        assert!(grid.connect_wire(
+            (1, 0, Terminal::Output),
+            (3, 0, Terminal::Input),
+            WireColor::Red
+        ));
+        assert!(grid.connect_wire(
+            (2, 0, Terminal::Input),
+            (3, 0, Terminal::Input),
+            WireColor::Green
+        ));
+        grid.tick();
+
+        let signals = grid.circuit.signals_at((3, 0, Terminal::Input));
+        assert_eq!(signals.get(&A), Some(&5));
+        assert_eq!(signals.get(&Signal::Item(Item::Coal)), Some(&5));
+        assert_eq!(grid.circuit.networks.len(), 2);
+    }
+
+    #[test]
+    fn arithmetic_combinator_feeding_itself_counts_ticks() {
+        let mut grid = Grid::new();
+        let counter = ArithmeticCombinator::new(
+            Direction::East,
+            Operand::Signal(A),
+            ArithmeticOperation::Add,
+            Operand::Constant(1),
+            A,
+        );
+        grid.place_entity(0, 0, Entity::ArithmeticCombinator(counter));
+        assert!(grid.connect_wire(
+            (0, 0, Terminal::Output),
+            (0, 0, Terminal::Input),
+            WireColor::Red
+        ));
+        for _ in 0..10 {
+            grid.tick();
+        }
+
+        match grid.get_entity(0, 0) {
+            Some(Entity::ArithmeticCombinator(counter)) => {
+                assert_eq!(counter.output.get(&A), Some(&10))
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn belt_condition_stops_and_starts_the_belt() {
+        let mut grid = Grid::new();
+        grid.place_belt(0, 0, Belt::straight(Direction::East));
+        grid.place_belt(1, 0, Belt::straight(Direction::East));
+        grid.drop_item(0, 0, Direction::North, Item::Coal);
+        if let Some(Entity::Belt(belt)) = grid.get_entity_mut(0, 0) {
+            belt.circuit = Some(CircuitControl {
+                condition: Some(Condition::new(A, Comparator::Greater, Operand::Constant(0))),
+                ..CircuitControl::new()
+            });
+        }
+        constant(&mut grid, 0, 1, A, 0);
+        assert!(grid.connect_wire(
+            (0, 1, Terminal::Input),
+            (0, 0, Terminal::Input),
+            WireColor::Green
+        ));
+
+        let position = |grid: &Grid| {
+            grid.get_belt(0, 0).unwrap().lanes[1]
+                .front()
+                .map(|item| item.position)
+        };
+        grid.tick();
+        let stopped_at = position(&grid);
+        for _ in 0..10 {
+            grid.tick();
+        }
+        assert_eq!(position(&grid), stopped_at);
+
+        if let Some(Entity::ConstantCombinator(combinator)) = grid.get_entity_mut(0, 1) {
+            add_signal(&mut combinator.signals, A, 1);
+        }
+        for _ in 0..40 {
+            grid.tick();
+        }
+        assert_eq!(position(&grid), None);
+        assert_eq!(grid.get_belt(1, 0).unwrap().lanes[0].len(), 1);
+    }
+
+    #[test]
+    fn stopped_belts_leave_their_items_standing_still() {
+        let mut grid = Grid::new();
+        grid.place_belt(0, 0, Belt::straight(Direction::East));
+        grid.drop_item(0, 0, Direction::North, Item::Coal);
+        if let Some(Entity::Belt(belt)) = grid.get_entity_mut(0, 0) {
+            belt.circuit = Some(CircuitControl {
+                condition: Some(Condition::new(A, Comparator::Greater, Operand::Constant(0))),
+                ..CircuitControl::new()
+            });
+        }
+        constant(&mut grid, 0, 1, A, 1);
+        assert!(grid.connect_wire(
+            (0, 1, Terminal::Input),
+            (0, 0, Terminal::Input),
+            WireColor::Green
+        ));
+
+        let moved = |grid: &Grid| grid.get_belt(0, 0).unwrap().lanes[0].front().unwrap().moved;
+        grid.tick();
+        assert!(moved(&grid) > 0);
+
+        if let Some(Entity::ConstantCombinator(combinator)) = grid.get_entity_mut(0, 1) {
+            add_signal(&mut combinator.signals, A, -1);
+        }
+        grid.tick();
+        grid.tick();
+        assert_eq!(moved(&grid), 0);
+    }
+
+    #[test]
+    fn belt_reader_counts_items() {
+        let mut grid = Grid::new();
+        grid.place_belt(0, 0, Belt::straight(Direction::East));
+        grid.drop_item(0, 0, Direction::North, Item::Coal);
+        grid.drop_item(0, 0, Direction::South, Item::IronOre);
+        if let Some(Entity::Belt(belt)) = grid.get_entity_mut(0, 0) {
+            belt.circuit = Some(CircuitControl {
+                read_contents: true,
+                ..CircuitControl::new()
+            });
+        }
+        constant(&mut grid, 1, 1, A, 1);
+        assert!(grid.connect_wire(
+            (0, 0, Terminal::Input),
+            (1, 1, Terminal::Input),
+            WireColor::Red
+        ));
+        grid.tick();
+
+        let signals = grid.circuit.signals_at((1, 1, Terminal::Input));
+        assert_eq!(signals.get(&Signal::Item(Item::Coal)), Some(&1));
+        assert_eq!(signals.get(&Signal::Item(Item::IronOre)), Some(&1));
+        assert_eq!(signals.get(&A), Some(&1));
+    }
+}
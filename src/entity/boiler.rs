@@ -0,0 +1,56 @@
+use crate::{
+    fluid::{Fluid, FluidBox},
+    grid::Direction,
+    inventory::Inventory,
+    item::Item,
+    stats::ProductionStats,
+};
+
+// Water turned into steam each tick while burning
+pub const BOILER_RATE: f32 = 1.0;
+
+// Burns fuel to turn water taken in from behind into steam output in `direction`
+#[derive(Clone, Debug)]
+pub struct Boiler {
+    pub direction: Direction,
+    pub fuel: Inventory,
+    pub burn_ticks: u32,
+    pub water: FluidBox,
+    pub steam: FluidBox,
+}
+
+impl Boiler {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            fuel: Inventory::new(1),
+            burn_ticks: 0,
+            water: FluidBox::filtered(200.0, Fluid::Water),
+            steam: FluidBox::filtered(200.0, Fluid::Steam),
+        }
+    }
+
+    pub fn insert(&mut self, item: Item) -> bool {
+        item.burn_ticks().is_some() && self.fuel.insert(item, 1) == 1
+    }
+
+    pub fn tick(&mut self, stats: &mut ProductionStats) {
+        if self.water.amount < BOILER_RATE || self.steam.space() < BOILER_RATE {
+            return;
+        }
+
+        if self.burn_ticks == 0 {
+            match self.fuel.extract_any(1) {
+                Some(fuel) if fuel.count == 1 => {
+                    stats.consume(fuel.item, 1);
+                    self.burn_ticks = fuel.item.burn_ticks().unwrap();
+                }
+                _ => return,
+            }
+        }
+
+        self.burn_ticks -= 1;
+        self.water.drain(BOILER_RATE);
+        self.steam.fill(Fluid::Steam, BOILER_RATE);
+    }
+}
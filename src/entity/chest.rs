@@ -0,0 +1,26 @@
+use crate::{inventory::Inventory, logistics::LogisticMode};
+
+pub const CHEST_SLOTS: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct Chest {
+    pub inventory: Inventory,
+    // Role in the logistic network, plain chests are ignored by robots
+    pub logistic: Option<LogisticMode>,
+}
+
+impl Chest {
+    pub fn new() -> Self {
+        Self {
+            inventory: Inventory::new(CHEST_SLOTS),
+            logistic: None,
+        }
+    }
+
+    pub fn logistic(mode: LogisticMode) -> Self {
+        Self {
+            logistic: Some(mode),
+            ..Self::new()
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    circuit::{add_signal, Condition, Operand, Signal, Signals},
+    grid::Direction,
+};
+
+// Puts a fixed set of signals on its networks
+#[derive(Clone, Debug)]
+pub struct ConstantCombinator {
+    pub signals: Signals,
+}
+
+impl ConstantCombinator {
+    pub fn new() -> Self {
+        Self {
+            signals: Signals::new(),
+        }
+    }
+
+    pub fn with_signal(mut self, signal: Signal, value: i32) -> Self {
+        add_signal(&mut self.signals, signal, value);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArithmeticOperation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl ArithmeticOperation {
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::Add => Self::Subtract,
+            Self::Subtract => Self::Multiply,
+            Self::Multiply => Self::Divide,
+            Self::Divide => Self::Modulo,
+            Self::Modulo => Self::Add,
+        }
+    }
+
+    // Integer arithmetic wraps around, dividing by zero gives zero
+    pub fn apply(&self, left: i32, right: i32) -> i32 {
+        match *self {
+            Self::Add => left.wrapping_add(right),
+            Self::Subtract => left.wrapping_sub(right),
+            Self::Multiply => left.wrapping_mul(right),
+            Self::Divide => left.checked_div(right).unwrap_or(0),
+            Self::Modulo => left.checked_rem(right).unwrap_or(0),
+        }
+    }
+}
+
+// Reads its input, outputs `left operation right` as `output_signal` on the next tick
+#[derive(Clone, Debug)]
+pub struct ArithmeticCombinator {
+    pub direction: Direction,
+    pub left: Operand,
+    pub operation: ArithmeticOperation,
+    pub right: Operand,
+    pub output_signal: Signal,
+    pub output: Signals,
+}
+
+impl ArithmeticCombinator {
+    pub fn new(
+        direction: Direction,
+        left: Operand,
+        operation: ArithmeticOperation,
+        right: Operand,
+        output_signal: Signal,
+    ) -> Self {
+        Self {
+            direction,
+            left,
+            operation,
+            right,
+            output_signal,
+            output: Signals::new(),
+        }
+    }
+
+    pub fn update(&mut self, input: &Signals) {
+        let value = self
+            .operation
+            .apply(self.left.value(input), self.right.value(input));
+        self.output = Signals::new();
+        add_signal(&mut self.output, self.output_signal, value);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeciderOutput {
+    One,
+    // The value the output signal has on the input
+    InputCount,
+}
+
+impl DeciderOutput {
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::One => Self::InputCount,
+            Self::InputCount => Self::One,
+        }
+    }
+}
+
+// Reads its input, outputs `output_signal` while the condition holds
+#[derive(Clone, Debug)]
+pub struct DeciderCombinator {
+    pub direction: Direction,
+    pub condition: Condition,
+    pub output_signal: Signal,
+    pub output_mode: DeciderOutput,
+    pub output: Signals,
+}
+
+impl DeciderCombinator {
+    pub fn new(
+        direction: Direction,
+        condition: Condition,
+        output_signal: Signal,
+        output_mode: DeciderOutput,
+    ) -> Self {
+        Self {
+            direction,
+            condition,
+            output_signal,
+            output_mode,
+            output: Signals::new(),
+        }
+    }
+
+    pub fn update(&mut self, input: &Signals) {
+        self.output = Signals::new();
+        if self.condition.evaluate(input) {
+            let value = match self.output_mode {
+                DeciderOutput::One => 1,
+                DeciderOutput::InputCount => Operand::Signal(self.output_signal).value(input),
+            };
+            add_signal(&mut self.output, self.output_signal, value);
+        }
+    }
+}
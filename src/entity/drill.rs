@@ -0,0 +1,24 @@
+use crate::{grid::Direction, item::Item};
+
+// Ticks needed to mine a single item
+pub const DRILL_MINING_TICKS: u32 = 120;
+
+// Mines the resource on its tile and outputs onto the tile in its output direction
+#[derive(Clone, Copy, Debug)]
+pub struct Drill {
+    pub output: Direction,
+    // Mining progress, in ticks times FULL_SATISFACTION
+    pub progress: u32,
+    // Mined item waiting for space on the output tile
+    pub output_buffer: Option<Item>,
+}
+
+impl Drill {
+    pub fn new(output: Direction) -> Self {
+        Self {
+            output,
+            progress: 0,
+            output_buffer: None,
+        }
+    }
+}
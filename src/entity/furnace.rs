@@ -0,0 +1,260 @@
+use crate::{
+    fluid::{Fluid, FluidBox},
+    inventory::Inventory,
+    item::Item,
+    recipe::{self, Recipe},
+    stats::ProductionStats,
+};
+
+// Burner furnace, picks its recipe from whatever is put in the source slot. Water for
+// recipes that need it is piped in from any side.
+#[derive(Clone, Debug)]
+pub struct Furnace {
+    pub fuel: Inventory,
+    pub source: Inventory,
+    pub result: Inventory,
+    pub water: FluidBox,
+    // Ticks left of the currently burning fuel item
+    pub burn_ticks: u32,
+    pub recipe: Option<&'static Recipe>,
+    pub progress: u32,
+}
+
+impl Furnace {
+    pub fn new() -> Self {
+        Self {
+            fuel: Inventory::new(1),
+            source: Inventory::new(1),
+            result: Inventory::new(1),
+            water: FluidBox::filtered(100.0, Fluid::Water),
+            burn_ticks: 0,
+            recipe: None,
+            progress: 0,
+        }
+    }
+
+    pub fn is_burning(&self) -> bool {
+        self.burn_ticks > 0
+    }
+
+    // Puts an item in the fuel or source slot depending on what it is.
+    // Returns whether the item was accepted.
+    pub fn insert(&mut self, item: Item) -> bool {
+        if item.burn_ticks().is_some() {
+            self.fuel.insert(item, 1) == 1
+        } else if recipe::smelting_recipe_for(item).is_some() {
+            self.source.insert(item, 1) == 1
+        } else {
+            false
+        }
+    }
+
+    pub fn tick(&mut self, stats: &mut ProductionStats) {
+        if self.recipe.is_none() {
+            self.start_recipe(stats);
+        }
+
+        let recipe = match self.recipe {
+            Some(recipe) => recipe,
+            None => return,
+        };
+
+        if self.burn_ticks == 0 {
+            match self.fuel.extract_any(1) {
+                Some(fuel) if fuel.count == 1 => {
+                    stats.consume(fuel.item, 1);
+                    self.burn_ticks = fuel.item.burn_ticks().unwrap();
+                }
+                _ => return,
+            }
+        }
+
+        self.burn_ticks -= 1;
+        self.progress += 1;
+        if self.progress >= recipe.ticks {
+            for &(item, count) in recipe.results {
+                self.result.insert(item, count);
+                stats.produce(item, count);
+            }
+            self.recipe = None;
+            self.progress = 0;
+        }
+    }
+
+    fn start_recipe(&mut self, stats: &mut ProductionStats) {
+        let source = match self.source.slots()[0] {
+            Some(stack) => stack,
+            None => return,
+        };
+        let recipe = match recipe::smelting_recipe_for(source.item) {
+            Some(recipe) => recipe,
+            None => return,
+        };
+
+        let has_ingredients = recipe
+            .ingredients
+            .iter()
+            .all(|&(item, count)| self.source.count(item) >= count);
+        let has_fluids = recipe
+            .fluid_ingredients
+            .iter()
+            .all(|&(fluid, amount)| self.water.fluid == Some(fluid) && self.water.amount >= amount);
+        let has_space = recipe
+            .results
+            .iter()
+            .all(|&(item, count)| self.result.can_insert(item, count));
+        if !has_ingredients || !has_fluids || !has_space {
+            return;
+        }
+
+        for &(item, count) in recipe.ingredients {
+            self.source.extract(item, count);
+            stats.consume(item, count);
+        }
+        for &(_, amount) in recipe.fluid_ingredients {
+            self.water.drain(amount);
+        }
+        self.recipe = Some(recipe);
+        self.progress = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{
+            chest::Chest,
+            generator::Generator,
+            inserter::{Inserter, INSERTER_SWING_TICKS},
+            pole::Pole,
+            Entity,
+        },
+        grid::{Direction, Grid},
+    };
+
+    fn loaded(ore: u32, coal: u32) -> Furnace {
+        let mut furnace = Furnace::new();
+        for _ in 0..ore {
+            assert!(furnace.insert(Item::IronOre));
+        }
+        for _ in 0..coal {
+            assert!(furnace.insert(Item::Coal));
+        }
+        furnace
+    }
+
+    fn run(furnace: &mut Furnace, stats: &mut ProductionStats, ticks: u32) {
+        for _ in 0..ticks {
+            furnace.tick(stats);
+        }
+    }
+
+    #[test]
+    fn smelts_a_plate_after_the_recipe_ticks() {
+        let mut furnace = loaded(2, 1);
+        let mut stats = ProductionStats::new();
+        let ticks = recipe::smelting_recipe_for(Item::IronOre).unwrap().ticks;
+        run(&mut furnace, &mut stats, ticks - 1);
+        assert_eq!(furnace.result.count(Item::IronPlate), 0);
+        assert_eq!(furnace.source.count(Item::IronOre), 1);
+        run(&mut furnace, &mut stats, 1);
+        assert_eq!(furnace.result.count(Item::IronPlate), 1);
+        run(&mut furnace, &mut stats, ticks);
+        assert_eq!(furnace.result.count(Item::IronPlate), 2);
+        assert!(furnace.recipe.is_none());
+    }
+
+    #[test]
+    fn burns_one_fuel_item_at_a_time() {
+        let mut furnace = loaded(50, 2);
+        let mut stats = ProductionStats::new();
+        run(&mut furnace, &mut stats, 1);
+        assert_eq!(furnace.fuel.count(Item::Coal), 1);
+        assert_eq!(furnace.burn_ticks, Item::Coal.burn_ticks().unwrap() - 1);
+
+        // The next item is only taken once the burning one runs out
+        furnace.burn_ticks = 3;
+        run(&mut furnace, &mut stats, 3);
+        assert_eq!(furnace.fuel.count(Item::Coal), 1);
+        assert!(!furnace.is_burning());
+        run(&mut furnace, &mut stats, 1);
+        assert_eq!(furnace.fuel.count(Item::Coal), 0);
+        assert!(furnace.is_burning());
+    }
+
+    #[test]
+    fn slots_only_take_a_stack() {
+        let mut furnace = loaded(Item::IronOre.stack_size(), Item::Coal.stack_size());
+        assert!(!furnace.insert(Item::IronOre));
+        assert!(!furnace.insert(Item::Coal));
+        // The single source slot holds one kind of ore, and only fuel and ore go in
+        assert!(!furnace.insert(Item::CopperOre));
+        assert!(!furnace.insert(Item::Chest));
+    }
+
+    #[test]
+    fn stops_while_the_result_slot_is_full() {
+        let mut furnace = loaded(1, 1);
+        let mut stats = ProductionStats::new();
+        furnace
+            .result
+            .insert(Item::IronPlate, Item::IronPlate.stack_size());
+        run(&mut furnace, &mut stats, 10);
+        assert!(furnace.recipe.is_none());
+        assert_eq!(furnace.source.count(Item::IronOre), 1);
+        assert_eq!(furnace.fuel.count(Item::Coal), 1);
+
+        // Taking a plate out lets it carry on
+        furnace.result.extract(Item::IronPlate, 1);
+        run(&mut furnace, &mut stats, 1);
+        assert!(furnace.recipe.is_some());
+        assert_eq!(furnace.fuel.count(Item::Coal), 0);
+    }
+
+    #[test]
+    fn waits_for_water_when_the_recipe_needs_it() {
+        let mut furnace = Furnace::new();
+        let mut stats = ProductionStats::new();
+        furnace.insert(Item::Stone);
+        furnace.insert(Item::Stone);
+        furnace.insert(Item::Coal);
+        run(&mut furnace, &mut stats, 10);
+        assert!(furnace.recipe.is_none());
+        assert_eq!(furnace.source.count(Item::Stone), 2);
+        assert_eq!(furnace.fuel.count(Item::Coal), 1);
+
+        let recipe = recipe::smelting_recipe_for(Item::Stone).unwrap();
+        let (fluid, amount) = recipe.fluid_ingredients[0];
+        furnace.water.fill(fluid, amount);
+        run(&mut furnace, &mut stats, recipe.ticks);
+        assert_eq!(furnace.result.count(Item::StoneBrick), 1);
+        assert_eq!(furnace.water.amount, 0.0);
+    }
+
+    #[test]
+    fn inserters_load_furnaces_on_the_grid() {
+        let mut grid = Grid::new();
+        let mut chest = Chest::new();
+        chest.inventory.insert(Item::IronOre, 1);
+        chest.inventory.insert(Item::Coal, 1);
+        grid.place_entity(0, 0, Entity::Chest(chest));
+        grid.place_entity(0, 1, Entity::Inserter(Inserter::new(Direction::North)));
+        grid.place_entity(0, 2, Entity::Furnace(Furnace::new()));
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(1, 1, Entity::Pole(Pole::new()));
+        grid.place_entity(2, 1, Entity::Generator(generator));
+
+        for _ in 0..INSERTER_SWING_TICKS * 4 + 5 {
+            grid.tick();
+        }
+        match grid.get_entity(0, 2) {
+            Some(Entity::Furnace(furnace)) => {
+                assert!(furnace.recipe.is_some());
+                assert!(furnace.is_burning());
+            }
+            _ => panic!("no furnace"),
+        }
+    }
+}
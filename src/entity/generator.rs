@@ -0,0 +1,69 @@
+use crate::{
+    fluid::{Fluid, FluidBox},
+    inventory::Inventory,
+    item::Item,
+    stats::ProductionStats,
+};
+
+// Power output in kW while burning fuel or steam
+pub const GENERATOR_OUTPUT: u32 = 900;
+// Steam used each tick while running, what a single boiler makes
+pub const GENERATOR_STEAM_RATE: f32 = 1.0;
+
+// Powers the network of the pole it stands next to, running on steam piped in from
+// any side or otherwise burning fuel
+#[derive(Clone, Debug)]
+pub struct Generator {
+    pub fuel: Inventory,
+    pub burn_ticks: u32,
+    pub steam: FluidBox,
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Self {
+            fuel: Inventory::new(1),
+            burn_ticks: 0,
+            steam: FluidBox::filtered(200.0, Fluid::Steam),
+        }
+    }
+
+    pub fn insert(&mut self, item: Item) -> bool {
+        item.burn_ticks().is_some() && self.fuel.insert(item, 1) == 1
+    }
+
+    fn has_steam(&self) -> bool {
+        self.steam.amount >= GENERATOR_STEAM_RATE
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.burn_ticks > 0 || self.has_steam()
+    }
+
+    // Power that can be produced this tick without burning anything
+    pub fn available_output(&self) -> u32 {
+        if self.is_running() || !self.fuel.is_empty() {
+            GENERATOR_OUTPUT
+        } else {
+            0
+        }
+    }
+
+    // Uses steam or burns fuel for a tick, only called while the network needs power
+    pub fn burn(&mut self, stats: &mut ProductionStats) {
+        if self.has_steam() {
+            self.steam.drain(GENERATOR_STEAM_RATE);
+            return;
+        }
+        if self.burn_ticks == 0 {
+            match self.fuel.extract_any(1) {
+                Some(fuel) if fuel.count == 1 => {
+                    stats.consume(fuel.item, 1);
+                    self.burn_ticks = fuel.item.burn_ticks().unwrap();
+                }
+                _ => return,
+            }
+        }
+        self.burn_ticks -= 1;
+    }
+}
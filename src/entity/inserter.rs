@@ -0,0 +1,29 @@
+use crate::{circuit::CircuitControl, grid::Direction, item::Item};
+
+// Ticks spent swinging between picking up and dropping an item
+pub const INSERTER_SWING_TICKS: u32 = 35;
+
+// Picks up items from the tile behind it and drops them on the tile in front of it
+#[derive(Clone, Copy, Debug)]
+pub struct Inserter {
+    pub direction: Direction,
+    pub held: Option<Item>,
+    // Remaining swing, in ticks times FULL_SATISFACTION
+    pub cooldown: u32,
+    pub circuit: Option<CircuitControl>,
+}
+
+impl Inserter {
+    pub fn is_enabled(&self) -> bool {
+        self.circuit.map_or(true, |control| control.enabled)
+    }
+
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            held: None,
+            cooldown: 0,
+            circuit: None,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::inventory::Inventory;
+
+pub const LAB_SLOTS: usize = 4;
+
+// Consumes science packs to research the current technology
+#[derive(Clone, Debug)]
+pub struct Lab {
+    pub inventory: Inventory,
+    // Progress on the unit being researched in ticks times satisfaction, None while no
+    // unit is started
+    pub progress: Option<u32>,
+}
+
+impl Lab {
+    pub fn new() -> Self {
+        Self {
+            inventory: Inventory::new(LAB_SLOTS),
+            progress: None,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+pub mod boiler;
+pub mod chest;
+pub mod combinator;
+pub mod drill;
+pub mod furnace;
+pub mod generator;
+pub mod inserter;
+pub mod lab;
+pub mod offshore_pump;
+pub mod pipe;
+pub mod pole;
+pub mod pump;
+pub mod rail;
+pub mod roboport;
+pub mod tank;
+pub mod train_stop;
+
+use crate::{
+    circuit::{add_signal, Signal, Signals, Terminal},
+    fluid::{FlowDirection, FluidBox},
+    grid::{Belt, Direction},
+    inventory::ItemStack,
+    item::Item,
+    logistics::LogisticMode,
+};
+
+use self::{
+    boiler::Boiler,
+    chest::Chest,
+    combinator::{ArithmeticCombinator, ConstantCombinator, DeciderCombinator},
+    drill::Drill,
+    furnace::Furnace,
+    generator::Generator,
+    inserter::Inserter,
+    lab::Lab,
+    offshore_pump::OffshorePump,
+    pipe::Pipe,
+    pole::Pole,
+    pump::Pump,
+    rail::Rail,
+    roboport::Roboport,
+    tank::Tank,
+    train_stop::TrainStop,
+};
+
+#[derive(Clone, Debug)]
+pub enum Entity {
+    Belt(Belt),
+    Chest(Chest),
+    Inserter(Inserter),
+    Drill(Drill),
+    Furnace(Furnace),
+    Pole(Pole),
+    Generator(Generator),
+    Pipe(Pipe),
+    Pump(Pump),
+    Tank(Tank),
+    OffshorePump(OffshorePump),
+    Boiler(Boiler),
+    ConstantCombinator(ConstantCombinator),
+    ArithmeticCombinator(ArithmeticCombinator),
+    DeciderCombinator(DeciderCombinator),
+    Rail(Rail),
+    TrainStop(TrainStop),
+    Roboport(Roboport),
+    Lab(Lab),
+}
+
+impl Entity {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Belt(belt) => belt.kind_name(),
+            Self::Chest(ref chest) => match chest.logistic {
+                None => "chest",
+                Some(LogisticMode::Provider) => "provider-chest",
+                Some(LogisticMode::Requester(_)) => "requester-chest",
+                Some(LogisticMode::Storage) => "storage-chest",
+            },
+            Self::Inserter(_) => "inserter",
+            Self::Drill(_) => "drill",
+            Self::Furnace(_) => "furnace",
+            Self::Pole(_) => "pole",
+            Self::Generator(_) => "generator",
+            Self::Pipe(_) => "pipe",
+            Self::Pump(_) => "pump",
+            Self::Tank(_) => "tank",
+            Self::OffshorePump(_) => "offshore-pump",
+            Self::Boiler(_) => "boiler",
+            Self::ConstantCombinator(_) => "constant-combinator",
+            Self::ArithmeticCombinator(_) => "arithmetic-combinator",
+            Self::DeciderCombinator(_) => "decider-combinator",
+            Self::Rail(_) => "rail",
+            Self::TrainStop(_) => "train-stop",
+            Self::Roboport(_) => "roboport",
+            Self::Lab(_) => "lab",
+        }
+    }
+
+    // Power in kW drawn from the network at full speed
+    pub fn power_demand(&self) -> u32 {
+        match *self {
+            Self::Drill(_) => 90,
+            Self::Inserter(_) => 13,
+            Self::Pump(_) => 30,
+            Self::Lab(_) => 60,
+            _ => 0,
+        }
+    }
+
+    // The fluid box reachable through `side` and which way fluid may pass through it
+    pub fn fluid_connection(&self, side: Direction) -> Option<(usize, FlowDirection)> {
+        match self {
+            Self::Pipe(pipe) if pipe.is_connected(side) => Some((0, FlowDirection::Both)),
+            Self::Tank(_) => Some((0, FlowDirection::Both)),
+            Self::Pump(pump) if side == pump.direction => Some((0, FlowDirection::Output)),
+            Self::Pump(pump) if side == pump.direction.flip() => Some((0, FlowDirection::Input)),
+            Self::OffshorePump(pump) if side == pump.direction => Some((0, FlowDirection::Output)),
+            Self::Boiler(boiler) if side == boiler.direction => Some((1, FlowDirection::Output)),
+            Self::Boiler(boiler) if side == boiler.direction.flip() => {
+                Some((0, FlowDirection::Input))
+            }
+            Self::Generator(_) | Self::Furnace(_) => Some((0, FlowDirection::Input)),
+            _ => None,
+        }
+    }
+
+    pub fn fluid_boxes(&self) -> Vec<&FluidBox> {
+        match self {
+            Self::Pipe(pipe) => vec![&pipe.fluid_box],
+            Self::Tank(tank) => vec![&tank.fluid_box],
+            Self::Pump(pump) => vec![&pump.fluid_box],
+            Self::OffshorePump(pump) => vec![&pump.fluid_box],
+            Self::Boiler(boiler) => vec![&boiler.water, &boiler.steam],
+            Self::Generator(generator) => vec![&generator.steam],
+            Self::Furnace(furnace) => vec![&furnace.water],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn fluid_box_mut(&mut self, index: usize) -> Option<&mut FluidBox> {
+        match (self, index) {
+            (Self::Pipe(pipe), 0) => Some(&mut pipe.fluid_box),
+            (Self::Tank(tank), 0) => Some(&mut tank.fluid_box),
+            (Self::Pump(pump), 0) => Some(&mut pump.fluid_box),
+            (Self::OffshorePump(pump), 0) => Some(&mut pump.fluid_box),
+            (Self::Boiler(boiler), 0) => Some(&mut boiler.water),
+            (Self::Boiler(boiler), 1) => Some(&mut boiler.steam),
+            (Self::Generator(generator), 0) => Some(&mut generator.steam),
+            (Self::Furnace(furnace), 0) => Some(&mut furnace.water),
+            _ => None,
+        }
+    }
+
+    // Items held by the entity, including ingredients taken for a recipe in progress
+    pub fn contents(&self) -> Vec<ItemStack> {
+        let single = |item: Option<Item>| item.map(|item| ItemStack::new(item, 1)).into_iter();
+        match self {
+            Self::Belt(belt) => belt
+                .lanes
+                .iter()
+                .flat_map(|lane| lane.items())
+                .map(|lane_item| ItemStack::new(lane_item.item, 1))
+                .collect(),
+            Self::Chest(chest) => chest.inventory.contents(),
+            Self::Inserter(inserter) => single(inserter.held).collect(),
+            Self::Drill(drill) => single(drill.output_buffer).collect(),
+            Self::Furnace(furnace) => {
+                let mut contents = furnace.fuel.contents();
+                contents.extend(furnace.source.contents());
+                contents.extend(furnace.result.contents());
+                if let Some(recipe) = furnace.recipe {
+                    contents.extend(
+                        recipe
+                            .ingredients
+                            .iter()
+                            .map(|&(item, count)| ItemStack::new(item, count)),
+                    );
+                }
+                contents
+            }
+            Self::Generator(generator) => generator.fuel.contents(),
+            Self::Boiler(boiler) => boiler.fuel.contents(),
+            Self::Lab(lab) => lab.inventory.contents(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Whether circuit wires can be attached to `terminal`
+    pub fn has_terminal(&self, terminal: Terminal) -> bool {
+        match self {
+            Self::ArithmeticCombinator(_) | Self::DeciderCombinator(_) => true,
+            Self::Belt(_) | Self::Inserter(_) | Self::Chest(_) | Self::ConstantCombinator(_) => {
+                terminal == Terminal::Input
+            }
+            _ => false,
+        }
+    }
+
+    // Signals the entity puts on the networks connected to `terminal`
+    pub fn circuit_output(&self, terminal: Terminal) -> Signals {
+        let mut signals = Signals::new();
+        match (self, terminal) {
+            (Self::ConstantCombinator(combinator), Terminal::Input) => {
+                signals = combinator.signals.clone()
+            }
+            (Self::ArithmeticCombinator(combinator), Terminal::Output) => {
+                signals = combinator.output.clone()
+            }
+            (Self::DeciderCombinator(combinator), Terminal::Output) => {
+                signals = combinator.output.clone()
+            }
+            (Self::Chest(chest), Terminal::Input) => {
+                for stack in chest.inventory.contents() {
+                    add_signal(&mut signals, Signal::Item(stack.item), stack.count as i32);
+                }
+            }
+            (Self::Belt(belt), Terminal::Input)
+                if belt.circuit.is_some_and(|control| control.read_contents) =>
+            {
+                for lane_item in belt.lanes.iter().flat_map(|lane| lane.items()) {
+                    add_signal(&mut signals, Signal::Item(lane_item.item), 1);
+                }
+            }
+            _ => (),
+        }
+        signals
+    }
+
+    // Whether a pipe placed next to this entity on `side` should connect to it
+    pub fn accepts_pipe(&self, side: Direction) -> bool {
+        match self {
+            Self::Pipe(_) => true,
+            _ => self.fluid_connection(side).is_some(),
+        }
+    }
+}
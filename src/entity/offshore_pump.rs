@@ -0,0 +1,27 @@
+use crate::{
+    fluid::{Fluid, FluidBox},
+    grid::Direction,
+};
+
+// Water produced each tick
+pub const OFFSHORE_PUMP_RATE: f32 = 20.0;
+
+// Source of water, outputs in `direction`
+#[derive(Clone, Copy, Debug)]
+pub struct OffshorePump {
+    pub direction: Direction,
+    pub fluid_box: FluidBox,
+}
+
+impl OffshorePump {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            fluid_box: FluidBox::new(100.0),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.fluid_box.fill(Fluid::Water, OFFSHORE_PUMP_RATE);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{fluid::FluidBox, grid::Direction};
+
+pub const PIPE_CAPACITY: f32 = 100.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Pipe {
+    pub fluid_box: FluidBox,
+    // Sides connected to a neighbouring fluid entity, indexed by `Direction::index`
+    pub connections: [bool; 4],
+}
+
+impl Pipe {
+    pub fn new() -> Self {
+        Self {
+            fluid_box: FluidBox::new(PIPE_CAPACITY),
+            connections: [false; 4],
+        }
+    }
+
+    pub fn is_connected(&self, side: Direction) -> bool {
+        self.connections[side.index()]
+    }
+}
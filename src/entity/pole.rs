@@ -0,0 +1,9 @@
+// Connects to other poles by wire and supplies power to the tiles around it
+#[derive(Clone, Copy, Debug)]
+pub struct Pole;
+
+impl Pole {
+    pub fn new() -> Self {
+        Self
+    }
+}
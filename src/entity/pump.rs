@@ -0,0 +1,21 @@
+use crate::{fluid::FluidBox, grid::Direction};
+
+pub const PUMP_CAPACITY: f32 = 100.0;
+// Fluid pushed out of the front each tick at full power
+pub const PUMP_RATE: f32 = 20.0;
+
+// Takes fluid in from behind and actively pushes it out in `direction`
+#[derive(Clone, Copy, Debug)]
+pub struct Pump {
+    pub direction: Direction,
+    pub fluid_box: FluidBox,
+}
+
+impl Pump {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            fluid_box: FluidBox::new(PUMP_CAPACITY),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::grid::Direction;
+
+// Track on a tile, every piece joining two of its sides. Opposite sides make a straight
+// piece and neighbouring sides a curve, several pieces on one tile form a junction.
+#[derive(Clone, Debug)]
+pub struct Rail {
+    pub pieces: Vec<(Direction, Direction)>,
+}
+
+impl Rail {
+    pub fn new(from: Direction, to: Direction) -> Self {
+        Self {
+            pieces: vec![(from, to)],
+        }
+    }
+
+    pub fn straight(direction: Direction) -> Self {
+        Self::new(direction.flip(), direction)
+    }
+
+    // Curve entered from behind `direction` that turns right
+    pub fn curve(direction: Direction) -> Self {
+        Self::new(direction.flip(), direction.rotate_clockwise())
+    }
+
+    // Adds the pieces of another rail, returns whether any were new
+    pub fn merge(&mut self, other: &Rail) -> bool {
+        let mut added = false;
+        for &(from, to) in other.pieces.iter() {
+            if from != to
+                && !self.pieces.contains(&(from, to))
+                && !self.pieces.contains(&(to, from))
+            {
+                self.pieces.push((from, to));
+                added = true;
+            }
+        }
+        added
+    }
+
+    pub fn has_side(&self, side: Direction) -> bool {
+        self.pieces
+            .iter()
+            .any(|&(from, to)| from == side || to == side)
+    }
+
+    // Sides a train entering through `entry` can leave through
+    pub fn exits(&self, entry: Direction) -> Vec<Direction> {
+        self.pieces
+            .iter()
+            .filter_map(|&(from, to)| {
+                if from == entry {
+                    Some(to)
+                } else if to == entry {
+                    Some(from)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
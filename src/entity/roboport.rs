@@ -0,0 +1,9 @@
+// Docks and charges logistic robots, which serve the logistic chests in its range
+#[derive(Clone, Copy, Debug)]
+pub struct Roboport;
+
+impl Roboport {
+    pub fn new() -> Self {
+        Self
+    }
+}
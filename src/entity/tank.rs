@@ -0,0 +1,16 @@
+use crate::fluid::FluidBox;
+
+pub const TANK_CAPACITY: f32 = 25000.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tank {
+    pub fluid_box: FluidBox,
+}
+
+impl Tank {
+    pub fn new() -> Self {
+        Self {
+            fluid_box: FluidBox::new(TANK_CAPACITY),
+        }
+    }
+}
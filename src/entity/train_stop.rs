@@ -0,0 +1,17 @@
+use crate::grid::Direction;
+
+// Named station next to a rail, trains stop on the rail tile in `rail` direction
+#[derive(Clone, Debug)]
+pub struct TrainStop {
+    pub name: String,
+    pub rail: Direction,
+}
+
+impl TrainStop {
+    pub fn new(name: &str, rail: Direction) -> Self {
+        Self {
+            name: name.to_string(),
+            rail,
+        }
+    }
+}
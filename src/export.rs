@@ -0,0 +1,220 @@
+use cgmath::{vec4, Vector4};
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    camera::TileRect,
+    entity::Entity,
+    grid::Grid,
+    renderer::{
+        atlas::{Atlas, AtlasBuilder},
+        rasterizer::draw_sprites,
+    },
+    sprites::{belt_item_sprites, belt_sprite, item_color, BeltAnimations},
+};
+
+// Same as the clear colour of the window
+const GROUND_COLOR: Vector4<f32> = vec4(0.3, 0.3, 0.6, 1.0);
+
+// What to export, read from `export <file> [--seed N] [--scale N] [--region x y w h]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportOptions {
+    pub path: String,
+    pub seed: Option<u64>,
+    // Pixels per tile
+    pub scale: u32,
+    // The whole grid when None
+    pub region: Option<TileRect>,
+}
+
+impl ExportOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let path = match args.next() {
+            Some(path) if !path.starts_with("--") => path.clone(),
+            _ => return Err("expected the file to write".to_string()),
+        };
+        let mut options = Self {
+            path,
+            seed: None,
+            scale: 32,
+            region: None,
+        };
+        while let Some(flag) = args.next() {
+            let mut number = || {
+                args.next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .ok_or(format!("{} expects a number", flag))
+            };
+            match flag.as_str() {
+                "--seed" => options.seed = Some(number()?),
+                "--scale" => match number()? {
+                    0 => return Err("--scale must be at least 1".to_string()),
+                    scale => options.scale = scale as u32,
+                },
+                "--region" => {
+                    let (x, y, width, height) = (number()?, number()?, number()?, number()?);
+                    if width == 0 || height == 0 {
+                        return Err("--region must not be empty".to_string());
+                    }
+                    options.region = Some(TileRect {
+                        min_x: x as usize,
+                        min_y: y as usize,
+                        max_x: (x + width) as usize,
+                        max_y: (y + height) as usize,
+                    });
+                }
+                _ => return Err(format!("unknown option {}", flag)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+// Image of the tiles in `region` at `scale` pixels per tile, with the belts and their
+// items drawn from the atlas the way the game draws them
+pub fn render(
+    grid: &Grid,
+    region: TileRect,
+    scale: u32,
+    atlas_image: &RgbaImage,
+    atlas: &Atlas,
+) -> RgbaImage {
+    let columns = region
+        .max_x
+        .min(grid.tiles[0].len())
+        .saturating_sub(region.min_x);
+    let rows = region
+        .max_y
+        .min(grid.tiles.len())
+        .saturating_sub(region.min_y);
+    let height = rows as u32 * scale;
+    let mut image = RgbaImage::new(columns as u32 * scale, height);
+
+    // The image has its top row first while tiles go up
+    let to_rgba = |color: Vector4<f32>| {
+        Rgba([color.x, color.y, color.z, color.w].map(|channel| (channel * 255.0).round() as u8))
+    };
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let tile_x = region.min_x + (x / scale) as usize;
+        let tile_y = region.min_y + ((height - 1 - y) / scale) as usize;
+        *pixel = match grid.resources[tile_y][tile_x] {
+            Some(resource) => to_rgba(item_color(resource.item)),
+            None => to_rgba(GROUND_COLOR),
+        };
+    }
+
+    let animations = BeltAnimations::new(atlas);
+    let item_uv = atlas.sprite("item");
+    let mut belts = Vec::new();
+    let mut items = Vec::new();
+    for y in region.min_y..region.min_y + rows {
+        for x in region.min_x..region.min_x + columns {
+            if let Some(Entity::Belt(belt)) = &grid.tiles[y][x] {
+                let white = vec4(1.0, 1.0, 1.0, 1.0);
+                belts.push(belt_sprite(
+                    &animations,
+                    grid.ticks,
+                    x as isize,
+                    y as isize,
+                    belt,
+                    white,
+                ));
+                items.extend(belt_item_sprites(belt, x, y, item_uv, 1.0));
+            }
+        }
+    }
+    // Items go on top of every belt, like in the game
+    belts.extend(items);
+    let origin = (32.0 * region.min_x as f32, 32.0 * region.min_y as f32);
+    draw_sprites(&mut image, atlas_image, &belts, origin, scale as f32 / 32.0);
+    image
+}
+
+// Renders with the textures in assets/ and writes the image, its format going by the
+// file extension
+pub fn export(grid: &Grid, region: TileRect, scale: u32, path: &str) -> Result<(), String> {
+    let mut builder = AtlasBuilder::new();
+    builder.add_directory("textures");
+    let (atlas_image, atlas) = builder.build();
+    render(grid, region, scale, &atlas_image, &atlas)
+        .save(path)
+        .map_err(|error| format!("Failed to write {}: {}", path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid::{Belt, Direction, Resource},
+        item::Item,
+    };
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_command_line() {
+        let options = ExportOptions::parse(&args("map.png --scale 8 --region 2 3 10 5")).unwrap();
+        assert_eq!(options.path, "map.png");
+        assert_eq!(options.scale, 8);
+        assert_eq!(
+            options.region,
+            Some(TileRect {
+                min_x: 2,
+                min_y: 3,
+                max_x: 12,
+                max_y: 8
+            })
+        );
+        assert_eq!(ExportOptions::parse(&args("map.png")).unwrap().seed, None);
+        assert!(ExportOptions::parse(&args("--seed 3")).is_err());
+        assert!(ExportOptions::parse(&args("map.png --scale big")).is_err());
+        assert!(ExportOptions::parse(&args("map.png --region 1 2 3")).is_err());
+    }
+
+    #[test]
+    fn renders_belts_over_the_ground() {
+        let mut grid = Grid::new();
+        grid.resources[0][1] = Some(Resource {
+            item: Item::Coal,
+            amount: 100,
+        });
+        grid.place_belt(0, 0, Belt::straight(Direction::East));
+
+        // Straight belts are green on the left, red at the top right and blue at the
+        // bottom right
+        let (green, red, blue) = (
+            Rgba([0, 255, 0, 255]),
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 0, 255, 255]),
+        );
+        let mut builder = AtlasBuilder::new();
+        for name in ["belt-left-0", "belt-right-0", "item"] {
+            builder.add(name, RgbaImage::new(16, 16));
+        }
+        builder.add(
+            "belt-straight-0",
+            RgbaImage::from_fn(16, 16, |x, y| match (x < 8, y < 8) {
+                (true, _) => green,
+                (false, true) => red,
+                (false, false) => blue,
+            }),
+        );
+        let (atlas_image, atlas) = builder.build();
+        let region = TileRect {
+            min_x: 0,
+            min_y: 0,
+            max_x: 2,
+            max_y: 1,
+        };
+
+        let image = render(&grid, region, 16, &atlas_image, &atlas);
+        assert_eq!((image.width(), image.height()), (32, 16));
+        assert_eq!(image.get_pixel(24, 8), &Rgba([26, 26, 26, 255]));
+        // Belts coming in from the west get a quarter turn, like in the game
+        assert_eq!(image.get_pixel(0, 0), &green);
+        assert_eq!(image.get_pixel(0, 15), &blue);
+        assert_eq!(image.get_pixel(15, 15), &red);
+    }
+}
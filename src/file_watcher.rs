@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+// Notices files in a directory being added or saved by polling their modification
+// times. Files that can't be read are skipped, so it never fails.
+pub struct FileWatcher {
+    directory: PathBuf,
+    interval: Duration,
+    last_poll: Instant,
+    modified: HashMap<String, SystemTime>,
+}
+
+impl FileWatcher {
+    pub fn new(directory: &str, interval: Duration) -> Self {
+        let mut watcher = Self {
+            directory: PathBuf::from(directory),
+            interval,
+            last_poll: Instant::now(),
+            modified: HashMap::new(),
+        };
+        watcher.modified = watcher.scan();
+        watcher
+    }
+
+    // Names of the files changed since the last poll, polls at most once per interval
+    pub fn changed(&mut self) -> Vec<String> {
+        if self.last_poll.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let modified = self.scan();
+        let mut changed: Vec<String> = modified
+            .iter()
+            .filter(|&(name, time)| self.modified.get(name) != Some(time))
+            .map(|(name, _)| name.clone())
+            .collect();
+        changed.sort();
+        self.modified = modified;
+        changed
+    }
+
+    fn scan(&self) -> HashMap<String, SystemTime> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return HashMap::new(),
+        };
+        entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((entry.file_name().to_string_lossy().into_owned(), modified))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn reports_saved_and_added_files() {
+        let directory = std::env::temp_dir().join(format!("watcher-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("a.vert"), "old").unwrap();
+        let mut watcher = FileWatcher::new(directory.to_str().unwrap(), Duration::ZERO);
+        assert!(watcher.changed().is_empty());
+
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(directory.join("a.vert"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        fs::write(directory.join("b.frag"), "new").unwrap();
+        assert_eq!(watcher.changed(), vec!["a.vert", "b.frag"]);
+        assert!(watcher.changed().is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(watcher.changed().is_empty());
+    }
+}
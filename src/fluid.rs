@@ -0,0 +1,287 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fluid {
+    Water,
+    Steam,
+}
+
+impl Fluid {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Water => "water",
+            Self::Steam => "steam",
+        }
+    }
+}
+
+// Which way fluid may pass through a connection, seen from the entity owning it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlowDirection {
+    Input,
+    Output,
+    Both,
+}
+
+// Fraction of the difference in fullness that is evened out between two boxes each tick
+const FLOW_FACTOR: f32 = 0.5;
+// Most fluid that can pass through a single connection each tick
+pub const MAX_FLOW: f32 = 100.0;
+// Boxes holding less than this are considered empty
+const EMPTY_EPSILON: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FluidBox {
+    pub fluid: Option<Fluid>,
+    pub amount: f32,
+    pub capacity: f32,
+    // The only fluid machines let into the box, any fluid when None
+    pub filter: Option<Fluid>,
+}
+
+impl FluidBox {
+    pub fn new(capacity: f32) -> Self {
+        Self {
+            fluid: None,
+            amount: 0.0,
+            capacity,
+            filter: None,
+        }
+    }
+
+    pub fn filtered(capacity: f32, fluid: Fluid) -> Self {
+        Self {
+            filter: Some(fluid),
+            ..Self::new(capacity)
+        }
+    }
+
+    pub fn space(&self) -> f32 {
+        self.capacity - self.amount
+    }
+
+    pub fn can_hold(&self, fluid: Fluid) -> bool {
+        (self.filter.is_none() || self.filter == Some(fluid))
+            && (self.fluid.is_none() || self.fluid == Some(fluid))
+    }
+
+    // Adds up to `amount` of fluid, returns how much was added
+    pub fn fill(&mut self, fluid: Fluid, amount: f32) -> f32 {
+        if !self.can_hold(fluid) {
+            return 0.0;
+        }
+
+        let added = amount.min(self.space()).max(0.0);
+        if added > 0.0 {
+            self.fluid = Some(fluid);
+            self.amount += added;
+        }
+        added
+    }
+
+    // Removes up to `amount` of fluid, returns how much was removed
+    pub fn drain(&mut self, amount: f32) -> f32 {
+        let removed = amount.min(self.amount).max(0.0);
+        self.amount -= removed;
+        if self.amount < EMPTY_EPSILON {
+            self.amount = 0.0;
+            self.fluid = None;
+        }
+        removed
+    }
+
+    // Amount that passively flows from `from` to `to` in a tick, negative when flowing back
+    pub fn flow(from: &FluidBox, to: &FluidBox) -> f32 {
+        match (from.fluid, to.fluid) {
+            (Some(a), Some(b)) if a != b => return 0.0,
+            (Some(fluid), None) if !to.can_hold(fluid) => return 0.0,
+            (None, Some(fluid)) if !from.can_hold(fluid) => return 0.0,
+            (None, None) => return 0.0,
+            _ => (),
+        }
+
+        // Moving this much would leave both boxes equally full
+        let balance =
+            (from.amount * to.capacity - to.amount * from.capacity) / (from.capacity + to.capacity);
+        (balance * FLOW_FACTOR).clamp(-MAX_FLOW, MAX_FLOW)
+    }
+
+    // Moves `amount` from one box to the other, returns how much was moved
+    pub fn transfer(from: &mut FluidBox, to: &mut FluidBox, amount: f32) -> f32 {
+        let fluid = match from.fluid {
+            Some(fluid) => fluid,
+            None => return 0.0,
+        };
+        let moved = to.fill(fluid, amount.min(from.amount));
+        from.drain(moved);
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{
+            boiler::Boiler, chest::Chest, furnace::Furnace, generator::Generator, lab::Lab,
+            offshore_pump::OffshorePump, pipe::Pipe, pole::Pole, pump::Pump, tank::Tank, Entity,
+        },
+        grid::{Direction, Grid},
+        item::Item,
+        power::FULL_SATISFACTION,
+    };
+
+    fn run(grid: &mut Grid, ticks: u32) {
+        for _ in 0..ticks {
+            grid.tick();
+        }
+    }
+
+    fn amount(grid: &Grid, x: isize, y: isize) -> f32 {
+        grid.fluid_box(x, y, 0).unwrap().amount
+    }
+
+    fn fill(grid: &mut Grid, x: isize, y: isize, amount: f32) {
+        let fluid_box = grid.get_entity_mut(x, y).unwrap().fluid_box_mut(0).unwrap();
+        fluid_box.fill(Fluid::Water, amount);
+    }
+
+    fn pipe(grid: &Grid, x: isize, y: isize) -> Pipe {
+        match grid.get_entity(x, y) {
+            Some(Entity::Pipe(pipe)) => *pipe,
+            _ => panic!("no pipe"),
+        }
+    }
+
+    #[test]
+    fn pipes_connect_to_fluid_neighbours() {
+        let mut grid = Grid::new();
+        grid.place_entity(1, 1, Entity::Pipe(Pipe::new()));
+        grid.place_entity(2, 1, Entity::Pipe(Pipe::new()));
+        grid.place_entity(1, 2, Entity::Pipe(Pipe::new()));
+        grid.place_entity(0, 1, Entity::Tank(Tank::new()));
+        grid.place_entity(1, 0, Entity::Chest(Chest::new()));
+        assert_eq!(pipe(&grid, 1, 1).connections, [true, true, true, false]);
+        assert_eq!(pipe(&grid, 2, 1).connections, [true, false, false, false]);
+
+        grid.clear_tile(2, 1);
+        assert!(!pipe(&grid, 1, 1).is_connected(Direction::East));
+
+        // Pumps only connect at their front and back
+        grid.place_entity(2, 1, Entity::Pump(Pump::new(Direction::North)));
+        assert!(!pipe(&grid, 1, 1).is_connected(Direction::East));
+        grid.place_entity(2, 0, Entity::Pipe(Pipe::new()));
+        assert!(pipe(&grid, 2, 0).is_connected(Direction::North));
+    }
+
+    #[test]
+    fn fluid_evens_out_along_pipes() {
+        let mut grid = Grid::new();
+        for x in 0..5 {
+            grid.place_entity(x, 0, Entity::Pipe(Pipe::new()));
+        }
+        fill(&mut grid, 0, 0, 100.0);
+
+        run(&mut grid, 1);
+        assert!(amount(&grid, 0, 0) < 100.0 && amount(&grid, 1, 0) > 0.0);
+        run(&mut grid, 200);
+        let amounts: Vec<f32> = (0..5).map(|x| amount(&grid, x, 0)).collect();
+        assert!((amounts.iter().sum::<f32>() - 100.0).abs() < 0.01);
+        assert!(amounts.iter().all(|amount| (amount - 20.0).abs() < 0.5));
+    }
+
+    #[test]
+    fn pumps_push_fluid_one_way() {
+        let mut grid = Grid::new();
+        for y in [0, 2] {
+            grid.place_entity(0, y, Entity::Pipe(Pipe::new()));
+            grid.place_entity(2, y, Entity::Pipe(Pipe::new()));
+            fill(&mut grid, 0, y, 100.0);
+        }
+        grid.place_entity(1, 0, Entity::Pump(Pump::new(Direction::East)));
+        grid.place_entity(1, 2, Entity::Pump(Pump::new(Direction::West)));
+        // Pumps run on electricity
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(1, 3, Entity::Generator(generator));
+        grid.place_entity(1, 1, Entity::Pole(Pole::new()));
+
+        run(&mut grid, 100);
+        assert!(amount(&grid, 0, 0) < 1.0);
+        assert!(amount(&grid, 2, 0) > 90.0);
+        assert_eq!(amount(&grid, 0, 2), 100.0);
+        assert_eq!(amount(&grid, 2, 2), 0.0);
+    }
+
+    #[test]
+    fn tanks_store_what_pipes_bring() {
+        let mut grid = Grid::new();
+        grid.place_entity(
+            0,
+            0,
+            Entity::OffshorePump(OffshorePump::new(Direction::East)),
+        );
+        grid.place_entity(1, 0, Entity::Pipe(Pipe::new()));
+        grid.place_entity(2, 0, Entity::Tank(Tank::new()));
+
+        run(&mut grid, 100);
+        let tank = amount(&grid, 2, 0);
+        assert!(tank > 1000.0);
+        // Tanks fill up proportionally to their size, so they take most of the water
+        assert!(tank > 10.0 * amount(&grid, 1, 0));
+        run(&mut grid, 10);
+        assert!(amount(&grid, 2, 0) > tank);
+    }
+
+    #[test]
+    fn boilers_feed_steam_to_generators() {
+        let mut grid = Grid::new();
+        grid.place_entity(
+            0,
+            5,
+            Entity::OffshorePump(OffshorePump::new(Direction::East)),
+        );
+        grid.place_entity(1, 5, Entity::Boiler(Boiler::new(Direction::East)));
+        grid.drop_item(1, 5, Direction::East, Item::Coal);
+        grid.place_entity(2, 5, Entity::Generator(Generator::new()));
+        // Water is kept out of the generator
+        grid.place_entity(
+            2,
+            6,
+            Entity::OffshorePump(OffshorePump::new(Direction::South)),
+        );
+        grid.place_entity(3, 5, Entity::Pole(Pole::new()));
+        grid.place_entity(3, 6, Entity::Lab(Lab::new()));
+
+        run(&mut grid, 100);
+        let steam = grid.fluid_box(2, 5, 0).unwrap();
+        assert_eq!(steam.fluid, Some(Fluid::Steam));
+        assert_eq!(grid.power.satisfaction_at(3, 6), FULL_SATISFACTION);
+        match grid.get_entity(2, 5) {
+            Some(Entity::Generator(generator)) => assert_eq!(generator.burn_ticks, 0),
+            _ => panic!("no generator"),
+        }
+    }
+
+    #[test]
+    fn furnaces_smelt_with_piped_water() {
+        let mut grid = Grid::new();
+        grid.place_entity(
+            0,
+            0,
+            Entity::OffshorePump(OffshorePump::new(Direction::East)),
+        );
+        grid.place_entity(1, 0, Entity::Pipe(Pipe::new()));
+        grid.place_entity(2, 0, Entity::Furnace(Furnace::new()));
+        for item in [Item::Stone, Item::Stone, Item::Coal].iter() {
+            assert!(grid.drop_item(2, 0, Direction::East, *item));
+        }
+        assert!(pipe(&grid, 1, 0).is_connected(Direction::East));
+
+        run(&mut grid, 200);
+        match grid.get_entity(2, 0) {
+            Some(Entity::Furnace(furnace)) => {
+                assert_eq!(furnace.result.count(Item::StoneBrick), 1)
+            }
+            _ => panic!("no furnace"),
+        }
+    }
+}
@@ -1,23 +1,127 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    circuit::{add_signals, CircuitControl, CircuitGrid, Port, Terminal, WireColor},
+    entity::{
+        drill::DRILL_MINING_TICKS, inserter::Inserter, inserter::INSERTER_SWING_TICKS,
+        pump::PUMP_RATE, rail::Rail, Entity,
+    },
+    fluid::{FlowDirection, FluidBox},
+    inventory::ItemStack,
+    item::Item,
+    logistics::{self, Delivery, LogisticGrid, Robot, ROBOPORT_ROBOTS},
+    player::{build_cost, Player},
+    power::{PowerGrid, FULL_SATISFACTION},
+    railway::{self, Car, Railway, ScheduleEntry, Train, TrainState, TRAIN_STEP_TICKS},
+    research::{Research, TechTree},
+    stats::ProductionStats,
+};
+
+// Length of a belt lane in sub-tile units
+pub const LANE_LENGTH: u32 = 256;
+// Minimum distance between two items on the same lane
+pub const ITEM_SPACING: u32 = 64;
+pub const LANE_CAPACITY: usize = (LANE_LENGTH / ITEM_SPACING) as usize;
+// Distance items travel along a lane each tick
+pub const BELT_SPEED: u32 = 8;
+// Furthest an underground exit can be from its entrance, in tiles
+pub const MAX_UNDERGROUND_DISTANCE: isize = 5;
+
+// Ore lying on a tile, below whatever entity is placed there
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resource {
+    pub item: Item,
+    pub amount: u32,
+}
+
 pub struct Grid {
-    pub tiles: [[Option<Belt>; 128]; 128],
+    pub tiles: Vec<Vec<Option<Entity>>>,
+    pub resources: Vec<Vec<Option<Resource>>>,
+    pub power: PowerGrid,
+    pub stats: ProductionStats,
+    pub circuit: CircuitGrid,
+    pub railway: Railway,
+    pub logistics: LogisticGrid,
+    pub research: Research,
+    pub player: Player,
+    // Ticks simulated so far
+    pub ticks: u64,
+    // Tiles built on, cleared or mined out since they were last taken
+    changed_tiles: HashSet<(usize, usize)>,
 }
 
 impl Grid {
     pub fn new() -> Self {
         Self {
-            tiles: [[None; 128]; 128],
+            tiles: vec![vec![None; 128]; 128],
+            resources: vec![vec![None; 128]; 128],
+            power: PowerGrid::new(128, 128),
+            stats: ProductionStats::new(),
+            circuit: CircuitGrid::new(),
+            railway: Railway::new(),
+            logistics: LogisticGrid::new(128, 128),
+            research: Research::new(TechTree::empty()),
+            // Builds for free until the game hands out a starting inventory
+            player: Player::creative(),
+            ticks: 0,
+            changed_tiles: HashSet::new(),
         }
     }
 
-    pub fn place_belt(&mut self, x: isize, y: isize, belt: Belt) {
-        let belt = self.calculate_belt_position(x, y, belt);
+    pub fn tick(&mut self) {
+        self.update_circuits();
+        self.update_power();
+        self.update_drills();
+        self.update_furnaces();
+        self.update_labs();
+        self.update_fluids();
+        self.update_belts();
+        self.update_trains();
+        self.update_robots();
+        self.update_inserters();
+        self.player.update_crafting(&mut self.stats);
+        self.stats.end_tick();
+        self.ticks += 1;
+    }
+
+    // Places or replaces a belt, returns whether it was placed
+    pub fn place_belt(&mut self, x: isize, y: isize, belt: Belt) -> bool {
+        if !self.in_bounds(x, y) || !self.is_unlocked(&Entity::Belt(belt)) {
+            return false;
+        }
+        let mut belt = self.calculate_belt_position(x, y, belt);
+        let mut replaced = None;
+        match self.get_entity(x, y) {
+            Some(Entity::Belt(existing)) if existing.splitter.is_none() => {
+                belt.lanes = existing.lanes;
+                belt.circuit = existing.circuit;
+                replaced = Some(*existing);
+            }
+            Some(_) => return false,
+            None => (),
+        }
+        // Turning a belt is free, replacing it with another kind swaps the items
+        if replaced.map(|existing| existing.kind_name()) != Some(belt.kind_name()) {
+            if !self.player.take_items(&build_cost(&Entity::Belt(belt))) {
+                return false;
+            }
+            if let Some(existing) = replaced {
+                self.player.give_items(&build_cost(&Entity::Belt(existing)));
+            }
+        }
+        // The tile in front of an underground entrance is above the tunnel
+        if belt.underground == Some(Underground::Entrance) {
+            self.set_belt(x, y, belt);
+            return true;
+        }
         // Adjust input of belt in front
         // - -
         //   |
         // front belt direction west/east
         // current belt direction north
-        if let (Some(mut front_belt), (front_belt_x, front_belt_y)) =
-            self.belt_in_front_of(x, y, belt)
+        let (front_belt, (front_belt_x, front_belt_y)) = self.belt_in_front_of(x, y, belt);
+        // Underground belts and splitters always stay straight
+        if let Some(mut front_belt) = front_belt.filter(|front_belt| !front_belt.is_straight_only())
         {
             if (front_belt.input == belt.output.rotate_clockwise()
                 || front_belt.input == belt.output.rotate_anti_clockwise())
@@ -42,9 +146,25 @@ impl Grid {
         }
 
         self.set_belt(x, y, belt);
+        true
+    }
+
+    // Whether research allows placing the entity, belts also need their tier
+    pub fn is_unlocked(&self, entity: &Entity) -> bool {
+        match entity {
+            Entity::Belt(belt) => {
+                self.research.is_unlocked(belt.tier.name())
+                    && self.research.is_unlocked(belt.kind_name())
+            }
+            _ => self.research.is_unlocked(entity.name()),
+        }
     }
 
     pub fn calculate_belt_position(&self, x: isize, y: isize, mut belt: Belt) -> Belt {
+        if belt.is_straight_only() {
+            return belt;
+        }
+
         let (belt_behind, _) = self.belt_behind(x, y, belt);
         if let Some(belt_behind) = belt_behind {
             if belt_behind.output == belt.input.flip() {
@@ -54,10 +174,7 @@ impl Grid {
 
         let (left_belt, _) = self.belt_left_of(x, y, belt);
         let (right_belt, _) = self.belt_right_of(x, y, belt);
-        if left_belt.is_some() && right_belt.is_some() {
-            let left_belt = left_belt.unwrap();
-            let right_belt = right_belt.unwrap();
-
+        if let (Some(left_belt), Some(right_belt)) = (left_belt, right_belt) {
             let left_belt_facing_into = left_belt.output == belt.output.rotate_clockwise();
             let right_belt_facing_into = right_belt.output == belt.output.rotate_anti_clockwise();
 
@@ -83,21 +200,1055 @@ impl Grid {
         belt
     }
 
-    pub fn clear_tile(&mut self, x: usize, y: usize) {
-        self.tiles[y][x] = None;
+    // Places a splitter with its left half on (x, y) and its right half next to it,
+    // returns whether both tiles were empty
+    pub fn place_splitter(&mut self, x: isize, y: isize, direction: Direction) -> bool {
+        if !self.is_unlocked(&Entity::Belt(Belt::splitter(direction, SplitterSide::Left))) {
+            return false;
+        }
+        let (right_x, right_y) = direction.rotate_clockwise().offset(x, y);
+        for (x, y) in [(x, y), (right_x, right_y)] {
+            if !self.in_bounds(x, y) || self.get_entity(x, y).is_some() {
+                return false;
+            }
+        }
+        if !self.player.take_items(&[ItemStack::new(Item::Splitter, 1)]) {
+            return false;
+        }
+
+        self.set_belt(x, y, Belt::splitter(direction, SplitterSide::Left));
+        self.set_belt(
+            right_x,
+            right_y,
+            Belt::splitter(direction, SplitterSide::Right),
+        );
+        true
     }
 
-    pub fn get_belt(&self, x: isize, y: isize) -> Option<Belt> {
-        if x >= 0 && x < self.tiles[0].len() as isize && y >= 0 && y < self.tiles.len() as isize {
-            self.tiles[y as usize][x as usize]
+    // Places a non-belt entity on an empty tile, returns whether it was placed
+    pub fn place_entity(&mut self, x: isize, y: isize, entity: Entity) -> bool {
+        if !self.in_bounds(x, y)
+            || self.get_entity(x, y).is_some()
+            || !self.is_unlocked(&entity)
+            || !self.player.take_items(&build_cost(&entity))
+        {
+            return false;
+        }
+
+        let is_pole = matches!(entity, Entity::Pole(_));
+        let is_rail = matches!(entity, Entity::Rail(_));
+        let is_roboport = matches!(entity, Entity::Roboport(_));
+        let is_logistic_chest = matches!(&entity, Entity::Chest(chest) if chest.logistic.is_some());
+        self.tiles[y as usize][x as usize] = Some(entity);
+        self.changed_tiles.insert((x as usize, y as usize));
+        if is_pole {
+            self.rebuild_power_networks();
+        }
+        if is_rail {
+            self.rebuild_rail_blocks();
+        }
+        if is_roboport {
+            for _ in 0..ROBOPORT_ROBOTS {
+                self.logistics.robots.push(Robot::new((x, y)));
+            }
+        }
+        if is_roboport || is_logistic_chest {
+            self.rebuild_logistic_networks();
+        }
+        self.update_pipe_connections(x, y);
+        true
+    }
+
+    // Removes the entity on a tile, giving the player back what it cost and everything
+    // it held. Returns false, leaving the tile as it is, when the inventory can't hold it.
+    pub fn clear_tile(&mut self, x: usize, y: usize) -> bool {
+        let refund = self.clear_refund(x, y);
+        if !self.player.can_receive(&refund) {
+            return false;
+        }
+        self.player.give_items(&refund);
+
+        match self.take_entity(x, y) {
+            Some(Entity::Chest(chest)) if chest.logistic.is_some() => {
+                self.rebuild_logistic_networks()
+            }
+            Some(Entity::Pole(_)) => self.rebuild_power_networks(),
+            Some(Entity::Roboport(_)) => {
+                // Robots docking here are removed along with it
+                let home = (x as isize, y as isize);
+                self.logistics.robots.retain(|robot| robot.home != home);
+                self.rebuild_logistic_networks();
+            }
+            Some(Entity::Rail(_)) => {
+                // Trains and signals on removed track are removed with it
+                let (x, y) = (x as isize, y as isize);
+                self.railway
+                    .trains
+                    .retain(|train| !train.tiles.contains(&(x, y)));
+                self.railway.signals.retain(|&(signal_x, signal_y, side)| {
+                    (signal_x, signal_y) != (x, y) && side.offset(signal_x, signal_y) != (x, y)
+                });
+                self.rebuild_rail_blocks();
+            }
+            Some(Entity::Belt(belt)) if belt.splitter.is_some() => {
+                if let Some((partner_x, partner_y)) = self.splitter_partner(x, y, belt) {
+                    self.take_entity(partner_x as usize, partner_y as usize);
+                }
+            }
+            _ => (),
+        }
+        true
+    }
+
+    // Everything clearing a tile gives back: the entity with its contents, the items on
+    // the other half of a splitter, robot cargo and the trains and signals on a rail
+    fn clear_refund(&self, x: usize, y: usize) -> Vec<ItemStack> {
+        let entity = match &self.tiles[y][x] {
+            Some(entity) => entity,
+            None => return Vec::new(),
+        };
+        let mut refund = build_cost(entity);
+        refund.extend(entity.contents());
+
+        let (x, y) = (x as isize, y as isize);
+        match entity {
+            Entity::Roboport(_) => refund.extend(
+                self.logistics
+                    .robots
+                    .iter()
+                    .filter(|robot| robot.home == (x, y))
+                    .filter_map(|robot| robot.cargo),
+            ),
+            Entity::Rail(_) => {
+                for train in self.railway.trains.iter() {
+                    if !train.tiles.contains(&(x, y)) {
+                        continue;
+                    }
+                    for car in train.cars.iter() {
+                        let item = Item::from_name(car.kind.name()).unwrap();
+                        refund.push(ItemStack::new(item, 1));
+                        refund.extend(car.cargo.contents());
+                    }
+                }
+                let signals = self
+                    .railway
+                    .signals
+                    .iter()
+                    .filter(|&&(signal_x, signal_y, side)| {
+                        (signal_x, signal_y) == (x, y) || side.offset(signal_x, signal_y) == (x, y)
+                    })
+                    .count();
+                refund.push(ItemStack::new(Item::RailSignal, signals as u32));
+            }
+            Entity::Belt(belt) if belt.splitter.is_some() => {
+                // Both halves are built from a single splitter item
+                if let Some((partner_x, partner_y)) =
+                    self.splitter_partner(x as usize, y as usize, *belt)
+                {
+                    refund.extend(self.get_entity(partner_x, partner_y).unwrap().contents());
+                }
+            }
+            _ => (),
+        }
+        refund
+    }
+
+    // The other half of the splitter on (x, y), if it is still there
+    fn splitter_partner(&self, x: usize, y: usize, belt: Belt) -> Option<(isize, isize)> {
+        let (partner_x, partner_y) = Self::splitter_partner_pos(x as isize, y as isize, belt);
+        self.get_belt(partner_x, partner_y)
+            .filter(|partner| partner.splitter.is_some())
+            .map(|_| (partner_x, partner_y))
+    }
+
+    // Removes the entity on a tile along with its wires and pipe connections
+    fn take_entity(&mut self, x: usize, y: usize) -> Option<Entity> {
+        let removed = self.tiles[y][x].take();
+        self.changed_tiles.insert((x, y));
+        self.circuit.disconnect_tile(x as isize, y as isize);
+        self.update_pipe_connections(x as isize, y as isize);
+        removed
+    }
+
+    // Places a rail on an empty tile or adds its pieces to the rail already there,
+    // returns whether anything changed
+    pub fn place_rail(&mut self, x: isize, y: isize, rail: Rail) -> bool {
+        if !self.research.is_unlocked("rail") {
+            return false;
+        }
+        match self.get_entity(x, y) {
+            Some(Entity::Rail(existing)) => {
+                let mut merged = existing.clone();
+                if !merged.merge(&rail) {
+                    return false;
+                }
+                let added = (merged.pieces.len() - existing.pieces.len()) as u32;
+                if !self.player.take_items(&[ItemStack::new(Item::Rail, added)]) {
+                    return false;
+                }
+                self.tiles[y as usize][x as usize] = Some(Entity::Rail(merged));
+                self.changed_tiles.insert((x as usize, y as usize));
+                self.rebuild_rail_blocks();
+                true
+            }
+            _ => self.place_entity(x, y, Entity::Rail(rail)),
+        }
+    }
+
+    // Places a signal between the rail on (x, y) and the rail next to it on `side`
+    pub fn place_rail_signal(&mut self, x: isize, y: isize, side: Direction) -> bool {
+        let (neighbour_x, neighbour_y) = side.offset(x, y);
+        let connected = [((x, y), side), ((neighbour_x, neighbour_y), side.flip())]
+            .iter()
+            .all(|&((x, y), side)| {
+                matches!(self.get_entity(x, y), Some(Entity::Rail(rail)) if rail.has_side(side))
+            });
+        if !connected
+            || self.railway.has_signal(x, y, side)
+            || !self.research.is_unlocked("rail-signal")
+            || !self
+                .player
+                .take_items(&[ItemStack::new(Item::RailSignal, 1)])
+        {
+            return false;
+        }
+
+        self.railway.signals.push((x, y, side));
+        self.rebuild_rail_blocks();
+        true
+    }
+
+    // Puts a train facing `direction` with its front car on (x, y) and the other cars
+    // on the rails behind it. Returns whether there was room for it.
+    pub fn place_train(
+        &mut self,
+        x: isize,
+        y: isize,
+        direction: Direction,
+        cars: Vec<Car>,
+        schedule: Vec<ScheduleEntry>,
+    ) -> bool {
+        if !self.research.is_unlocked("locomotive") {
+            return false;
+        }
+        let entry = direction.flip();
+        let mut tiles = VecDeque::new();
+        let mut position = (x, y, entry);
+        for _ in 0..cars.len() {
+            let (x, y, entry) = position;
+            let on_rail =
+                matches!(self.get_entity(x, y), Some(Entity::Rail(rail)) if rail.has_side(entry));
+            if !on_rail || tiles.contains(&(x, y)) || self.railway.train_at(x, y).is_some() {
+                return false;
+            }
+            tiles.push_back((x, y));
+
+            // The tile behind is the one the train came from through `entry`
+            let (behind_x, behind_y) = entry.offset(x, y);
+            let behind_entry = match self.get_entity(behind_x, behind_y) {
+                Some(Entity::Rail(rail)) => rail.exits(entry.flip()).first().copied(),
+                _ => None,
+            };
+            position = (behind_x, behind_y, behind_entry.unwrap_or(entry));
+        }
+        let cost: Vec<ItemStack> = cars
+            .iter()
+            .map(|car| ItemStack::new(Item::from_name(car.kind.name()).unwrap(), 1))
+            .collect();
+        if !self.player.take_items(&cost) {
+            return false;
+        }
+
+        self.railway
+            .trains
+            .push(Train::new(cars, tiles, entry, schedule));
+        true
+    }
+
+    fn rebuild_rail_blocks(&mut self) {
+        let blocks = railway::blocks(self);
+        self.railway.set_blocks(blocks);
+    }
+
+    fn update_trains(&mut self) {
+        for index in 0..self.railway.trains.len() {
+            let mut train = self.railway.trains[index].clone();
+            self.update_train(index, &mut train);
+            self.railway.trains[index] = train;
+        }
+    }
+
+    // Waits out the stop at a station, otherwise moves a tile towards the next station
+    // every few ticks unless the tile is taken or a signal holds the train
+    fn update_train(&self, index: usize, train: &mut Train) {
+        if train.schedule.is_empty() {
+            train.state = TrainState::Idle;
+            return;
+        }
+
+        if let TrainState::Stopped { ticks } = train.state {
+            let wait = train.schedule[train.current].wait;
+            if train.wait_over(wait, ticks) {
+                train.current = (train.current + 1) % train.schedule.len();
+                train.state = TrainState::Moving;
+            } else {
+                train.state = TrainState::Stopped { ticks: ticks + 1 };
+            }
+            return;
+        }
+
+        let goal = match railway::station_tile(self, &train.schedule[train.current].station) {
+            Some(goal) => goal,
+            None => {
+                train.state = TrainState::NoPath;
+                return;
+            }
+        };
+        let front = train.front();
+        if (front.0, front.1) == goal {
+            train.state = TrainState::Stopped { ticks: 0 };
+            return;
+        }
+
+        train.progress = (train.progress + 1).min(TRAIN_STEP_TICKS);
+        if train.progress < TRAIN_STEP_TICKS {
+            return;
+        }
+        let next =
+            match railway::find_path(self, front, goal).and_then(|path| path.first().copied()) {
+                Some(next) => next,
+                None => {
+                    train.state = TrainState::NoPath;
+                    return;
+                }
+            };
+
+        // The last car moves out of the way in the same step
+        let (next_x, next_y, next_entry) = next;
+        let own_tile = train
+            .tiles
+            .iter()
+            .take(train.tiles.len() - 1)
+            .any(|&tile| tile == (next_x, next_y));
+        let other_train = self
+            .railway
+            .train_at(next_x, next_y)
+            .is_some_and(|other| other != index);
+        let block = self.railway.block_at(next_x, next_y);
+        let signal_closed = block != self.railway.block_at(front.0, front.1)
+            && block.is_some_and(|block| self.railway.is_block_occupied(block, Some(index)));
+        if own_tile || other_train || signal_closed {
+            train.state = TrainState::WaitingForSignal;
+            return;
+        }
+
+        train.tiles.push_front((next_x, next_y));
+        train.tiles.pop_back();
+        train.entry = next_entry;
+        train.progress = 0;
+        train.state = TrainState::Moving;
+    }
+
+    // Connects two entities with a circuit wire, returns whether they were connected.
+    // Entities with a single terminal are connected through it whichever was asked for.
+    pub fn connect_wire(&mut self, from: Port, to: Port, color: WireColor) -> bool {
+        let (from, to) = match (self.circuit_port(from), self.circuit_port(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return false,
+        };
+        CircuitGrid::in_reach(from, to) && self.circuit.connect(from, to, color)
+    }
+
+    fn circuit_port(&self, (x, y, terminal): Port) -> Option<Port> {
+        let entity = self.get_entity(x, y)?;
+        if entity.has_terminal(terminal) {
+            Some((x, y, terminal))
+        } else if entity.has_terminal(Terminal::Input) {
+            Some((x, y, Terminal::Input))
         } else {
             None
         }
     }
 
+    // Puts what every entity outputs on its networks, then lets entities react to the
+    // signals they read. Outputs always lag a tick behind the inputs they depend on.
+    fn update_circuits(&mut self) {
+        for network in self.circuit.networks.iter_mut() {
+            network.signals.clear();
+            for &(x, y, terminal) in network.ports.iter() {
+                if let Some(entity) = &self.tiles[y as usize][x as usize] {
+                    add_signals(&mut network.signals, &entity.circuit_output(terminal));
+                }
+            }
+        }
+
+        let circuit = &self.circuit;
+        for y in 0..self.tiles.len() {
+            for x in 0..self.tiles[0].len() {
+                let port = (x as isize, y as isize, Terminal::Input);
+                let control = match &mut self.tiles[y][x] {
+                    Some(Entity::ArithmeticCombinator(combinator)) => {
+                        combinator.update(&circuit.signals_at(port));
+                        continue;
+                    }
+                    Some(Entity::DeciderCombinator(combinator)) => {
+                        combinator.update(&circuit.signals_at(port));
+                        continue;
+                    }
+                    Some(Entity::Belt(Belt {
+                        circuit: Some(control),
+                        ..
+                    })) => control,
+                    Some(Entity::Inserter(Inserter {
+                        circuit: Some(control),
+                        ..
+                    })) => control,
+                    _ => continue,
+                };
+
+                // Entities without wires ignore their condition
+                let connected = [WireColor::Red, WireColor::Green]
+                    .iter()
+                    .any(|&color| circuit.network_at(port, color).is_some());
+                control.enabled = match control.condition {
+                    Some(condition) if connected => condition.evaluate(&circuit.signals_at(port)),
+                    _ => true,
+                };
+            }
+        }
+    }
+
+    // Reconnects a pipe on (x, y) and the pipes around it to their neighbours
+    fn update_pipe_connections(&mut self, x: isize, y: isize) {
+        self.calculate_pipe_connections(x, y);
+        for side in Direction::ALL.iter() {
+            let (neighbour_x, neighbour_y) = side.offset(x, y);
+            self.calculate_pipe_connections(neighbour_x, neighbour_y);
+        }
+    }
+
+    fn calculate_pipe_connections(&mut self, x: isize, y: isize) {
+        let mut connections = [false; 4];
+        for side in Direction::ALL.iter() {
+            let (neighbour_x, neighbour_y) = side.offset(x, y);
+            connections[side.index()] = self
+                .get_entity(neighbour_x, neighbour_y)
+                .is_some_and(|neighbour| neighbour.accepts_pipe(side.flip()));
+        }
+
+        if let Some(Entity::Pipe(pipe)) = self.get_entity_mut(x, y) {
+            pipe.connections = connections;
+        }
+    }
+
+    fn rebuild_power_networks(&mut self) {
+        let mut poles = Vec::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if let Some(Entity::Pole(_)) = tile {
+                    poles.push((x as isize, y as isize));
+                }
+            }
+        }
+        self.power.rebuild(&poles);
+    }
+
+    fn rebuild_logistic_networks(&mut self) {
+        let mut roboports = Vec::new();
+        let mut chests = Vec::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                match tile {
+                    Some(Entity::Roboport(_)) => roboports.push((x as isize, y as isize)),
+                    Some(Entity::Chest(chest)) if chest.logistic.is_some() => {
+                        chests.push((x as isize, y as isize))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        self.logistics.rebuild(&roboports, &chests);
+    }
+
+    // Gives docked robots new deliveries, then moves every robot a step along its way
+    fn update_robots(&mut self) {
+        for (robot, delivery) in logistics::assign_deliveries(self) {
+            self.logistics.robots[robot].delivery = Some(delivery);
+        }
+
+        for index in 0..self.logistics.robots.len() {
+            let mut robot = self.logistics.robots[index].clone();
+            if robot.is_docked() {
+                robot.charge();
+            } else if robot.fly_to(robot.target()) {
+                self.robot_arrived(&mut robot);
+            }
+            self.logistics.robots[index] = robot;
+        }
+    }
+
+    fn robot_arrived(&mut self, robot: &mut Robot) {
+        match (robot.delivery, robot.cargo) {
+            (Some(delivery), None) => {
+                // The chest may have been emptied or removed since the delivery was assigned
+                let (x, y) = delivery.from;
+                let taken = match self.get_entity_mut(x, y) {
+                    Some(Entity::Chest(chest)) => {
+                        chest.inventory.extract(delivery.item, delivery.count)
+                    }
+                    _ => 0,
+                };
+                if taken > 0 {
+                    robot.cargo = Some(ItemStack::new(delivery.item, taken));
+                } else {
+                    robot.delivery = None;
+                }
+            }
+            (Some(delivery), Some(stack)) => {
+                let (x, y) = delivery.to;
+                let inserted = match self.get_entity_mut(x, y) {
+                    Some(Entity::Chest(chest)) => chest.inventory.insert(stack.item, stack.count),
+                    _ => 0,
+                };
+                if inserted == stack.count {
+                    robot.cargo = None;
+                    robot.delivery = None;
+                    return;
+                }
+
+                // What doesn't fit goes to storage, or waits here until there is room
+                let stack = ItemStack::new(stack.item, stack.count - inserted);
+                robot.cargo = Some(stack);
+                if let Some(storage) = logistics::storage_for(self, delivery.to, stack) {
+                    robot.delivery = Some(Delivery {
+                        from: delivery.to,
+                        to: storage,
+                        item: stack.item,
+                        count: stack.count,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn update_power(&mut self) {
+        self.power.begin_tick();
+        for y in 0..self.tiles.len() {
+            for x in 0..self.tiles[0].len() {
+                let network = match self.power.network_at(x as isize, y as isize) {
+                    Some(network) => network,
+                    None => continue,
+                };
+                if let Some(entity) = &self.tiles[y][x] {
+                    let network = &mut self.power.networks[network];
+                    network.demand += entity.power_demand();
+                    if let Entity::Generator(generator) = entity {
+                        network.production += generator.available_output();
+                    }
+                }
+            }
+        }
+
+        for y in 0..self.tiles.len() {
+            for x in 0..self.tiles[0].len() {
+                let network = match self.power.network_at(x as isize, y as isize) {
+                    Some(network) => &self.power.networks[network],
+                    None => continue,
+                };
+                if let Some(Entity::Generator(generator)) = &mut self.tiles[y][x] {
+                    if network.demand > 0 {
+                        generator.burn(&mut self.stats);
+                    }
+                }
+            }
+        }
+        self.power.end_tick();
+    }
+
+    pub fn in_bounds(&self, x: isize, y: isize) -> bool {
+        (0..self.tiles[0].len() as isize).contains(&x)
+            && (0..self.tiles.len() as isize).contains(&y)
+    }
+
+    pub fn get_entity(&self, x: isize, y: isize) -> Option<&Entity> {
+        if self.in_bounds(x, y) {
+            self.tiles[y as usize][x as usize].as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_entity_mut(&mut self, x: isize, y: isize) -> Option<&mut Entity> {
+        if self.in_bounds(x, y) {
+            self.tiles[y as usize][x as usize].as_mut()
+        } else {
+            None
+        }
+    }
+
+    // Tiles built on, cleared or mined out since the last call, in no particular order
+    pub fn take_changed_tiles(&mut self) -> Vec<(usize, usize)> {
+        self.changed_tiles.drain().collect()
+    }
+
+    pub fn get_resource(&self, x: isize, y: isize) -> Option<Resource> {
+        if self.in_bounds(x, y) {
+            self.resources[y as usize][x as usize]
+        } else {
+            None
+        }
+    }
+
+    // Removes one unit of ore from a tile, returning the mined item
+    pub fn mine_resource(&mut self, x: isize, y: isize) -> Option<Item> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+
+        let tile = &mut self.resources[y as usize][x as usize];
+        let resource = tile.as_mut()?;
+        resource.amount -= 1;
+        let item = resource.item;
+        if resource.amount == 0 {
+            *tile = None;
+            self.changed_tiles.insert((x as usize, y as usize));
+        }
+        Some(item)
+    }
+
+    pub fn get_belt(&self, x: isize, y: isize) -> Option<Belt> {
+        match self.get_entity(x, y) {
+            Some(Entity::Belt(belt)) => Some(*belt),
+            _ => None,
+        }
+    }
+
     pub fn set_belt(&mut self, x: isize, y: isize, belt: Belt) {
-        if x >= 0 && x < self.tiles[0].len() as isize && y >= 0 && y < self.tiles.len() as isize {
-            self.tiles[y as usize][x as usize] = Some(belt);
+        if self.in_bounds(x, y) {
+            let tile = &mut self.tiles[y as usize][x as usize];
+            if !matches!(tile, Some(Entity::Belt(_))) {
+                self.changed_tiles.insert((x as usize, y as usize));
+            }
+            *tile = Some(Entity::Belt(belt));
+        }
+    }
+
+    fn update_belts(&mut self) {
+        let height = self.tiles.len() as isize;
+        let width = self.tiles[0].len() as isize;
+
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(mut belt) = self.get_belt(x, y) {
+                    // Stopped belts still advance by nothing, so their items are drawn
+                    // standing still
+                    let speed = if belt.is_enabled() {
+                        belt.tier.speed()
+                    } else {
+                        0
+                    };
+                    for lane in 0..belt.lanes.len() {
+                        let limit = self.lane_limit(x, y, belt, lane);
+                        belt.lanes[lane].advance(speed, limit);
+                    }
+                    self.set_belt(x, y, belt);
+                }
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(belt) = self.get_belt(x, y).filter(Belt::is_enabled) {
+                    for lane in 0..belt.lanes.len() {
+                        self.hand_over(x, y, lane);
+                    }
+                }
+            }
+        }
+    }
+
+    // How far the front item of a lane may move before it would collide with
+    // whatever the belt is feeding into
+    fn lane_limit(&self, x: isize, y: isize, belt: Belt, lane: usize) -> u32 {
+        self.belt_targets(x, y, belt)
+            .into_iter()
+            .map(|(front_x, front_y)| self.lane_limit_into(front_x, front_y, belt, lane))
+            .max()
+            .unwrap_or(LANE_LENGTH - 1)
+    }
+
+    fn lane_limit_into(&self, front_x: isize, front_y: isize, belt: Belt, lane: usize) -> u32 {
+        match self.get_entity(front_x, front_y) {
+            Some(Entity::Belt(front_belt)) => match belt.target_lane(front_belt, lane) {
+                Some((target_lane, 0)) => match front_belt.lanes[target_lane].back() {
+                    Some(back) => LANE_LENGTH + back.position - ITEM_SPACING,
+                    None => LANE_LENGTH + LANE_LENGTH,
+                },
+                Some((target_lane, position))
+                    if front_belt.lanes[target_lane].can_insert(position) =>
+                {
+                    LANE_LENGTH
+                }
+                _ => LANE_LENGTH - 1,
+            },
+            Some(Entity::Chest(chest)) => match belt.lanes[lane].front() {
+                Some(front) if chest.inventory.can_insert(front.item, 1) => LANE_LENGTH,
+                _ => LANE_LENGTH - 1,
+            },
+            _ => LANE_LENGTH - 1,
+        }
+    }
+
+    // The tile items leave a belt to, the paired exit for underground entrances
+    pub fn belt_target(&self, x: isize, y: isize, belt: Belt) -> (isize, isize) {
+        if belt.underground != Some(Underground::Entrance) {
+            return Self::front_pos(x, y, belt);
+        }
+
+        let (mut exit_x, mut exit_y) = (x, y);
+        for _ in 0..MAX_UNDERGROUND_DISTANCE {
+            let (next_x, next_y) = belt.output.offset(exit_x, exit_y);
+            exit_x = next_x;
+            exit_y = next_y;
+            if let Some(exit) = self.get_belt(exit_x, exit_y) {
+                if exit.underground == Some(Underground::Exit) && exit.output == belt.output {
+                    return (exit_x, exit_y);
+                }
+            }
+        }
+        // No exit, point at the tile above the tunnel which never accepts items
+        (x, y)
+    }
+
+    // All tiles items may leave a belt to. Splitters can also output in front of the
+    // other half, which is listed second.
+    pub fn belt_targets(&self, x: isize, y: isize, belt: Belt) -> Vec<(isize, isize)> {
+        let mut targets = vec![self.belt_target(x, y, belt)];
+        if belt.splitter.is_some() {
+            let (partner_x, partner_y) = Self::splitter_partner_pos(x, y, belt);
+            if let Some(partner) = self.get_belt(partner_x, partner_y) {
+                if partner.splitter.is_some() && partner.output == belt.output {
+                    targets.push(Self::front_pos(partner_x, partner_y, partner));
+                }
+            }
+        }
+        targets
+    }
+
+    // Moves the front item of a lane onto the tile in front once it has reached the end
+    fn hand_over(&mut self, x: isize, y: isize, lane: usize) {
+        let mut belt = match self.get_belt(x, y) {
+            Some(belt) => belt,
+            None => return,
+        };
+        let front = match belt.lanes[lane].front() {
+            Some(front) if front.position >= LANE_LENGTH => front,
+            _ => return,
+        };
+
+        let mut targets = self.belt_targets(x, y, belt);
+        let own_target = targets[0];
+        // Splitters alternate which side the items of a lane go to, falling back to the
+        // other side when the preferred one is blocked
+        if let Some(splitter) = belt.splitter.as_mut() {
+            if splitter.alternate[lane] {
+                targets.reverse();
+            }
+        }
+
+        let mut delivered = None;
+        for (front_x, front_y) in targets.iter().copied() {
+            let accepted = match self.get_entity_mut(front_x, front_y) {
+                Some(Entity::Belt(front_belt)) => match belt.target_lane(front_belt, lane) {
+                    Some((target_lane, position)) => front_belt.lanes[target_lane].insert_moved(
+                        front.item,
+                        position + front.position - LANE_LENGTH,
+                        front.moved,
+                    ),
+                    None => false,
+                },
+                Some(Entity::Chest(chest)) => chest.inventory.insert(front.item, 1) == 1,
+                _ => false,
+            };
+            if accepted {
+                delivered = Some((front_x, front_y));
+                break;
+            }
+        }
+
+        if let Some(target) = delivered {
+            belt.lanes[lane].pop_front();
+            // The next item prefers the side this one didn't go to
+            if let Some(splitter) = belt.splitter.as_mut() {
+                splitter.alternate[lane] = target == own_target;
+            }
+        } else {
+            let front = belt.lanes[lane].items[0].as_mut().unwrap();
+            let overshoot = front.position - (LANE_LENGTH - 1);
+            front.moved = front.moved.saturating_sub(overshoot);
+            front.position = LANE_LENGTH - 1;
+        }
+        self.set_belt(x, y, belt);
+    }
+
+    fn update_drills(&mut self) {
+        for y in 0..self.tiles.len() as isize {
+            for x in 0..self.tiles[0].len() as isize {
+                let mut drill = match self.get_entity(x, y) {
+                    Some(Entity::Drill(drill)) => *drill,
+                    _ => continue,
+                };
+
+                if let Some(item) = drill.output_buffer {
+                    let (output_x, output_y) = drill.output.offset(x, y);
+                    if self.drop_item(output_x, output_y, drill.output, item) {
+                        drill.output_buffer = None;
+                    }
+                } else if self.get_resource(x, y).is_some() {
+                    drill.progress += self.power.satisfaction_at(x, y);
+                    if drill.progress >= DRILL_MINING_TICKS * FULL_SATISFACTION {
+                        drill.progress = 0;
+                        drill.output_buffer = self.mine_resource(x, y);
+                        if let Some(item) = drill.output_buffer {
+                            self.stats.produce(item, 1);
+                        }
+                    }
+                }
+
+                if let Some(Entity::Drill(current)) = self.get_entity_mut(x, y) {
+                    *current = drill;
+                }
+            }
+        }
+    }
+
+    fn update_furnaces(&mut self) {
+        for tile in self.tiles.iter_mut().flatten() {
+            if let Some(Entity::Furnace(furnace)) = tile {
+                furnace.tick(&mut self.stats);
+            }
+        }
+    }
+
+    // Labs with the science packs of the current research start a unit, which takes
+    // longer without full power
+    fn update_labs(&mut self) {
+        let technology = match self.research.current_technology() {
+            Some(technology) => technology.clone(),
+            None => return,
+        };
+        let mut units = 0;
+        for y in 0..self.tiles.len() {
+            for x in 0..self.tiles[0].len() {
+                let satisfaction = self.power.satisfaction_at(x as isize, y as isize);
+                let lab = match &mut self.tiles[y][x] {
+                    Some(Entity::Lab(lab)) => lab,
+                    _ => continue,
+                };
+
+                if lab.progress.is_none()
+                    && satisfaction > 0
+                    && technology
+                        .ingredients
+                        .iter()
+                        .all(|stack| lab.inventory.count(stack.item) >= stack.count)
+                {
+                    for stack in technology.ingredients.iter() {
+                        lab.inventory.extract(stack.item, stack.count);
+                        self.stats.consume(stack.item, stack.count);
+                    }
+                    lab.progress = Some(0);
+                }
+                if let Some(progress) = lab.progress.as_mut() {
+                    *progress += satisfaction;
+                    if *progress >= technology.unit_ticks * FULL_SATISFACTION {
+                        lab.progress = None;
+                        units += 1;
+                    }
+                }
+            }
+        }
+        for _ in 0..units {
+            self.research.add_unit();
+        }
+    }
+
+    fn update_fluids(&mut self) {
+        for tile in self.tiles.iter_mut().flatten() {
+            match tile {
+                Some(Entity::OffshorePump(pump)) => pump.tick(),
+                Some(Entity::Boiler(boiler)) => boiler.tick(&mut self.stats),
+                _ => (),
+            }
+        }
+
+        // Passive flow, each connection is visited once through its west and south side
+        for y in 0..self.tiles.len() as isize {
+            for x in 0..self.tiles[0].len() as isize {
+                for &side in [Direction::West, Direction::South].iter() {
+                    let (other_x, other_y) = side.offset(x, y);
+                    self.flow_between(x, y, other_x, other_y, side);
+                }
+            }
+        }
+
+        for y in 0..self.tiles.len() as isize {
+            for x in 0..self.tiles[0].len() as isize {
+                let direction = match self.get_entity(x, y) {
+                    Some(Entity::Pump(pump)) => pump.direction,
+                    _ => continue,
+                };
+                let rate =
+                    PUMP_RATE * self.power.satisfaction_at(x, y) as f32 / FULL_SATISFACTION as f32;
+                let (front_x, front_y) = direction.offset(x, y);
+                let front_index = match self.get_entity(front_x, front_y) {
+                    Some(front) => match front.fluid_connection(direction.flip()) {
+                        Some((index, FlowDirection::Input))
+                        | Some((index, FlowDirection::Both)) => index,
+                        _ => continue,
+                    },
+                    None => continue,
+                };
+
+                let mut pump_box = *self.fluid_box(x, y, 0).unwrap();
+                let mut front_box = *self.fluid_box(front_x, front_y, front_index).unwrap();
+                FluidBox::transfer(&mut pump_box, &mut front_box, rate);
+                self.set_fluid_box(x, y, 0, pump_box);
+                self.set_fluid_box(front_x, front_y, front_index, front_box);
+            }
+        }
+    }
+
+    fn flow_between(
+        &mut self,
+        x: isize,
+        y: isize,
+        other_x: isize,
+        other_y: isize,
+        side: Direction,
+    ) {
+        let (index, direction) = match self
+            .get_entity(x, y)
+            .and_then(|entity| entity.fluid_connection(side))
+        {
+            Some(connection) => connection,
+            None => return,
+        };
+        let (other_index, other_direction) = match self
+            .get_entity(other_x, other_y)
+            .and_then(|entity| entity.fluid_connection(side.flip()))
+        {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let mut fluid_box = *self.fluid_box(x, y, index).unwrap();
+        let mut other_box = *self.fluid_box(other_x, other_y, other_index).unwrap();
+        let flow = FluidBox::flow(&fluid_box, &other_box);
+        let outwards =
+            direction != FlowDirection::Input && other_direction != FlowDirection::Output;
+        let inwards = direction != FlowDirection::Output && other_direction != FlowDirection::Input;
+        if flow > 0.0 && outwards {
+            FluidBox::transfer(&mut fluid_box, &mut other_box, flow);
+        } else if flow < 0.0 && inwards {
+            FluidBox::transfer(&mut other_box, &mut fluid_box, -flow);
+        } else {
+            return;
+        }
+
+        self.set_fluid_box(x, y, index, fluid_box);
+        self.set_fluid_box(other_x, other_y, other_index, other_box);
+    }
+
+    pub fn fluid_box(&self, x: isize, y: isize, index: usize) -> Option<&FluidBox> {
+        self.get_entity(x, y)?.fluid_boxes().get(index).copied()
+    }
+
+    fn set_fluid_box(&mut self, x: isize, y: isize, index: usize, fluid_box: FluidBox) {
+        if let Some(current) = self
+            .get_entity_mut(x, y)
+            .and_then(|entity| entity.fluid_box_mut(index))
+        {
+            *current = fluid_box;
+        }
+    }
+
+    fn update_inserters(&mut self) {
+        for y in 0..self.tiles.len() as isize {
+            for x in 0..self.tiles[0].len() as isize {
+                let mut inserter = match self.get_entity(x, y) {
+                    Some(Entity::Inserter(inserter)) => *inserter,
+                    _ => continue,
+                };
+
+                let satisfaction = self.power.satisfaction_at(x, y);
+                if satisfaction == 0 || !inserter.is_enabled() {
+                    continue;
+                }
+
+                if inserter.cooldown > 0 {
+                    inserter.cooldown = inserter.cooldown.saturating_sub(satisfaction);
+                } else if let Some(item) = inserter.held {
+                    let (drop_x, drop_y) = inserter.direction.offset(x, y);
+                    if self.drop_item(drop_x, drop_y, inserter.direction, item) {
+                        inserter.held = None;
+                        inserter.cooldown = INSERTER_SWING_TICKS * FULL_SATISFACTION;
+                    }
+                } else {
+                    let (pick_x, pick_y) = inserter.direction.flip().offset(x, y);
+                    if let Some(item) = self.take_item(pick_x, pick_y) {
+                        inserter.held = Some(item);
+                        inserter.cooldown = INSERTER_SWING_TICKS * FULL_SATISFACTION;
+                    }
+                }
+
+                if let Some(Entity::Inserter(current)) = self.get_entity_mut(x, y) {
+                    *current = inserter;
+                }
+            }
+        }
+    }
+
+    // Removes a single item from the entity on a tile, if it holds any
+    pub fn take_item(&mut self, x: isize, y: isize) -> Option<Item> {
+        if let Some(Entity::Rail(_)) = self.get_entity(x, y) {
+            let cargo = self.railway.cargo_at_mut(x, y)?;
+            return cargo.extract_any(1).map(|stack| stack.item);
+        }
+        match self.get_entity_mut(x, y)? {
+            Entity::Chest(chest) => chest.inventory.extract_any(1).map(|stack| stack.item),
+            Entity::Furnace(furnace) => furnace.result.extract_any(1).map(|stack| stack.item),
+            Entity::Belt(belt) => {
+                let lane = (0..belt.lanes.len())
+                    .filter(|&lane| !belt.lanes[lane].is_empty())
+                    .max_by_key(|&lane| belt.lanes[lane].front().unwrap().position)?;
+                belt.lanes[lane].pop_front().map(|lane_item| lane_item.item)
+            }
+            _ => None,
+        }
+    }
+
+    // Gives a single item to the entity on a tile, `direction` being the direction
+    // the item travels in. Returns whether the item was accepted.
+    pub fn drop_item(&mut self, x: isize, y: isize, direction: Direction, item: Item) -> bool {
+        if let Some(Entity::Rail(_)) = self.get_entity(x, y) {
+            return self
+                .railway
+                .cargo_at_mut(x, y)
+                .is_some_and(|cargo| cargo.insert(item, 1) == 1);
+        }
+        match self.get_entity_mut(x, y) {
+            Some(Entity::Chest(chest)) => chest.inventory.insert(item, 1) == 1,
+            Some(Entity::Furnace(furnace)) => furnace.insert(item),
+            Some(Entity::Generator(generator)) => generator.insert(item),
+            Some(Entity::Boiler(boiler)) => boiler.insert(item),
+            Some(Entity::Lab(lab)) => item.is_science_pack() && lab.inventory.insert(item, 1) == 1,
+            Some(Entity::Belt(belt)) => {
+                let lane = belt.drop_lane(direction);
+                belt.lanes[lane].insert(item, LANE_LENGTH / 2)
+            }
+            _ => false,
         }
     }
 
@@ -137,6 +1288,13 @@ impl Grid {
         }
     }
 
+    fn splitter_partner_pos(x: isize, y: isize, belt: Belt) -> (isize, isize) {
+        match belt.splitter.map(|splitter| splitter.side) {
+            Some(SplitterSide::Right) => Self::left_pos(x, y, belt),
+            _ => Self::right_pos(x, y, belt),
+        }
+    }
+
     fn belt_left_of(&self, x: isize, y: isize, belt: Belt) -> (Option<Belt>, (isize, isize)) {
         let (x, y) = Self::left_pos(x, y, belt);
         (self.get_belt(x, y), (x, y))
@@ -147,7 +1305,12 @@ impl Grid {
         (self.get_belt(x, y), (x, y))
     }
 
-    fn belt_in_front_of(&self, x: isize, y: isize, belt: Belt) -> (Option<Belt>, (isize, isize)) {
+    pub fn belt_in_front_of(
+        &self,
+        x: isize,
+        y: isize,
+        belt: Belt,
+    ) -> (Option<Belt>, (isize, isize)) {
         let (x, y) = Self::front_pos(x, y, belt);
         (self.get_belt(x, y), (x, y))
     }
@@ -157,16 +1320,41 @@ impl Grid {
         self.set_belt(x, y, new_belt);
     }
 
-    fn belt_behind(&self, x: isize, y: isize, belt: Belt) -> (Option<Belt>, (isize, isize)) {
+    pub fn belt_behind(&self, x: isize, y: isize, belt: Belt) -> (Option<Belt>, (isize, isize)) {
         let (x, y) = Self::behind_pos(x, y, belt);
         (self.get_belt(x, y), (x, y))
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Underground {
+    Entrance,
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitterSide {
+    Left,
+    Right,
+}
+
+// One half of a two tile splitter, each half being a straight belt
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Splitter {
+    pub side: SplitterSide,
+    // Whether the next item of each lane goes in front of the other half first
+    pub alternate: [bool; 2],
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Belt {
     pub input: Direction,
     pub output: Direction,
+    pub lanes: [Lane; 2],
+    pub underground: Option<Underground>,
+    pub splitter: Option<Splitter>,
+    pub circuit: Option<CircuitControl>,
+    pub tier: BeltTier,
 }
 
 impl Belt {
@@ -174,11 +1362,123 @@ impl Belt {
         Self {
             input: Direction::West,
             output: Direction::East,
+            lanes: [Lane::new(); 2],
+            underground: None,
+            splitter: None,
+            circuit: None,
+            tier: BeltTier::Basic,
+        }
+    }
+
+    // Straight belt going in `direction`
+    pub fn straight(direction: Direction) -> Self {
+        Self {
+            input: direction.flip(),
+            output: direction,
+            ..Self::new()
+        }
+    }
+
+    pub fn underground(direction: Direction, kind: Underground) -> Self {
+        Self {
+            underground: Some(kind),
+            ..Self::straight(direction)
+        }
+    }
+
+    pub fn splitter(direction: Direction, side: SplitterSide) -> Self {
+        Self {
+            splitter: Some(Splitter {
+                side,
+                alternate: [false; 2],
+            }),
+            ..Self::straight(direction)
         }
     }
 }
 
 impl Belt {
+    // Lane of `front_belt` and the position on it that items leaving `lane` of this
+    // belt enter at. Belts joining head-on keep both lanes, belts joining from the side
+    // put all items on the near lane of the front belt.
+    pub fn target_lane(&self, front_belt: &Belt, lane: usize) -> Option<(usize, u32)> {
+        let entry_side = self.output.flip();
+        if front_belt.input == entry_side {
+            Some((lane, 0))
+        } else if entry_side == front_belt.output.rotate_anti_clockwise() {
+            Some((LEFT_LANE, LANE_LENGTH / 2))
+        } else if entry_side == front_belt.output.rotate_clockwise() {
+            Some((RIGHT_LANE, LANE_LENGTH / 2))
+        } else {
+            None
+        }
+    }
+
+    // Lane that items dropped on the belt by something facing `direction` land on,
+    // the one furthest away from where they come from
+    pub fn drop_lane(&self, direction: Direction) -> usize {
+        if direction == self.output.rotate_anti_clockwise() {
+            LEFT_LANE
+        } else {
+            RIGHT_LANE
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.circuit.map_or(true, |control| control.enabled)
+    }
+
+    // Name of the item the belt is built from, which is also its research name
+    pub fn kind_name(&self) -> &'static str {
+        if self.splitter.is_some() {
+            "splitter"
+        } else if self.underground.is_some() {
+            "underground-belt"
+        } else {
+            self.tier.name()
+        }
+    }
+
+    // Underground belts and splitters can't be turned by belts placed next to them
+    pub fn is_straight_only(&self) -> bool {
+        self.underground.is_some() || self.splitter.is_some()
+    }
+
+    // Where an item `position` along `lane` is, in tiles from the center of the belt.
+    // Positions past either end continue straight out of the belt.
+    pub fn lane_point(&self, lane: usize, position: f32) -> (f32, f32) {
+        // Travelling along +y with the left lane at -x, then rotated to the belt
+        let offset = if lane == LEFT_LANE { -0.25 } else { 0.25 };
+        let t = position / LANE_LENGTH as f32;
+        let turn = self.turn();
+        let (x, y) = match turn {
+            Turn::Forward => (offset, t - 0.5),
+            _ if t < 0.0 => (offset, t - 0.5),
+            Turn::Left | Turn::Right => {
+                // Curves go around the corner between the input side and the output side
+                let side = if matches!(turn, Turn::Left) {
+                    -1.0
+                } else {
+                    1.0
+                };
+                let radius = 0.5 - side * offset;
+                let angle = t.min(1.0) * std::f32::consts::FRAC_PI_2;
+                let past_end = (t - 1.0).max(0.0);
+                (
+                    side * (0.5 - radius * angle.cos() + past_end),
+                    -0.5 + radius * angle.sin(),
+                )
+            }
+        };
+
+        let (forward_x, forward_y) = self.input.flip().offset(0, 0);
+        let (right_x, right_y) = self.input.rotate_anti_clockwise().offset(0, 0);
+        (
+            x * right_x as f32 + y * forward_x as f32,
+            x * right_y as f32 + y * forward_y as f32,
+        )
+    }
+
     pub fn turn(&self) -> Turn {
         let dir = self.input.rotate_clockwise();
         if dir == self.output {
@@ -200,6 +1500,40 @@ impl Belt {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BeltTier {
+    Basic,
+    Fast,
+    Express,
+}
+
+impl BeltTier {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Basic => "belt",
+            Self::Fast => "fast-belt",
+            Self::Express => "express-belt",
+        }
+    }
+
+    // Distance items travel along a lane each tick
+    pub fn speed(&self) -> u32 {
+        match *self {
+            Self::Basic => BELT_SPEED,
+            Self::Fast => 2 * BELT_SPEED,
+            Self::Express => 3 * BELT_SPEED,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::Basic => Self::Fast,
+            Self::Fast => Self::Express,
+            Self::Express => Self::Basic,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Direction {
     West,
     North,
@@ -208,6 +1542,22 @@ pub enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::West,
+        Direction::North,
+        Direction::East,
+        Direction::South,
+    ];
+
+    pub fn index(&self) -> usize {
+        match *self {
+            Self::West => 0,
+            Self::North => 1,
+            Self::East => 2,
+            Self::South => 3,
+        }
+    }
+
     pub fn rotate_clockwise(&self) -> Self {
         match *self {
             Self::West => Self::North,
@@ -226,6 +1576,16 @@ impl Direction {
         }
     }
 
+    // The tile next to (x, y) in this direction
+    pub fn offset(&self, x: isize, y: isize) -> (isize, isize) {
+        match *self {
+            Self::West => (x - 1, y),
+            Self::North => (x, y + 1),
+            Self::East => (x + 1, y),
+            Self::South => (x, y - 1),
+        }
+    }
+
     pub fn flip(&self) -> Self {
         match *self {
             Self::West => Self::East,
@@ -236,9 +1596,436 @@ impl Direction {
     }
 }
 
+// Lanes are indexed as seen when looking in the direction of the belt output
+pub const LEFT_LANE: usize = 0;
+pub const RIGHT_LANE: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LaneItem {
+    pub item: Item,
+    // Distance travelled along the lane, 0 is where the belt input is
+    pub position: u32,
+    // Distance moved on the last tick, for drawing items between ticks
+    pub moved: u32,
+}
+
+// Items on one side of a belt, ordered from the front (closest to the output) to the back
+#[derive(Clone, Copy, Debug)]
+pub struct Lane {
+    items: [Option<LaneItem>; LANE_CAPACITY],
+}
+
+impl Lane {
+    pub fn new() -> Self {
+        Self {
+            items: [None; LANE_CAPACITY],
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &LaneItem> {
+        self.items.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items[0].is_none()
+    }
+
+    pub fn front(&self) -> Option<LaneItem> {
+        self.items[0]
+    }
+
+    pub fn back(&self) -> Option<LaneItem> {
+        self.items().last().copied()
+    }
+
+    pub fn can_insert(&self, position: u32) -> bool {
+        self.len() < LANE_CAPACITY
+            && position < LANE_LENGTH
+            && self
+                .items()
+                .all(|lane_item| lane_item.position.abs_diff(position) >= ITEM_SPACING)
+    }
+
+    pub fn insert(&mut self, item: Item, position: u32) -> bool {
+        self.insert_moved(item, position, 0)
+    }
+
+    // Inserts an item that is still moving, such as one handed over from another belt
+    pub fn insert_moved(&mut self, item: Item, position: u32, moved: u32) -> bool {
+        if !self.can_insert(position) {
+            return false;
+        }
+
+        let index = self
+            .items()
+            .take_while(|lane_item| lane_item.position > position)
+            .count();
+        self.items[index..].rotate_right(1);
+        self.items[index] = Some(LaneItem {
+            item,
+            position,
+            moved,
+        });
+        true
+    }
+
+    pub fn pop_front(&mut self) -> Option<LaneItem> {
+        let front = self.items[0].take();
+        self.items.rotate_left(1);
+        front
+    }
+
+    // Moves all items forward while keeping them spaced apart, the front item
+    // is not moved past `limit`
+    pub fn advance(&mut self, speed: u32, limit: u32) {
+        let mut limit = limit;
+        for lane_item in self.items.iter_mut().flatten() {
+            let position = (lane_item.position + speed)
+                .min(limit)
+                .max(lane_item.position);
+            lane_item.moved = position - lane_item.position;
+            lane_item.position = position;
+            limit = lane_item.position.saturating_sub(ITEM_SPACING);
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Turn {
     Left,
     Forward,
     Right,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{chest::Chest, drill::Drill, generator::Generator, pole::Pole};
+
+    fn belt(output: Direction) -> Belt {
+        Belt::straight(output)
+    }
+
+    fn fill_lanes(grid: &mut Grid, x: isize, y: isize, item: Item) {
+        let mut belt = grid.get_belt(x, y).unwrap();
+        for lane in belt.lanes.iter_mut() {
+            assert!(lane.insert(item, 0));
+            assert!(lane.insert(item, ITEM_SPACING * 2));
+        }
+        grid.set_belt(x, y, belt);
+    }
+
+    fn lane_items(grid: &Grid, x: isize, y: isize, lane: usize) -> Vec<Item> {
+        grid.get_belt(x, y).unwrap().lanes[lane]
+            .items()
+            .map(|lane_item| lane_item.item)
+            .collect()
+    }
+
+    #[test]
+    fn lanes_keep_items_spaced_and_ordered() {
+        let mut lane = Lane::new();
+        assert!(lane.insert(Item::Coal, 100));
+        assert!(!lane.insert(Item::Coal, 100 + ITEM_SPACING - 1));
+        assert!(lane.insert(Item::Stone, 100 + ITEM_SPACING));
+        assert!(lane.insert(Item::IronOre, 0));
+        assert!(!lane.insert(Item::IronOre, LANE_LENGTH));
+        assert_eq!(lane.front().unwrap().item, Item::Stone);
+        assert_eq!(lane.back().unwrap().item, Item::IronOre);
+
+        // The front item stops at the limit and the others keep their distance
+        lane.advance(BELT_SPEED, 170);
+        let positions: Vec<u32> = lane.items().map(|lane_item| lane_item.position).collect();
+        assert_eq!(positions, vec![170, 106, 8]);
+        let moved: Vec<u32> = lane.items().map(|lane_item| lane_item.moved).collect();
+        assert_eq!(moved, vec![6, 6, 8]);
+
+        assert_eq!(lane.pop_front().unwrap().item, Item::Stone);
+        assert_eq!(lane.len(), 2);
+        assert_eq!(lane.front().unwrap().item, Item::Coal);
+    }
+
+    #[test]
+    fn lane_points_follow_straight_belts_and_curves() {
+        let close = |(x, y): (f32, f32), (expected_x, expected_y): (f32, f32)| {
+            assert!((x - expected_x).abs() < 1e-5 && (y - expected_y).abs() < 1e-5);
+        };
+        let length = LANE_LENGTH as f32;
+        let belt = belt(Direction::East);
+        // Travelling east the left lane is on the north side
+        close(belt.lane_point(LEFT_LANE, 0.0), (-0.5, 0.25));
+        close(belt.lane_point(RIGHT_LANE, length), (0.5, -0.25));
+        close(belt.lane_point(RIGHT_LANE, -length / 4.0), (-0.75, -0.25));
+
+        // Turning left from east to north, the right lane takes the outside of the curve
+        let turn = Belt {
+            input: Direction::West,
+            output: Direction::North,
+            ..Belt::new()
+        };
+        close(turn.lane_point(RIGHT_LANE, 0.0), (-0.5, -0.25));
+        close(turn.lane_point(RIGHT_LANE, length), (0.25, 0.5));
+        close(turn.lane_point(LEFT_LANE, length), (-0.25, 0.5));
+        close(turn.lane_point(LEFT_LANE, length * 1.5), (-0.25, 1.0));
+    }
+
+    fn run(grid: &mut Grid, ticks: u32) {
+        for _ in 0..ticks {
+            grid.tick();
+        }
+    }
+
+    #[test]
+    fn head_on_join_keeps_both_lanes() {
+        let mut grid = Grid::new();
+        grid.place_belt(0, 5, belt(Direction::East));
+        grid.place_belt(1, 5, belt(Direction::East));
+        let mut first = grid.get_belt(0, 5).unwrap();
+        first.lanes[LEFT_LANE].insert(Item::IronPlate, 0);
+        first.lanes[RIGHT_LANE].insert(Item::CopperPlate, 0);
+        grid.set_belt(0, 5, first);
+
+        run(&mut grid, 100);
+
+        assert!(grid.get_belt(0, 5).unwrap().lanes[LEFT_LANE].is_empty());
+        assert_eq!(lane_items(&grid, 1, 5, LEFT_LANE), vec![Item::IronPlate]);
+        assert_eq!(lane_items(&grid, 1, 5, RIGHT_LANE), vec![Item::CopperPlate]);
+    }
+
+    #[test]
+    fn side_load_from_left_fills_near_lane() {
+        let mut grid = Grid::new();
+        for x in 0..3 {
+            grid.place_belt(x, 5, belt(Direction::East));
+        }
+        // North is to the left of a belt going east
+        grid.place_belt(1, 6, belt(Direction::South));
+        assert!(matches!(grid.get_belt(1, 5).unwrap().turn(), Turn::Forward));
+        fill_lanes(&mut grid, 1, 6, Item::IronPlate);
+
+        run(&mut grid, 200);
+
+        assert!(grid
+            .get_belt(1, 6)
+            .unwrap()
+            .lanes
+            .iter()
+            .all(Lane::is_empty));
+        assert_eq!(lane_items(&grid, 2, 5, LEFT_LANE), vec![Item::IronPlate; 4]);
+        assert!(lane_items(&grid, 2, 5, RIGHT_LANE).is_empty());
+    }
+
+    #[test]
+    fn side_load_from_right_fills_near_lane() {
+        let mut grid = Grid::new();
+        for x in 0..3 {
+            grid.place_belt(x, 5, belt(Direction::East));
+        }
+        grid.place_belt(1, 4, belt(Direction::North));
+        assert!(matches!(grid.get_belt(1, 5).unwrap().turn(), Turn::Forward));
+        fill_lanes(&mut grid, 1, 4, Item::CopperPlate);
+
+        run(&mut grid, 200);
+
+        assert!(lane_items(&grid, 2, 5, LEFT_LANE).is_empty());
+        assert_eq!(
+            lane_items(&grid, 2, 5, RIGHT_LANE),
+            vec![Item::CopperPlate; 4]
+        );
+    }
+
+    #[test]
+    fn feeding_from_both_sides_merges_into_both_lanes() {
+        let mut grid = Grid::new();
+        grid.place_belt(1, 6, belt(Direction::South));
+        grid.place_belt(1, 4, belt(Direction::North));
+        grid.place_belt(1, 5, belt(Direction::East));
+        grid.place_belt(2, 5, belt(Direction::East));
+        assert!(matches!(grid.get_belt(1, 5).unwrap().turn(), Turn::Forward));
+        fill_lanes(&mut grid, 1, 6, Item::IronPlate);
+        fill_lanes(&mut grid, 1, 4, Item::CopperPlate);
+
+        run(&mut grid, 300);
+
+        let left = [
+            lane_items(&grid, 1, 5, LEFT_LANE),
+            lane_items(&grid, 2, 5, LEFT_LANE),
+        ]
+        .concat();
+        let right = [
+            lane_items(&grid, 1, 5, RIGHT_LANE),
+            lane_items(&grid, 2, 5, RIGHT_LANE),
+        ]
+        .concat();
+        assert_eq!(left, vec![Item::IronPlate; 4]);
+        assert_eq!(right, vec![Item::CopperPlate; 4]);
+    }
+
+    #[test]
+    fn side_load_onto_curve_is_a_head_on_join() {
+        let mut grid = Grid::new();
+        // Nothing behind the front belt, so it turns to take items from the feeder
+        grid.place_belt(1, 5, belt(Direction::East));
+        grid.place_belt(2, 5, belt(Direction::East));
+        grid.place_belt(1, 6, belt(Direction::South));
+        assert!(matches!(grid.get_belt(1, 5).unwrap().turn(), Turn::Left));
+        fill_lanes(&mut grid, 1, 6, Item::IronPlate);
+
+        run(&mut grid, 300);
+
+        let left = [
+            lane_items(&grid, 1, 5, LEFT_LANE),
+            lane_items(&grid, 2, 5, LEFT_LANE),
+        ]
+        .concat();
+        let right = [
+            lane_items(&grid, 1, 5, RIGHT_LANE),
+            lane_items(&grid, 2, 5, RIGHT_LANE),
+        ]
+        .concat();
+        assert_eq!(left, vec![Item::IronPlate; 2]);
+        assert_eq!(right, vec![Item::IronPlate; 2]);
+    }
+
+    #[test]
+    fn building_clearing_and_mining_out_change_tiles() {
+        let mut grid = Grid::new();
+        grid.resources[5][5] = Some(Resource {
+            item: Item::Coal,
+            amount: 1,
+        });
+        grid.place_belt(0, 0, belt(Direction::East));
+        grid.place_belt(1, 0, belt(Direction::East));
+        grid.place_entity(2, 2, Entity::Chest(Chest::new()));
+        let mut changed = grid.take_changed_tiles();
+        changed.sort();
+        assert_eq!(changed, vec![(0, 0), (1, 0), (2, 2)]);
+
+        // Items moving along belts don't change the tiles
+        grid.drop_item(0, 0, Direction::North, Item::Coal);
+        grid.tick();
+        assert!(grid.take_changed_tiles().is_empty());
+
+        grid.clear_tile(2, 2);
+        assert_eq!(grid.mine_resource(5, 5), Some(Item::Coal));
+        let mut changed = grid.take_changed_tiles();
+        changed.sort();
+        assert_eq!(changed, vec![(2, 2), (5, 5)]);
+    }
+
+    #[test]
+    fn removing_a_splitter_removes_both_halves_and_their_wires() {
+        let mut grid = Grid::new();
+        grid.place_splitter(0, 1, Direction::North);
+        let splitter = grid.get_belt(0, 1).unwrap();
+        let (partner_x, partner_y) = Grid::splitter_partner_pos(0, 1, splitter);
+        grid.place_belt(partner_x + 1, partner_y, belt(Direction::North));
+        assert!(grid.connect_wire(
+            (partner_x, partner_y, Terminal::Input),
+            (partner_x + 1, partner_y, Terminal::Input),
+            WireColor::Red
+        ));
+
+        grid.clear_tile(0, 1);
+        assert!(grid.get_entity(0, 1).is_none());
+        assert!(grid.get_entity(partner_x, partner_y).is_none());
+        assert!(grid.circuit.wires.is_empty());
+    }
+
+    #[test]
+    fn splitter_alternates_between_outputs() {
+        let mut grid = Grid::new();
+        grid.place_splitter(0, 1, Direction::North);
+        for x in 0..2 {
+            grid.place_belt(x, 0, belt(Direction::North));
+            grid.place_belt(x, 2, belt(Direction::North));
+        }
+        for _ in 0..4 {
+            grid.drop_item(0, 0, Direction::North, Item::Coal);
+            for _ in 0..40 {
+                grid.tick();
+            }
+        }
+        for _ in 0..200 {
+            grid.tick();
+        }
+
+        let count = |x| {
+            grid.get_belt(x, 2)
+                .unwrap()
+                .lanes
+                .iter()
+                .map(|lane| lane.len())
+                .sum::<usize>()
+        };
+        assert_eq!((count(0), count(1)), (2, 2));
+    }
+
+    // A powered drill at (1, 1) outputting east over a patch of coal
+    fn drill_grid(amount: u32) -> Grid {
+        let mut grid = Grid::new();
+        grid.resources[1][1] = Some(Resource {
+            item: Item::Coal,
+            amount,
+        });
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(0, 0, Entity::Generator(generator));
+        grid.place_entity(1, 0, Entity::Pole(Pole::new()));
+        grid.place_entity(1, 1, Entity::Drill(Drill::new(Direction::East)));
+        grid
+    }
+
+    fn chest_count(grid: &Grid, x: isize, y: isize) -> u32 {
+        match grid.get_entity(x, y) {
+            Some(Entity::Chest(chest)) => chest.inventory.count(Item::Coal),
+            _ => panic!("no chest"),
+        }
+    }
+
+    fn drill_buffer(grid: &Grid) -> Option<Item> {
+        match grid.get_entity(1, 1) {
+            Some(Entity::Drill(drill)) => drill.output_buffer,
+            _ => panic!("no drill"),
+        }
+    }
+
+    #[test]
+    fn drills_mine_the_ore_under_them_until_it_runs_out() {
+        let mut grid = drill_grid(2);
+        grid.place_entity(2, 1, Entity::Chest(Chest::new()));
+
+        run(&mut grid, DRILL_MINING_TICKS + 5);
+        assert_eq!(grid.get_resource(1, 1).unwrap().amount, 1);
+        assert_eq!(chest_count(&grid, 2, 1), 1);
+
+        run(&mut grid, DRILL_MINING_TICKS);
+        assert!(grid.get_resource(1, 1).is_none());
+        assert_eq!(chest_count(&grid, 2, 1), 2);
+
+        // Nothing left to mine
+        run(&mut grid, DRILL_MINING_TICKS);
+        assert_eq!(chest_count(&grid, 2, 1), 2);
+    }
+
+    #[test]
+    fn drills_hold_their_item_while_the_output_is_blocked() {
+        let mut grid = drill_grid(10);
+        grid.place_entity(1, 2, Entity::Chest(Chest::new()));
+
+        // Nothing takes the item east of the drill, so it waits without mining more
+        run(&mut grid, DRILL_MINING_TICKS * 2 + 5);
+        assert_eq!(drill_buffer(&grid), Some(Item::Coal));
+        assert_eq!(grid.get_resource(1, 1).unwrap().amount, 9);
+        assert_eq!(chest_count(&grid, 1, 2), 0);
+
+        grid.place_entity(2, 1, Entity::Chest(Chest::new()));
+        grid.tick();
+        assert_eq!(drill_buffer(&grid), None);
+        assert_eq!(chest_count(&grid, 2, 1), 1);
+    }
+}
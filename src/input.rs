@@ -18,39 +18,33 @@ impl KeyboardState {
     }
 
     pub fn is_pressed(&self, key: VirtualKeyCode) -> bool {
-        self.state.get(&key).is_some()
+        self.state.contains(&key)
     }
 
     pub fn was_pressed(&self, key: VirtualKeyCode) -> bool {
         self.momentary_state.get(&key) == Some(&ElementState::Pressed)
     }
 
-    pub fn was_released(&self, key: VirtualKeyCode) -> bool {
-        self.momentary_state.get(&key) == Some(&ElementState::Released)
-    }
-
     pub fn process_event(&mut self, event: &WindowEvent) {
-        match *event {
-            WindowEvent::KeyboardInput {
-                input:
-                    KeyboardInput {
-                        state,
-                        virtual_keycode: Some(virtual_keycode),
-                        ..
-                    },
-                ..
-            } => {
-                self.momentary_state.insert(virtual_keycode, state);
-                match state {
-                    ElementState::Pressed => {
-                        self.state.insert(virtual_keycode);
-                    }
-                    ElementState::Released => {
-                        self.state.remove(&virtual_keycode);
-                    }
+        if let WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state,
+                    virtual_keycode: Some(virtual_keycode),
+                    ..
+                },
+            ..
+        } = *event
+        {
+            self.momentary_state.insert(virtual_keycode, state);
+            match state {
+                ElementState::Pressed => {
+                    self.state.insert(virtual_keycode);
+                }
+                ElementState::Released => {
+                    self.state.remove(&virtual_keycode);
                 }
             }
-            _ => (),
         }
     }
 
@@ -91,17 +85,13 @@ impl MouseState {
     }
 
     pub fn is_pressed(&self, button: MouseButton) -> bool {
-        self.button_state.get(&button).is_some()
+        self.button_state.contains(&button)
     }
 
     pub fn was_pressed(&self, button: MouseButton) -> bool {
         self.momentary_button_state.get(&button) == Some(&ElementState::Pressed)
     }
 
-    pub fn was_released(&self, button: MouseButton) -> bool {
-        self.momentary_button_state.get(&button) == Some(&ElementState::Released)
-    }
-
     pub fn process_event(&mut self, event: &WindowEvent) {
         match *event {
             WindowEvent::CursorMoved { position, .. } => {
@@ -120,12 +110,12 @@ impl MouseState {
                     }
                 }
             }
-            WindowEvent::MouseWheel { delta, .. } => match delta {
-                MouseScrollDelta::LineDelta(_, y) => {
-                    self.scroll_delta += y;
-                }
-                _ => (),
-            },
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(_, y),
+                ..
+            } => {
+                self.scroll_delta += y;
+            }
             _ => (),
         }
     }
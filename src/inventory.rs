@@ -0,0 +1,183 @@
+use crate::item::Item;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: Item, count: u32) -> Self {
+        Self { item, count }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_none())
+    }
+
+    // Whether every slot holds a full stack
+    pub fn is_full(&self) -> bool {
+        self.slots
+            .iter()
+            .all(|slot| slot.is_some_and(|stack| stack.count == stack.item.stack_size()))
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    // Total amount of every item, merged across slots and sorted by item
+    pub fn contents(&self) -> Vec<ItemStack> {
+        let mut contents: Vec<ItemStack> = Vec::new();
+        for stack in self.slots.iter().flatten() {
+            match contents.iter_mut().find(|s| s.item == stack.item) {
+                Some(existing) => existing.count += stack.count,
+                None => contents.push(*stack),
+            }
+        }
+        contents.sort_by_key(|stack| stack.item);
+        contents
+    }
+
+    pub fn space_for(&self, item: Item) -> u32 {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.item == item => item.stack_size() - stack.count,
+                Some(_) => 0,
+                None => item.stack_size(),
+            })
+            .sum()
+    }
+
+    pub fn can_insert(&self, item: Item, count: u32) -> bool {
+        self.space_for(item) >= count
+    }
+
+    // Inserts as many as fit, topping up existing stacks before using empty slots.
+    // Returns the amount that was inserted.
+    pub fn insert(&mut self, item: Item, count: u32) -> u32 {
+        let mut remaining = count;
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.item == item {
+                let moved = remaining.min(item.stack_size() - stack.count);
+                stack.count += moved;
+                remaining -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let moved = remaining.min(item.stack_size());
+                *slot = Some(ItemStack::new(item, moved));
+                remaining -= moved;
+            }
+        }
+        count - remaining
+    }
+
+    // Removes up to `count` of `item`, taking from the last slots first.
+    // Returns the amount that was extracted.
+    pub fn extract(&mut self, item: Item, count: u32) -> u32 {
+        let mut remaining = count;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = slot {
+                if stack.item == item {
+                    let moved = remaining.min(stack.count);
+                    stack.count -= moved;
+                    remaining -= moved;
+                    if stack.count == 0 {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+        count - remaining
+    }
+
+    // Removes up to `count` items of whichever item is in the first occupied slot
+    pub fn extract_any(&mut self, count: u32) -> Option<ItemStack> {
+        let item = self.slots.iter().flatten().next()?.item;
+        let extracted = self.extract(item, count);
+        Some(ItemStack::new(item, extracted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_fills_stacks_up_to_their_limit() {
+        let mut inventory = Inventory::new(2);
+        assert_eq!(inventory.insert(Item::Coal, 30), 30);
+        assert_eq!(inventory.insert(Item::Coal, 30), 30);
+        assert_eq!(
+            inventory.slots(),
+            &[
+                Some(ItemStack::new(Item::Coal, 50)),
+                Some(ItemStack::new(Item::Coal, 10))
+            ]
+        );
+        assert_eq!(inventory.space_for(Item::Coal), 40);
+        assert_eq!(inventory.space_for(Item::IronPlate), 0);
+        assert!(!inventory.can_insert(Item::Coal, 41));
+
+        // Only what fits is inserted
+        assert_eq!(inventory.insert(Item::Coal, 100), 40);
+        assert!(inventory.is_full());
+        assert_eq!(inventory.insert(Item::Coal, 1), 0);
+    }
+
+    #[test]
+    fn extract_takes_from_the_last_slots_first() {
+        let mut inventory = Inventory::new(3);
+        inventory.insert(Item::IronPlate, 150);
+        inventory.insert(Item::Coal, 5);
+        assert_eq!(inventory.extract(Item::IronPlate, 60), 60);
+        assert_eq!(
+            inventory.slots()[0],
+            Some(ItemStack::new(Item::IronPlate, 90))
+        );
+        assert_eq!(inventory.slots()[1], None);
+        assert_eq!(inventory.extract(Item::IronPlate, 100), 90);
+        assert_eq!(inventory.extract(Item::Stone, 1), 0);
+
+        assert_eq!(
+            inventory.extract_any(10),
+            Some(ItemStack::new(Item::Coal, 5))
+        );
+        assert!(inventory.is_empty());
+        assert_eq!(inventory.extract_any(1), None);
+    }
+}
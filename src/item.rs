@@ -0,0 +1,163 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Item {
+    Coal,
+    Stone,
+    IronOre,
+    CopperOre,
+    IronPlate,
+    CopperPlate,
+    SteelPlate,
+    StoneBrick,
+    AutomationSciencePack,
+    LogisticSciencePack,
+    // Items that build the entity of the same name
+    Belt,
+    FastBelt,
+    ExpressBelt,
+    UndergroundBelt,
+    Splitter,
+    Chest,
+    ProviderChest,
+    RequesterChest,
+    StorageChest,
+    Inserter,
+    Drill,
+    Furnace,
+    Pole,
+    Generator,
+    Pipe,
+    Pump,
+    Tank,
+    OffshorePump,
+    Boiler,
+    ConstantCombinator,
+    ArithmeticCombinator,
+    DeciderCombinator,
+    Rail,
+    TrainStop,
+    RailSignal,
+    Locomotive,
+    CargoWagon,
+    Roboport,
+    Lab,
+}
+
+impl Item {
+    pub const ALL: [Item; 39] = [
+        Item::Coal,
+        Item::Stone,
+        Item::IronOre,
+        Item::CopperOre,
+        Item::IronPlate,
+        Item::CopperPlate,
+        Item::SteelPlate,
+        Item::StoneBrick,
+        Item::AutomationSciencePack,
+        Item::LogisticSciencePack,
+        Item::Belt,
+        Item::FastBelt,
+        Item::ExpressBelt,
+        Item::UndergroundBelt,
+        Item::Splitter,
+        Item::Chest,
+        Item::ProviderChest,
+        Item::RequesterChest,
+        Item::StorageChest,
+        Item::Inserter,
+        Item::Drill,
+        Item::Furnace,
+        Item::Pole,
+        Item::Generator,
+        Item::Pipe,
+        Item::Pump,
+        Item::Tank,
+        Item::OffshorePump,
+        Item::Boiler,
+        Item::ConstantCombinator,
+        Item::ArithmeticCombinator,
+        Item::DeciderCombinator,
+        Item::Rail,
+        Item::TrainStop,
+        Item::RailSignal,
+        Item::Locomotive,
+        Item::CargoWagon,
+        Item::Roboport,
+        Item::Lab,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Coal => "coal",
+            Self::Stone => "stone",
+            Self::IronOre => "iron-ore",
+            Self::CopperOre => "copper-ore",
+            Self::IronPlate => "iron-plate",
+            Self::CopperPlate => "copper-plate",
+            Self::SteelPlate => "steel-plate",
+            Self::StoneBrick => "stone-brick",
+            Self::AutomationSciencePack => "automation-science-pack",
+            Self::LogisticSciencePack => "logistic-science-pack",
+            Self::Belt => "belt",
+            Self::FastBelt => "fast-belt",
+            Self::ExpressBelt => "express-belt",
+            Self::UndergroundBelt => "underground-belt",
+            Self::Splitter => "splitter",
+            Self::Chest => "chest",
+            Self::ProviderChest => "provider-chest",
+            Self::RequesterChest => "requester-chest",
+            Self::StorageChest => "storage-chest",
+            Self::Inserter => "inserter",
+            Self::Drill => "drill",
+            Self::Furnace => "furnace",
+            Self::Pole => "pole",
+            Self::Generator => "generator",
+            Self::Pipe => "pipe",
+            Self::Pump => "pump",
+            Self::Tank => "tank",
+            Self::OffshorePump => "offshore-pump",
+            Self::Boiler => "boiler",
+            Self::ConstantCombinator => "constant-combinator",
+            Self::ArithmeticCombinator => "arithmetic-combinator",
+            Self::DeciderCombinator => "decider-combinator",
+            Self::Rail => "rail",
+            Self::TrainStop => "train-stop",
+            Self::RailSignal => "rail-signal",
+            Self::Locomotive => "locomotive",
+            Self::CargoWagon => "cargo-wagon",
+            Self::Roboport => "roboport",
+            Self::Lab => "lab",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn stack_size(&self) -> u32 {
+        match *self {
+            Self::Coal | Self::Stone | Self::IronOre | Self::CopperOre => 50,
+            Self::IronPlate | Self::CopperPlate | Self::SteelPlate | Self::StoneBrick => 100,
+            Self::AutomationSciencePack | Self::LogisticSciencePack => 200,
+            _ => 50,
+        }
+    }
+
+    pub fn is_science_pack(&self) -> bool {
+        matches!(
+            self,
+            Self::AutomationSciencePack | Self::LogisticSciencePack
+        )
+    }
+
+    // Ticks a burner entity runs on a single item, None if it is not a fuel
+    pub fn burn_ticks(&self) -> Option<u32> {
+        match *self {
+            Self::Coal => Some(2400),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|item| item.name() == name)
+    }
+}
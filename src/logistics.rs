@@ -0,0 +1,494 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    entity::{chest::Chest, Entity},
+    grid::Grid,
+    inventory::ItemStack,
+    item::Item,
+};
+
+// Tiles around a roboport (in each direction) its robots serve
+pub const ROBOPORT_RANGE: isize = 12;
+// Robots that come with every roboport
+pub const ROBOPORT_ROBOTS: usize = 6;
+// Tiles a robot flies each tick
+pub const ROBOT_SPEED: f32 = 0.08;
+// Robots with a flat battery keep flying at a fraction of their speed
+pub const LOW_BATTERY_SPEED: f32 = 0.2;
+// Battery in tiles of flight
+pub const ROBOT_BATTERY: f32 = 150.0;
+// Battery regained each tick while docked
+pub const ROBOT_CHARGE_RATE: f32 = 1.0;
+// Most items a robot carries at once
+pub const ROBOT_CARGO: u32 = 4;
+
+type Tile = (isize, isize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogisticMode {
+    // Robots take items out to fulfil requests
+    Provider,
+    // Robots keep it filled with the requested items
+    Requester(Vec<ItemStack>),
+    // Robots take from it when no provider has the item, and leave cargo in it that
+    // couldn't be delivered
+    Storage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delivery {
+    pub from: Tile,
+    pub to: Tile,
+    pub item: Item,
+    pub count: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Robot {
+    pub x: f32,
+    pub y: f32,
+    pub battery: f32,
+    // Roboport the robot returns to when it has nothing to do
+    pub home: Tile,
+    pub delivery: Option<Delivery>,
+    pub cargo: Option<ItemStack>,
+}
+
+impl Robot {
+    pub fn new(home: Tile) -> Self {
+        Self {
+            x: home.0 as f32,
+            y: home.1 as f32,
+            battery: ROBOT_BATTERY,
+            home,
+            delivery: None,
+            cargo: None,
+        }
+    }
+
+    pub fn is_docked(&self) -> bool {
+        self.delivery.is_none()
+            && self.cargo.is_none()
+            && (self.x, self.y) == (self.home.0 as f32, self.home.1 as f32)
+    }
+
+    // Where the robot is flying to next
+    pub fn target(&self) -> Tile {
+        match (self.delivery, self.cargo) {
+            (Some(delivery), Some(_)) => delivery.to,
+            (Some(delivery), None) => delivery.from,
+            _ => self.home,
+        }
+    }
+
+    // Moves a step towards a tile, returns whether it got there
+    pub fn fly_to(&mut self, (x, y): Tile) -> bool {
+        let (dx, dy) = (x as f32 - self.x, y as f32 - self.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let speed = if self.battery > 0.0 {
+            ROBOT_SPEED
+        } else {
+            ROBOT_SPEED * LOW_BATTERY_SPEED
+        };
+        if distance <= speed {
+            (self.x, self.y) = (x as f32, y as f32);
+        } else {
+            self.x += dx / distance * speed;
+            self.y += dy / distance * speed;
+        }
+        self.battery = (self.battery - speed.min(distance)).max(0.0);
+        distance <= speed
+    }
+
+    pub fn charge(&mut self) {
+        self.battery = (self.battery + ROBOT_CHARGE_RATE).min(ROBOT_BATTERY);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogisticNetwork {
+    pub roboports: Vec<Tile>,
+    // Logistic chests covered by the network, in row-major order
+    pub chests: Vec<Tile>,
+}
+
+pub struct LogisticGrid {
+    pub networks: Vec<LogisticNetwork>,
+    pub robots: Vec<Robot>,
+    // Network covering each tile, if any
+    coverage: Vec<Vec<Option<usize>>>,
+}
+
+impl LogisticGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            networks: Vec::new(),
+            robots: Vec::new(),
+            coverage: vec![vec![None; width]; height],
+        }
+    }
+
+    // Groups roboports with overlapping coverage into networks and hands each network
+    // the logistic chests it covers. Roboports and chests are expected in row-major
+    // order, which keeps network ids and the order requests are handled in stable.
+    pub fn rebuild(&mut self, roboports: &[Tile], chests: &[Tile]) {
+        let overlap = |a: Tile, b: Tile| {
+            (a.0 - b.0).abs() <= 2 * ROBOPORT_RANGE && (a.1 - b.1).abs() <= 2 * ROBOPORT_RANGE
+        };
+        let mut network_of = vec![None; roboports.len()];
+        self.networks.clear();
+        for start in 0..roboports.len() {
+            if network_of[start].is_some() {
+                continue;
+            }
+
+            let id = self.networks.len();
+            let mut network = LogisticNetwork {
+                roboports: Vec::new(),
+                chests: Vec::new(),
+            };
+            let mut queue = VecDeque::new();
+            network_of[start] = Some(id);
+            queue.push_back(start);
+            while let Some(current) = queue.pop_front() {
+                network.roboports.push(roboports[current]);
+                for next in 0..roboports.len() {
+                    if network_of[next].is_none() && overlap(roboports[current], roboports[next]) {
+                        network_of[next] = Some(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            self.networks.push(network);
+        }
+
+        for row in self.coverage.iter_mut() {
+            for tile in row.iter_mut() {
+                *tile = None;
+            }
+        }
+        for (i, &(port_x, port_y)) in roboports.iter().enumerate() {
+            for y in (port_y - ROBOPORT_RANGE).max(0)..=port_y + ROBOPORT_RANGE {
+                for x in (port_x - ROBOPORT_RANGE).max(0)..=port_x + ROBOPORT_RANGE {
+                    if let Some(tile) = self
+                        .coverage
+                        .get_mut(y as usize)
+                        .and_then(|row| row.get_mut(x as usize))
+                    {
+                        tile.get_or_insert(network_of[i].unwrap());
+                    }
+                }
+            }
+        }
+
+        for &(x, y) in chests {
+            if let Some(network) = self.network_at(x, y) {
+                self.networks[network].chests.push((x, y));
+            }
+        }
+    }
+
+    pub fn network_at(&self, x: isize, y: isize) -> Option<usize> {
+        if y < 0 || x < 0 {
+            return None;
+        }
+        *self.coverage.get(y as usize)?.get(x as usize)?
+    }
+}
+
+fn distance(a: Tile, b: Tile) -> f32 {
+    let (dx, dy) = ((a.0 - b.0) as f32, (a.1 - b.1) as f32);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Logistic chests of a network with their tile, in row-major order
+fn network_chests(grid: &Grid, network: usize) -> impl Iterator<Item = (Tile, &Chest)> + '_ {
+    grid.logistics.networks[network]
+        .chests
+        .iter()
+        .filter_map(move |&(x, y)| match grid.get_entity(x, y) {
+            Some(Entity::Chest(chest)) => Some(((x, y), chest)),
+            _ => None,
+        })
+}
+
+// Items already on their way to a chest, and items reserved to be picked up from one
+#[derive(Default)]
+struct Pending {
+    incoming: HashMap<(Tile, Item), u32>,
+    outgoing: HashMap<(Tile, Item), u32>,
+}
+
+impl Pending {
+    fn add(&mut self, delivery: Delivery, picked_up: bool) {
+        *self
+            .incoming
+            .entry((delivery.to, delivery.item))
+            .or_insert(0) += delivery.count;
+        if !picked_up {
+            *self
+                .outgoing
+                .entry((delivery.from, delivery.item))
+                .or_insert(0) += delivery.count;
+        }
+    }
+
+    fn incoming(&self, tile: Tile, item: Item) -> u32 {
+        self.incoming.get(&(tile, item)).copied().unwrap_or(0)
+    }
+
+    fn outgoing(&self, tile: Tile, item: Item) -> u32 {
+        self.outgoing.get(&(tile, item)).copied().unwrap_or(0)
+    }
+}
+
+// Hands out deliveries for missing requested items to docked robots. Requests are
+// handled network by network in row-major order of the requesters and every choice
+// breaks ties on tile and robot order, so the same world always gets the same
+// assignments.
+pub fn assign_deliveries(grid: &Grid) -> Vec<(usize, Delivery)> {
+    let robots = &grid.logistics.robots;
+    let mut pending = Pending::default();
+    for robot in robots.iter() {
+        if let Some(delivery) = robot.delivery {
+            pending.add(delivery, robot.cargo.is_some());
+        }
+    }
+    let mut busy = vec![false; robots.len()];
+    let mut assigned = Vec::new();
+
+    for network in 0..grid.logistics.networks.len() {
+        let chests: Vec<(Tile, &Chest)> = network_chests(grid, network).collect();
+        assign_network_deliveries(
+            grid,
+            network,
+            &chests,
+            &mut pending,
+            &mut busy,
+            &mut assigned,
+        );
+    }
+    assigned
+}
+
+fn assign_network_deliveries(
+    grid: &Grid,
+    network: usize,
+    chests: &[(Tile, &Chest)],
+    pending: &mut Pending,
+    busy: &mut [bool],
+    assigned: &mut Vec<(usize, Delivery)>,
+) {
+    let robots = &grid.logistics.robots;
+    for &(requester, chest) in chests.iter() {
+        let requests = match &chest.logistic {
+            Some(LogisticMode::Requester(requests)) => requests,
+            _ => continue,
+        };
+        for request in requests.iter() {
+            loop {
+                let have =
+                    chest.inventory.count(request.item) + pending.incoming(requester, request.item);
+                let missing = request
+                    .count
+                    .saturating_sub(have)
+                    .min(chest.inventory.space_for(request.item));
+                if missing == 0 {
+                    break;
+                }
+
+                // Nearest provider with the item, storage chests only if no provider has it
+                let source = [true, false].iter().find_map(|&providers| {
+                    chests
+                        .iter()
+                        .filter(|(tile, source)| {
+                            *tile != requester
+                                && match source.logistic {
+                                    Some(LogisticMode::Provider) => providers,
+                                    Some(LogisticMode::Storage) => !providers,
+                                    _ => false,
+                                }
+                        })
+                        .filter_map(|&(tile, source)| {
+                            let available = source
+                                .inventory
+                                .count(request.item)
+                                .saturating_sub(pending.outgoing(tile, request.item));
+                            (available > 0).then_some((tile, available))
+                        })
+                        .min_by(|(a, _), (b, _)| {
+                            distance(*a, requester).total_cmp(&distance(*b, requester))
+                        })
+                });
+                let (from, available) = match source {
+                    Some(source) => source,
+                    None => break,
+                };
+
+                // Nearest docked robot of the network with the battery for the round trip
+                let robot = (0..robots.len())
+                    .filter(|&index| {
+                        let robot = &robots[index];
+                        let trip = distance(robot.home, from)
+                            + distance(from, requester)
+                            + distance(requester, robot.home);
+                        !busy[index]
+                            && robot.is_docked()
+                            && robot.battery >= trip
+                            && grid.logistics.network_at(robot.home.0, robot.home.1)
+                                == Some(network)
+                    })
+                    .min_by(|&a, &b| {
+                        distance(robots[a].home, from).total_cmp(&distance(robots[b].home, from))
+                    });
+                let robot = match robot {
+                    Some(robot) => robot,
+                    None => break,
+                };
+                let delivery = Delivery {
+                    from,
+                    to: requester,
+                    item: request.item,
+                    count: missing.min(available).min(ROBOT_CARGO),
+                };
+                busy[robot] = true;
+                pending.add(delivery, false);
+                assigned.push((robot, delivery));
+            }
+        }
+    }
+}
+
+// Nearest storage chest of the network at `tile` with room for the items
+pub fn storage_for(grid: &Grid, tile: Tile, stack: ItemStack) -> Option<Tile> {
+    let network = grid.logistics.network_at(tile.0, tile.1)?;
+    network_chests(grid, network)
+        .filter(|(_, chest)| {
+            chest.logistic == Some(LogisticMode::Storage)
+                && chest.inventory.space_for(stack.item) > 0
+        })
+        .map(|(chest_tile, _)| chest_tile)
+        .min_by(|a, b| distance(*a, tile).total_cmp(&distance(*b, tile)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::roboport::Roboport;
+
+    fn chest(grid: &mut Grid, x: isize, y: isize, mode: LogisticMode, contents: &[ItemStack]) {
+        let mut chest = Chest::logistic(mode);
+        for stack in contents {
+            chest.inventory.insert(stack.item, stack.count);
+        }
+        assert!(grid.place_entity(x, y, Entity::Chest(chest)));
+    }
+
+    fn count(grid: &Grid, x: isize, y: isize, item: Item) -> u32 {
+        match grid.get_entity(x, y) {
+            Some(Entity::Chest(chest)) => chest.inventory.count(item),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn robots_fill_requests_from_the_nearest_provider() {
+        let mut grid = Grid::new();
+        assert!(grid.place_entity(10, 10, Entity::Roboport(Roboport::new())));
+        let plates = ItemStack::new(Item::IronPlate, 20);
+        chest(&mut grid, 5, 10, LogisticMode::Provider, &[plates]);
+        chest(&mut grid, 0, 10, LogisticMode::Provider, &[plates]);
+        chest(&mut grid, 15, 10, LogisticMode::Storage, &[plates]);
+        chest(
+            &mut grid,
+            10,
+            15,
+            LogisticMode::Requester(vec![ItemStack::new(Item::IronPlate, 10)]),
+            &[],
+        );
+
+        let deliveries = assign_deliveries(&grid);
+        assert_eq!(deliveries.len(), 3);
+        assert!(deliveries
+            .iter()
+            .all(|(_, delivery)| delivery.from == (5, 10) && delivery.to == (10, 15)));
+        assert_eq!(
+            deliveries
+                .iter()
+                .map(|(_, delivery)| delivery.count)
+                .collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+
+        let mut ticks = 0;
+        while count(&grid, 10, 15, Item::IronPlate) < 10
+            || !grid.logistics.robots.iter().all(Robot::is_docked)
+        {
+            grid.tick();
+            ticks += 1;
+            assert!(ticks < 1000);
+        }
+        assert_eq!(count(&grid, 10, 15, Item::IronPlate), 10);
+        assert_eq!(count(&grid, 5, 10, Item::IronPlate), 10);
+        assert_eq!(count(&grid, 0, 10, Item::IronPlate), 20);
+        assert_eq!(count(&grid, 15, 10, Item::IronPlate), 20);
+        assert!(grid.logistics.robots.iter().all(Robot::is_docked));
+    }
+
+    #[test]
+    fn chests_outside_coverage_are_not_served() {
+        let mut grid = Grid::new();
+        assert!(grid.place_entity(10, 10, Entity::Roboport(Roboport::new())));
+        let ore = ItemStack::new(Item::IronOre, 5);
+        chest(&mut grid, 40, 10, LogisticMode::Provider, &[ore]);
+        chest(&mut grid, 12, 12, LogisticMode::Requester(vec![ore]), &[]);
+        assert!(assign_deliveries(&grid).is_empty());
+
+        assert!(grid.place_entity(30, 10, Entity::Roboport(Roboport::new())));
+        assert_eq!(grid.logistics.networks.len(), 1);
+        assert_eq!(assign_deliveries(&grid).len(), 2);
+    }
+
+    #[test]
+    fn networks_track_placed_and_removed_chests() {
+        let mut grid = Grid::new();
+        assert!(grid.place_entity(10, 10, Entity::Roboport(Roboport::new())));
+        let ore = ItemStack::new(Item::IronOre, 1);
+        chest(&mut grid, 12, 12, LogisticMode::Requester(vec![ore]), &[]);
+        chest(&mut grid, 8, 8, LogisticMode::Provider, &[ore]);
+        assert!(grid.place_entity(9, 9, Entity::Chest(Chest::new())));
+        assert_eq!(grid.logistics.networks[0].chests, vec![(8, 8), (12, 12)]);
+        assert_eq!(assign_deliveries(&grid).len(), 1);
+
+        grid.clear_tile(8, 8);
+        assert_eq!(grid.logistics.networks[0].chests, vec![(12, 12)]);
+        assert!(assign_deliveries(&grid).is_empty());
+    }
+
+    #[test]
+    fn robots_need_battery_for_the_trip() {
+        let mut grid = Grid::new();
+        assert!(grid.place_entity(10, 10, Entity::Roboport(Roboport::new())));
+        let ore = ItemStack::new(Item::IronOre, 1);
+        chest(&mut grid, 20, 20, LogisticMode::Provider, &[ore]);
+        chest(&mut grid, 0, 0, LogisticMode::Requester(vec![ore]), &[]);
+        for robot in grid.logistics.robots.iter_mut() {
+            robot.battery = 10.0;
+        }
+        assert!(assign_deliveries(&grid).is_empty());
+
+        // Docked robots charge until one can make the trip
+        let mut ticks = 0;
+        while grid
+            .logistics
+            .robots
+            .iter()
+            .all(|robot| robot.delivery.is_none())
+        {
+            grid.tick();
+            ticks += 1;
+        }
+        assert!(ticks > 40);
+        assert_eq!(grid.logistics.robots[0].delivery.unwrap().from, (20, 20));
+    }
+}
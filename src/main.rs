@@ -1,19 +1,91 @@
+mod balancer;
 mod camera;
+mod circuit;
+mod entity;
+mod export;
+mod file_watcher;
+mod fluid;
 mod grid;
 mod input;
+mod inventory;
+mod item;
+mod logistics;
+mod minimap;
+mod player;
+mod power;
+mod railway;
+mod recipe;
 mod renderer;
+mod research;
+mod router;
+mod sprites;
+mod stats;
+mod throughput;
+mod ui;
+mod worldgen;
 
-use std::{ffi::CString, time::Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use cgmath::{vec3, vec4, Deg, Matrix2, Matrix4, Rad};
+use cgmath::{vec4, Matrix4, Rad, SquareMatrix, Vector4};
 use gl::types::*;
+use image::DynamicImage;
 use input::{KeyboardState, MouseState};
-use renderer::{shader::Shader, texture::Texture, vertex_buffer::VertexBuffer};
+use renderer::{
+    atlas::{AtlasBuilder, UvRect},
+    framebuffer::Framebuffer,
+    shader::Shader,
+    sprite_batch::{Sprite, SpriteBatch, SpriteRenderer},
+    text::Font,
+    texture::Texture,
+    vertex_buffer::VertexBuffer,
+};
 
 use crate::{
-    camera::Camera,
-    grid::{Belt, Direction, Grid, Turn},
+    camera::{Camera, TileRect},
+    circuit::{
+        CircuitControl, Comparator, Condition, Operand, Signal, Signals, Terminal, WireColor,
+    },
+    entity::{
+        boiler::Boiler,
+        chest::Chest,
+        combinator::{
+            ArithmeticCombinator, ArithmeticOperation, ConstantCombinator, DeciderCombinator,
+            DeciderOutput,
+        },
+        drill::Drill,
+        furnace::Furnace,
+        generator::Generator,
+        inserter::Inserter,
+        lab::Lab,
+        offshore_pump::OffshorePump,
+        pipe::Pipe,
+        pole::Pole,
+        pump::Pump,
+        rail::Rail,
+        roboport::Roboport,
+        tank::Tank,
+        train_stop::TrainStop,
+        Entity,
+    },
+    export::ExportOptions,
+    file_watcher::FileWatcher,
+    fluid::Fluid,
+    grid::{Belt, BeltTier, Direction, Grid, SplitterSide, Underground},
+    inventory::Inventory,
+    inventory::ItemStack,
+    item::Item,
+    logistics::LogisticMode,
+    minimap::Minimap,
+    player::Player,
+    power::FULL_SATISFACTION,
+    railway::{Car, CarKind, ScheduleEntry, WaitCondition},
+    recipe::crafting_recipe_for,
     renderer::{debug::DebugCallback, vertex_array::VertexArray, VertexBufferElement},
+    research::{Research, TechTree},
+    sprites::{belt_item_sprites, belt_sprite, item_color, BeltAnimations},
+    stats::{Kind, ProductionStats, Window},
+    throughput::BeltFlow,
+    ui::{Layer, Rect, Ui},
 };
 
 // Vertex data
@@ -26,12 +98,106 @@ static QUAD_DATA: [GLfloat; 24] = [
     0.5, 0.5, 1.0, 1.0, // top right
 ];
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placeable {
+    Belt,
+    Chest,
+    Inserter,
+    Drill,
+    Furnace,
+    Pole,
+    Generator,
+    Pipe,
+    Pump,
+    Tank,
+    OffshorePump,
+    Boiler,
+    Splitter,
+    ConstantCombinator,
+    ArithmeticCombinator,
+    DeciderCombinator,
+    Rail,
+    CurvedRail,
+    RailSignal,
+    TrainStop,
+    Train,
+    Roboport,
+    ProviderChest,
+    RequesterChest,
+    StorageChest,
+    Lab,
+}
+
+// Everything in the build toolbar, in the order of the keys that select them
+const TOOLBAR: [Placeable; 26] = [
+    Placeable::Belt,
+    Placeable::Chest,
+    Placeable::Inserter,
+    Placeable::Drill,
+    Placeable::Furnace,
+    Placeable::Pole,
+    Placeable::Generator,
+    Placeable::Pipe,
+    Placeable::Pump,
+    Placeable::Tank,
+    Placeable::OffshorePump,
+    Placeable::Boiler,
+    Placeable::Splitter,
+    Placeable::ConstantCombinator,
+    Placeable::ArithmeticCombinator,
+    Placeable::DeciderCombinator,
+    Placeable::Rail,
+    Placeable::CurvedRail,
+    Placeable::RailSignal,
+    Placeable::TrainStop,
+    Placeable::Train,
+    Placeable::Lab,
+    Placeable::Roboport,
+    Placeable::ProviderChest,
+    Placeable::RequesterChest,
+    Placeable::StorageChest,
+];
+const TOOLBAR_COLUMNS: usize = 13;
+const TOOLBAR_SLOT_SIZE: f32 = 40.0;
+const TOOLBAR_BUTTON_WIDTH: f32 = 80.0;
+const TOOLBAR_HEIGHT: f32 =
+    TOOLBAR.len().div_ceil(TOOLBAR_COLUMNS) as f32 * TOOLBAR_SLOT_SIZE + 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ToolbarAction {
+    Select(Placeable),
+    Rotate,
+    Craft,
+}
+
+static STARTING_ITEMS: [(Item, u32); 8] = [
+    (Item::IronPlate, 100),
+    (Item::CopperPlate, 50),
+    (Item::StoneBrick, 20),
+    (Item::Belt, 50),
+    (Item::Inserter, 10),
+    (Item::Drill, 4),
+    (Item::Furnace, 4),
+    (Item::Chest, 4),
+];
+
+const TICK_MS: f64 = 1000.0 / 60.0;
+// Frames are drawn at most this often, whether or not a tick happened in between
+const FRAME_MS: f64 = 1000.0 / 240.0;
+const MAX_CATCH_UP_TICKS: u32 = 5;
+
 static LINE_DATA: [GLfloat; 4] = [
     0.0, 0.0, // origo
     1.0, 0.0,
 ];
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("export") {
+        export_from_command_line(&args[2..]);
+        return;
+    }
+
     let event_loop = glutin::event_loop::EventLoop::new();
     let window = glutin::window::WindowBuilder::new();
     let gl_window = glutin::ContextBuilder::new()
@@ -44,8 +210,7 @@ fn main() {
     // Load the OpenGL function pointers
     gl::load_with(|symbol| gl_window.get_proc_address(symbol));
 
-    let base_shader = Shader::from_file("base.vert", "base.frag");
-    let tex_shader = Shader::from_file("texture.vert", "texture.frag");
+    let mut base_shader = Shader::from_file("base.vert", "base.frag");
 
     unsafe {
         gl::Enable(gl::BLEND);
@@ -56,12 +221,16 @@ fn main() {
 
     let _debug_callback = unsafe {
         DebugCallback::new(|message| {
-            println!("{:?}", message);
+            println!("{}", message);
         })
     };
 
-    let tex = unsafe { Texture::from_path("assets/textures/belt-1.png", true) };
-    println!("{:?}", tex);
+    let mut atlas_builder = AtlasBuilder::new();
+    atlas_builder.add_directory("textures");
+    let (atlas_image, atlas) = atlas_builder.build();
+    let atlas_texture = unsafe { Texture::new(DynamicImage::ImageRgba8(atlas_image), false) };
+    let belt_animations = BeltAnimations::new(&atlas);
+    let font = Font::new(&atlas);
     let quad_va = unsafe {
         let vb = VertexBuffer::new(
             &QUAD_DATA,
@@ -73,30 +242,93 @@ fn main() {
         VertexArray::new(&[vb])
     };
 
+    let mut sprite_renderer = unsafe { SpriteRenderer::new(&QUAD_DATA) };
+    let mut shader_watcher = FileWatcher::new("assets/shaders", Duration::from_millis(500));
+    let mut sprite_batch = SpriteBatch::new();
+    let mut text_batch = SpriteBatch::new();
+    let mut ui = Ui::new();
+
     let line_va = unsafe {
         let vb = VertexBuffer::new(&LINE_DATA, vec![VertexBufferElement::floats(2)]);
         VertexArray::new(&[vb])
     };
 
     let mut camera = Camera::new();
-    camera.position.x = 300.0;
-    camera.position.y = 300.0;
 
     let mut keyboard_state = KeyboardState::new();
     let mut mouse_state = MouseState::new();
 
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        });
+    println!("World seed: {}", seed);
+
     let mut grid = Grid::new();
+    worldgen::generate_resources(&mut grid, seed);
+    grid.research = Research::new(TechTree::from_file("technologies.txt"));
+    grid.player = Player::new();
+    for (item, count) in STARTING_ITEMS {
+        grid.player.inventory.insert(item, count);
+    }
+    grid.player.x = 9.0;
+    grid.player.y = 9.0;
+    camera.position = player_camera_position(&grid.player);
+
+    let mut minimap = Minimap::new(grid.tiles[0].len(), grid.tiles.len());
+    let minimap_framebuffer =
+        unsafe { Framebuffer::new(minimap.columns as u32, minimap.rows as u32) };
 
     let mut zoom = 2.0;
 
     let mut debug_grid = true;
     let mut show_fps = false;
+    let mut paused = false;
 
     let mut last_update_time = Instant::now();
+    let mut last_tick_time = Instant::now();
+
+    // Shown on screen, the FPS counts the frames drawn in the last full second
+    let mut fps = 0;
+    let mut frame_count = 0;
+    let mut last_fps_time = Instant::now();
+    let mut tick_ms = 0.0;
+    let mut render_ms = 0.0;
 
     let mut current_belt = Belt::new();
 
     let mut is_placing = true;
+    let mut current_placeable = Placeable::Belt;
+
+    let mut hovered_tile = None;
+
+    // Belt routing tool, routes from the start tile to the hovered tile
+    let mut is_routing = false;
+    let mut allow_underground = false;
+    let mut route_start = None;
+    let mut route_preview: Option<Vec<(isize, isize, Belt)>> = None;
+    let mut route_key = None;
+
+    let mut throughput_analysis = None;
+
+    // Production panel showing rates over the selected window, hidden when None
+    let mut stats_window: Option<Window> = None;
+
+    // Wire tool, connects the output of the first clicked entity to the input of the second
+    let mut wire_color: Option<WireColor> = None;
+    let mut wire_start: Option<(isize, isize)> = None;
+
+    // Balancer verifier, checks the region between the corner and the clicked tile
+    let mut is_verifying = false;
+    let mut verify_corner: Option<(isize, isize)> = None;
+
+    // Train stops are named in the order they are placed
+    let mut train_stop_count = 0;
 
     event_loop.run(move |event, _, control_flow| {
         use glutin::event::{Event, MouseButton, VirtualKeyCode, WindowEvent};
@@ -121,8 +353,31 @@ fn main() {
 
         let now = Instant::now();
         let ms_since_last_update = (now - last_update_time).as_nanos() as f64 / 1_000_000.0;
-        if ms_since_last_update > 16.666 {
+        if ms_since_last_update > FRAME_MS {
             last_update_time = now;
+            // The player walks the same distance per tick at any frame rate
+            let frame_ticks = (ms_since_last_update / TICK_MS) as f32;
+
+            let window_size = gl_window.window().inner_size();
+            ui.begin(
+                mouse_state.position.x as f32,
+                window_size.height as f32 - mouse_state.position.y as f32,
+                mouse_state.was_pressed(MouseButton::Left),
+                mouse_state.is_pressed(MouseButton::Left),
+            );
+            let toolbar_action = build_toolbar(
+                &mut ui,
+                &grid.player,
+                current_placeable,
+                current_belt,
+                window_size.width as f32,
+            );
+            // Clicking the minimap centers the camera on that spot
+            let minimap_rect = minimap.rect(window_size.width as f32, window_size.height as f32);
+            if ui.panel(minimap_rect.shrink(-4.0)) {
+                camera.position =
+                    minimap.world_position(minimap_rect, ui.mouse_x, ui.mouse_y, 32.0);
+            }
 
             if keyboard_state.was_pressed(VirtualKeyCode::G) {
                 debug_grid = !debug_grid;
@@ -132,10 +387,303 @@ fn main() {
                 show_fps = !show_fps;
             }
 
-            if is_placing {
-                if keyboard_state.was_pressed(VirtualKeyCode::R) {
-                    current_belt.input = current_belt.input.rotate_clockwise();
-                    current_belt.output = current_belt.output.rotate_clockwise();
+            if is_placing
+                && (keyboard_state.was_pressed(VirtualKeyCode::R)
+                    || toolbar_action == Some(ToolbarAction::Rotate))
+            {
+                current_belt.input = current_belt.input.rotate_clockwise();
+                current_belt.output = current_belt.output.rotate_clockwise();
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::Key1) {
+                // Pressing it again switches to the next tier
+                if current_placeable == Placeable::Belt {
+                    current_belt.tier = current_belt.tier.next();
+                    println!("Placing {}", current_belt.tier.name());
+                }
+                current_placeable = Placeable::Belt;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key2) {
+                current_placeable = Placeable::Chest;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key3) {
+                current_placeable = Placeable::Inserter;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key4) {
+                current_placeable = Placeable::Drill;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key5) {
+                current_placeable = Placeable::Furnace;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key6) {
+                current_placeable = Placeable::Pole;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key7) {
+                current_placeable = Placeable::Generator;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key8) {
+                current_placeable = Placeable::Pipe;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key9) {
+                current_placeable = Placeable::Pump;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Key0) {
+                current_placeable = Placeable::Tank;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::O) {
+                current_placeable = Placeable::OffshorePump;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::B) {
+                current_placeable = Placeable::Boiler;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Y) {
+                current_placeable = Placeable::Splitter;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Z) {
+                current_placeable = match current_placeable {
+                    Placeable::ConstantCombinator => Placeable::ArithmeticCombinator,
+                    Placeable::ArithmeticCombinator => Placeable::DeciderCombinator,
+                    _ => Placeable::ConstantCombinator,
+                };
+            } else if keyboard_state.was_pressed(VirtualKeyCode::I) {
+                current_placeable = match current_placeable {
+                    Placeable::Rail => Placeable::CurvedRail,
+                    Placeable::CurvedRail => Placeable::RailSignal,
+                    _ => Placeable::Rail,
+                };
+            } else if keyboard_state.was_pressed(VirtualKeyCode::K) {
+                current_placeable = Placeable::TrainStop;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::L) {
+                current_placeable = Placeable::Train;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::Q) {
+                current_placeable = Placeable::Lab;
+            } else if keyboard_state.was_pressed(VirtualKeyCode::M) {
+                current_placeable = match current_placeable {
+                    Placeable::Roboport => Placeable::ProviderChest,
+                    Placeable::ProviderChest => Placeable::RequesterChest,
+                    Placeable::RequesterChest => Placeable::StorageChest,
+                    _ => Placeable::Roboport,
+                };
+            }
+
+            if let Some(ToolbarAction::Select(placeable)) = toolbar_action {
+                // Like the 1 key, selecting belts again switches to the next tier
+                if placeable == Placeable::Belt && current_placeable == Placeable::Belt {
+                    current_belt.tier = current_belt.tier.next();
+                    println!("Placing {}", current_belt.tier.name());
+                }
+                current_placeable = placeable;
+            }
+
+            // Cycles through the technologies that can be researched now
+            if keyboard_state.was_pressed(VirtualKeyCode::E) {
+                let available = grid.research.available();
+                let next = available
+                    .iter()
+                    .find(|&&index| Some(index) > grid.research.current)
+                    .or(available.first())
+                    .copied();
+                match next {
+                    Some(index) => {
+                        let name = grid.research.tree.technologies[index].name.clone();
+                        grid.research.start(&name);
+                        let technology = &grid.research.tree.technologies[index];
+                        let cost = technology
+                            .ingredients
+                            .iter()
+                            .map(|stack| format!("{} {}", stack.count, stack.item.name()))
+                            .collect::<Vec<_>>();
+                        println!(
+                            "Researching {} ({}/{} units of {}), unlocks {}",
+                            technology.name,
+                            grid.research.progress[index],
+                            technology.units,
+                            cost.join(", "),
+                            technology.unlocks.join(", ")
+                        );
+                    }
+                    None => println!("Nothing left to research"),
+                }
+            }
+
+            // Hand crafts one of whatever is being placed, with shift the last queued
+            // craft is cancelled instead
+            if keyboard_state.was_pressed(VirtualKeyCode::F1)
+                && keyboard_state.is_pressed(VirtualKeyCode::LShift)
+            {
+                if grid.player.cancel_craft() {
+                    println!(
+                        "Cancelled craft ({} queued)",
+                        grid.player.crafting_queue.len()
+                    );
+                }
+            } else if keyboard_state.was_pressed(VirtualKeyCode::F1)
+                || toolbar_action == Some(ToolbarAction::Craft)
+            {
+                let item = placeable_item(current_placeable, current_belt);
+                match crafting_recipe_for(item) {
+                    Some(recipe) if grid.player.queue_craft(recipe, 1, &mut grid.stats) > 0 => {
+                        let queued = grid.player.crafting_queue.len();
+                        println!("Crafting {} ({} queued)", item.name(), queued)
+                    }
+                    Some(recipe) => println!(
+                        "Not enough ingredients for {}, needs {}",
+                        item.name(),
+                        recipe
+                            .ingredients
+                            .iter()
+                            .map(|(item, count)| format!("{} {}", count, item.name()))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    None => println!("{} can't be crafted", item.name()),
+                }
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::F2) {
+                grid.player.creative = !grid.player.creative;
+                println!("Creative mode: {}", grid.player.creative);
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::Tab) {
+                println!("Inventory: {}", describe_inventory(&grid.player.inventory));
+                if let Some(recipe) = grid.player.crafting_queue.front() {
+                    println!(
+                        "Crafting {} ({}/{} ticks), {} queued",
+                        recipe.name,
+                        grid.player.crafting_progress,
+                        recipe.ticks,
+                        grid.player.crafting_queue.len()
+                    );
+                }
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::T) {
+                is_routing = !is_routing;
+                route_start = None;
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::U) {
+                allow_underground = !allow_underground;
+                println!("Underground routing: {}", allow_underground);
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::P) {
+                stats_window = match stats_window {
+                    None => Some(Window::FiveSeconds),
+                    Some(Window::FiveSeconds) => Some(Window::OneMinute),
+                    Some(Window::OneMinute) => Some(Window::TenMinutes),
+                    Some(Window::TenMinutes) => Some(Window::OneHour),
+                    Some(Window::OneHour) => None,
+                };
+                if let Some(window) = stats_window {
+                    println!("Production per minute over {}:", window.name());
+                    for item in Item::ALL.iter() {
+                        let produced = grid.stats.per_minute(window, Kind::Produced, *item);
+                        let consumed = grid.stats.per_minute(window, Kind::Consumed, *item);
+                        if produced > 0.0 || consumed > 0.0 {
+                            println!("  {}: +{:.1} -{:.1}", item.name(), produced, consumed);
+                        }
+                    }
+                }
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::C) {
+                let window = stats_window.unwrap_or(Window::OneMinute);
+                let path = format!("production_{}.csv", window.name());
+                match std::fs::write(&path, grid.stats.to_csv(window)) {
+                    Ok(()) => println!("Wrote production statistics to {}", path),
+                    Err(error) => println!("Failed to write {}: {}", path, error),
+                }
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::J) {
+                wire_color = match wire_color {
+                    None => Some(WireColor::Red),
+                    Some(WireColor::Red) => Some(WireColor::Green),
+                    Some(WireColor::Green) => None,
+                };
+                wire_start = None;
+                println!("Wire tool: {:?}", wire_color);
+            }
+
+            // Cycles the circuit mode of the hovered belt or inserter, the operation or
+            // comparison of a combinator (its output with shift) or what the train on the
+            // hovered rail waits for at its stops
+            if keyboard_state.was_pressed(VirtualKeyCode::N) {
+                let shift = keyboard_state.is_pressed(VirtualKeyCode::LShift);
+                if let Some(id) =
+                    hovered_tile.and_then(|(x, y)| grid.railway.train_at(x as isize, y as isize))
+                {
+                    let train = &mut grid.railway.trains[id];
+                    for entry in train.schedule.iter_mut() {
+                        entry.wait = entry.wait.next();
+                    }
+                    if let Some(entry) = train.schedule.first() {
+                        println!("Train waits for {:?}", entry.wait);
+                    }
+                } else if let Some((x, y)) = hovered_tile {
+                    let condition = Condition::new(
+                        Signal::Letter('A'),
+                        Comparator::Greater,
+                        Operand::Constant(0),
+                    );
+                    let read_contents = CircuitControl {
+                        read_contents: true,
+                        ..CircuitControl::new()
+                    };
+                    let enable_condition = CircuitControl {
+                        condition: Some(condition),
+                        ..CircuitControl::new()
+                    };
+                    match grid.get_entity_mut(x as isize, y as isize) {
+                        Some(Entity::Belt(belt)) => {
+                            belt.circuit = match belt.circuit {
+                                None => Some(read_contents),
+                                Some(control) if control.read_contents => Some(enable_condition),
+                                Some(_) => None,
+                            };
+                            println!("Belt circuit mode: {:?}", belt.circuit);
+                        }
+                        Some(Entity::Inserter(inserter)) => {
+                            inserter.circuit = match inserter.circuit {
+                                None => Some(enable_condition),
+                                Some(_) => None,
+                            };
+                            println!("Inserter circuit mode: {:?}", inserter.circuit);
+                        }
+                        Some(Entity::ArithmeticCombinator(combinator)) => {
+                            combinator.operation = combinator.operation.next();
+                            println!("Arithmetic combinator: {:?}", combinator.operation);
+                        }
+                        Some(Entity::DeciderCombinator(combinator)) if shift => {
+                            combinator.output_mode = combinator.output_mode.next();
+                            println!("Decider combinator output: {:?}", combinator.output_mode);
+                        }
+                        Some(Entity::DeciderCombinator(combinator)) => {
+                            let condition = &mut combinator.condition;
+                            condition.comparator = condition.comparator.next();
+                            println!("Decider combinator: {:?}", condition.comparator);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::V) {
+                is_verifying = !is_verifying;
+                verify_corner = None;
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::Escape) {
+                route_start = None;
+                verify_corner = None;
+                wire_start = None;
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::H) {
+                throughput_analysis = match throughput_analysis {
+                    Some(_) => None,
+                    None => {
+                        let analysis = throughput::analyze(&grid);
+                        println!("Belt bottlenecks: {}", analysis.bottlenecks().count());
+                        Some(analysis)
+                    }
+                };
+            }
+
+            if keyboard_state.was_pressed(VirtualKeyCode::X) {
+                let path = "throughput_report.csv";
+                match std::fs::write(path, throughput::analyze(&grid).report()) {
+                    Ok(()) => println!("Wrote throughput report to {}", path),
+                    Err(error) => println!("Failed to write {}: {}", path, error),
                 }
             }
 
@@ -143,16 +691,22 @@ fn main() {
                 is_placing = !is_placing;
             }
 
-            if keyboard_state.is_pressed(VirtualKeyCode::W) {
-                camera.move_vertical(10.0 / zoom);
-            } else if keyboard_state.is_pressed(VirtualKeyCode::S) {
-                camera.move_vertical(-10.0 / zoom);
+            if keyboard_state.was_pressed(VirtualKeyCode::Pause) {
+                paused = !paused;
+                println!("Paused: {}", paused);
             }
 
-            if keyboard_state.is_pressed(VirtualKeyCode::D) {
-                camera.move_horizontal(10.0 / zoom);
-            } else if keyboard_state.is_pressed(VirtualKeyCode::A) {
-                camera.move_horizontal(-10.0 / zoom);
+            // The camera follows the player again once they walk
+            let walk_x = keyboard_state.is_pressed(VirtualKeyCode::D) as i32
+                - keyboard_state.is_pressed(VirtualKeyCode::A) as i32;
+            let walk_y = keyboard_state.is_pressed(VirtualKeyCode::W) as i32
+                - keyboard_state.is_pressed(VirtualKeyCode::S) as i32;
+            if (walk_x, walk_y) != (0, 0) {
+                let player = &mut grid.player;
+                player.walk(walk_x as f32, walk_y as f32, frame_ticks);
+                player.x = player.x.clamp(0.0, grid.tiles[0].len() as f32 - 1.0);
+                player.y = player.y.clamp(0.0, grid.tiles.len() as f32 - 1.0);
+                camera.position = player_camera_position(player);
             }
 
             if mouse_state.scroll_delta < 0.0 {
@@ -161,8 +715,6 @@ fn main() {
                 zoom *= 1.0 + mouse_state.scroll_delta / 10.0;
             }
 
-            let window_size = gl_window.window().inner_size();
-
             let mut mouse_grid_pos = camera.position * zoom;
             mouse_grid_pos.x += mouse_state.position.x as f32 - window_size.width as f32 / 2.0;
             mouse_grid_pos.y += window_size.height as f32 / 2.0 - mouse_state.position.y as f32;
@@ -170,149 +722,783 @@ fn main() {
             let mouse_grid_x = (mouse_grid_pos.x / 32.0 / zoom).floor() as i32;
             let mouse_grid_y = (mouse_grid_pos.y / 32.0 / zoom).floor() as i32;
 
-            let mouse_in_grid =
-                mouse_grid_x >= 0 && mouse_grid_x < 128 && mouse_grid_y >= 0 && mouse_grid_y < 128;
-            if is_placing && mouse_in_grid {
+            // The mouse only reaches the world when it isn't being used by the UI
+            let mouse_in_grid = !ui.wants_mouse()
+                && (0..128).contains(&mouse_grid_x)
+                && (0..128).contains(&mouse_grid_y);
+            if let (Some(color), true) = (wire_color, mouse_in_grid) {
+                let mouse_tile = (mouse_grid_x as isize, mouse_grid_y as isize);
+                if mouse_state.was_pressed(MouseButton::Left) {
+                    match wire_start.take() {
+                        Some((x, y)) => {
+                            let from = (x, y, Terminal::Output);
+                            let to = (mouse_tile.0, mouse_tile.1, Terminal::Input);
+                            if !grid.connect_wire(from, to, color) {
+                                println!("Can't connect {:?} to {:?}", (x, y), mouse_tile);
+                            }
+                        }
+                        None => wire_start = Some(mouse_tile),
+                    }
+                }
+            } else if is_verifying && mouse_in_grid {
+                let mouse_tile = (mouse_grid_x as isize, mouse_grid_y as isize);
+                if mouse_state.was_pressed(MouseButton::Left) {
+                    match verify_corner.take() {
+                        Some(corner) => {
+                            let min = (corner.0.min(mouse_tile.0), corner.1.min(mouse_tile.1));
+                            let max = (corner.0.max(mouse_tile.0), corner.1.max(mouse_tile.1));
+                            let (inputs, outputs) = balancer::boundary_belts(&grid, min, max);
+                            println!(
+                                "Balancer with {} inputs and {} outputs",
+                                inputs.len(),
+                                outputs.len()
+                            );
+                            if inputs.is_empty() || outputs.is_empty() {
+                                println!("No input or output belts in the selection");
+                            } else {
+                                match balancer::verify(&grid, min, max, &inputs, &outputs) {
+                                    Ok(verification) => println!("{}", verification.describe()),
+                                    Err(error) => println!("{}", error),
+                                }
+                            }
+                        }
+                        None => verify_corner = Some(mouse_tile),
+                    }
+                }
+            } else if is_routing && mouse_in_grid {
+                let mouse_tile = (mouse_grid_x as isize, mouse_grid_y as isize);
+                if mouse_state.was_pressed(MouseButton::Left) {
+                    // Routed again rather than trusting the preview from an earlier frame
+                    match route_start.map(|start| {
+                        router::route(
+                            &grid,
+                            start,
+                            mouse_tile,
+                            current_belt.output,
+                            allow_underground,
+                        )
+                    }) {
+                        Some(Some(path))
+                            if path.iter().all(|&(x, y, _)| grid.player.can_reach(x, y)) =>
+                        {
+                            for (x, y, belt) in path {
+                                if !grid.place_belt(x, y, belt) {
+                                    println!("Could not place a belt at ({}, {}), stopping", x, y);
+                                    break;
+                                }
+                            }
+                            route_start = None;
+                        }
+                        Some(Some(_)) => println!("The route goes out of reach"),
+                        Some(None) => (),
+                        None => route_start = Some(mouse_tile),
+                    }
+                    route_key = None;
+                }
+
+                let key = route_start
+                    .map(|start| (start, mouse_tile, current_belt.output, allow_underground));
+                if key != route_key {
+                    route_key = key;
+                    route_preview = key.and_then(|(start, goal, direction, allow_underground)| {
+                        router::route(&grid, start, goal, direction, allow_underground)
+                    });
+                }
+            } else if is_placing
+                && mouse_in_grid
+                && grid
+                    .player
+                    .can_reach(mouse_grid_x as isize, mouse_grid_y as isize)
+            {
                 if mouse_state.is_pressed(MouseButton::Left) {
-                    grid.place_belt(mouse_grid_x as isize, mouse_grid_y as isize, current_belt);
+                    let (x, y) = (mouse_grid_x as isize, mouse_grid_y as isize);
+                    match current_placeable {
+                        Placeable::Belt => {
+                            grid.place_belt(x, y, current_belt);
+                        }
+                        Placeable::Splitter => {
+                            grid.place_splitter(x, y, current_belt.output);
+                        }
+                        Placeable::Rail | Placeable::CurvedRail => {
+                            if let Some(Entity::Rail(rail)) =
+                                placeable_entity(current_placeable, current_belt.output)
+                            {
+                                grid.place_rail(x, y, rail);
+                            }
+                        }
+                        Placeable::RailSignal => {
+                            grid.place_rail_signal(x, y, current_belt.output);
+                        }
+                        Placeable::TrainStop => {
+                            let name = format!("Stop {}", train_stop_count + 1);
+                            let stop = TrainStop::new(&name, current_belt.output);
+                            if grid.place_entity(x, y, Entity::TrainStop(stop)) {
+                                train_stop_count += 1;
+                            }
+                        }
+                        Placeable::Train => {
+                            let cars =
+                                vec![Car::locomotive(), Car::cargo_wagon(), Car::cargo_wagon()];
+                            let schedule = default_schedule(&grid);
+                            grid.place_train(x, y, current_belt.output, cars, schedule);
+                        }
+                        placeable => {
+                            if let Some(entity) = placeable_entity(placeable, current_belt.output) {
+                                grid.place_entity(x, y, entity);
+                            }
+                        }
+                    }
+                }
+
+                if mouse_state.is_pressed(MouseButton::Right)
+                    && !grid.clear_tile(mouse_grid_x as usize, mouse_grid_y as usize)
+                    && mouse_state.was_pressed(MouseButton::Right)
+                {
+                    println!("Not enough inventory space to pick that up");
                 }
+            }
 
-                if mouse_state.is_pressed(MouseButton::Right) {
-                    grid.clear_tile(mouse_grid_x as usize, mouse_grid_y as usize);
+            // Ticks at a fixed rate, skipping ahead rather than falling further behind
+            let tick = Duration::from_secs_f64(TICK_MS / 1000.0);
+            let mut ticks = 0;
+            while !paused && now - last_tick_time >= tick {
+                let tick_start = Instant::now();
+                grid.tick();
+                tick_ms = (Instant::now() - tick_start).as_secs_f64() * 1000.0;
+                last_tick_time += tick;
+                ticks += 1;
+                if ticks == MAX_CATCH_UP_TICKS {
+                    last_tick_time = now;
+                }
+            }
+            if paused {
+                last_tick_time = now;
+            }
+            if ticks > 0 && throughput_analysis.is_some() {
+                throughput_analysis = Some(throughput::analyze(&grid));
+            }
+            // How far into the next tick this frame is, items are drawn between ticks
+            let tick_progress =
+                ((now - last_tick_time).as_secs_f64() * 1000.0 / TICK_MS).min(1.0) as f32;
+
+            hovered_tile = Some((mouse_grid_x, mouse_grid_y)).filter(|_| mouse_in_grid);
+
+            // Only tiles on screen are drawn
+            let visible = camera.visible_tiles(
+                window_size.width as f32,
+                window_size.height as f32,
+                zoom,
+                32.0,
+                grid.tiles[0].len(),
+                grid.tiles.len(),
+            );
+
+            if keyboard_state.was_pressed(VirtualKeyCode::F3) {
+                let path = format!("export_{}.png", grid.ticks);
+                match export::export(&grid, visible, 32, &path) {
+                    Ok(()) => println!("Exported the view to {}", path),
+                    Err(error) => println!("{}", error),
+                }
+            }
+
+            // Saved shaders are rebuilt, a broken one keeps drawing with its old program
+            let changed_files = shader_watcher.changed();
+            for shader in [&mut base_shader, &mut sprite_renderer.shader] {
+                if changed_files.iter().any(|file| shader.uses(file)) {
+                    match shader.reload() {
+                        Ok(()) => println!("Reloaded shaders {}", changed_files.join(", ")),
+                        Err(error) => println!("{}", error),
+                    }
                 }
             }
 
             let start = Instant::now();
             unsafe {
+                let changed_tiles = grid.take_changed_tiles();
+                // The route preview is made again once something is built or removed
+                if !changed_tiles.is_empty() {
+                    route_key = None;
+                }
+                let changes = minimap.changes(&changed_tiles, |x, y| minimap_color(&grid, x, y));
+                if !changes.is_empty() {
+                    minimap_framebuffer.bind();
+                    quad_va.bind();
+                    base_shader.enable();
+                    base_shader.set_mat4("view", Matrix4::identity());
+                    base_shader.set_mat4(
+                        "projection",
+                        cgmath::ortho(
+                            0.0,
+                            minimap.columns as f32,
+                            0.0,
+                            minimap.rows as f32,
+                            1.0,
+                            -1.0,
+                        ),
+                    );
+                    for (x, y, color) in changes {
+                        let model = Matrix4::from_translation(cgmath::vec3(
+                            x as f32 + 0.5,
+                            y as f32 + 0.5,
+                            0.0,
+                        ));
+                        base_shader.set_mat4("model", model);
+                        base_shader.set_vec4("color", color);
+                        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                    }
+                    minimap_framebuffer.unbind(window_size.width, window_size.height);
+                }
+
                 gl::ClearColor(0.3, 0.3, 0.6, 1.0);
                 gl::Clear(gl::COLOR_BUFFER_BIT);
 
-                tex.bind_to_unit(0);
-
-                tex_shader.enable();
-                tex_shader.set_int(&CString::new("atlas_size").unwrap(), 2);
-                tex_shader.set_mat4(&CString::new("view").unwrap(), camera.view_matrix());
-                tex_shader.set_mat4(
-                    &CString::new("projection").unwrap(),
+                quad_va.bind();
+                base_shader.enable();
+                base_shader.set_mat4("view", camera.view_matrix());
+                base_shader.set_mat4(
+                    "projection",
                     camera.projection_matrix(
                         window_size.width as f32,
                         window_size.height as f32,
                         zoom,
                     ),
                 );
-                quad_va.bind();
-
-                for (y, row) in grid.tiles.iter().enumerate() {
-                    for (x, tile) in row.iter().enumerate() {
-                        if let Some(belt) = tile {
+                for y in visible.min_y..visible.max_y {
+                    for x in visible.min_x..visible.max_x {
+                        if let Some(resource) = grid.resources[y][x] {
                             let model_scale = Matrix4::from_nonuniform_scale(32.0, 32.0, 0.0);
                             let model_trans = Matrix4::from_translation(cgmath::vec3(
                                 16.0 + 32.0 * x as f32,
                                 16.0 + 32.0 * y as f32,
                                 0.0,
                             ));
-                            let model = model_trans * model_scale;
-
-                            let tex_angle = match belt.input {
-                                Direction::West => 90.0,
-                                Direction::North => 180.0,
-                                Direction::East => 270.0,
-                                Direction::South => 0.0,
-                            };
-                            let tex_rot = Matrix2::from_angle(Deg(tex_angle));
-
-                            let atlas_index = match belt.turn() {
-                                Turn::Left => 0,
-                                Turn::Forward => 2,
-                                Turn::Right => 3,
-                            };
+                            base_shader.set_mat4("model", model_trans * model_scale);
+                            base_shader.set_vec4("color", item_color(resource.item));
+                            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                        }
+                    }
+                }
 
-                            tex_shader.set_mat4(&CString::new("model").unwrap(), model);
-                            tex_shader.set_vec4(
-                                &CString::new("color").unwrap(),
+                sprite_batch.clear();
+                for y in visible.min_y..visible.max_y {
+                    for x in visible.min_x..visible.max_x {
+                        if let Some(Entity::Belt(belt)) = &grid.tiles[y][x] {
+                            sprite_batch.push(belt_sprite(
+                                &belt_animations,
+                                grid.ticks,
+                                x as isize,
+                                y as isize,
+                                belt,
                                 vec4(1.0, 1.0, 1.0, 1.0),
-                            );
-                            tex_shader.set_mat2(&CString::new("tex_rot").unwrap(), tex_rot);
-                            tex_shader.set_int(&CString::new("atlas_index").unwrap(), atlas_index);
+                            ));
+                        }
+                    }
+                }
 
-                            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                let item_uv = atlas.sprite("item");
+                for y in visible.min_y..visible.max_y {
+                    for x in visible.min_x..visible.max_x {
+                        if let Some(Entity::Belt(belt)) = &grid.tiles[y][x] {
+                            for sprite in belt_item_sprites(belt, x, y, item_uv, tick_progress) {
+                                sprite_batch.push(sprite);
+                            }
                         }
                     }
                 }
 
-                if is_placing && mouse_in_grid {
+                if let (true, Some(path)) = (is_routing, &route_preview) {
+                    for (x, y, belt) in path.iter() {
+                        sprite_batch.push(belt_sprite(
+                            &belt_animations,
+                            grid.ticks,
+                            *x,
+                            *y,
+                            belt,
+                            vec4(1.0, 1.0, 1.0, 0.4),
+                        ));
+                    }
+                }
+
+                if !is_routing
+                    && is_placing
+                    && mouse_in_grid
+                    && current_placeable == Placeable::Belt
+                {
                     let current_belt = grid.calculate_belt_position(
                         mouse_grid_x as isize,
                         mouse_grid_y as isize,
                         current_belt,
                     );
-                    let model_scale = Matrix4::from_nonuniform_scale(32.0, 32.0, 0.0);
-                    let model_trans = Matrix4::from_translation(cgmath::vec3(
-                        16.0 + 32.0 * mouse_grid_x as f32,
-                        16.0 + 32.0 * mouse_grid_y as f32,
-                        0.0,
+                    sprite_batch.push(belt_sprite(
+                        &belt_animations,
+                        grid.ticks,
+                        mouse_grid_x as isize,
+                        mouse_grid_y as isize,
+                        &current_belt,
+                        vec4(1.0, 1.0, 1.0, 0.4),
                     ));
-                    let model = model_trans * model_scale;
+                }
 
-                    let tex_angle = match current_belt.input {
-                        Direction::West => 90.0,
-                        Direction::North => 180.0,
-                        Direction::East => 270.0,
-                        Direction::South => 0.0,
-                    };
-                    let tex_rot = Matrix2::from_angle(Deg(tex_angle));
+                sprite_renderer.draw(
+                    &sprite_batch,
+                    &atlas_texture,
+                    camera.view_matrix(),
+                    camera.projection_matrix(
+                        window_size.width as f32,
+                        window_size.height as f32,
+                        zoom,
+                    ),
+                );
 
-                    let atlas_index = match current_belt.turn() {
-                        Turn::Left => 0,
-                        Turn::Forward => 2,
-                        Turn::Right => 3,
+                base_shader.enable();
+                base_shader.set_mat4("view", camera.view_matrix());
+                base_shader.set_mat4(
+                    "projection",
+                    camera.projection_matrix(
+                        window_size.width as f32,
+                        window_size.height as f32,
+                        zoom,
+                    ),
+                );
+                for y in visible.min_y..visible.max_y {
+                    for x in visible.min_x..visible.max_x {
+                        let shapes = grid.tiles[y][x]
+                            .as_ref()
+                            .map(entity_shapes)
+                            .unwrap_or_default();
+                        for (model_shape, color) in shapes {
+                            let model_trans = Matrix4::from_translation(cgmath::vec3(
+                                16.0 + 32.0 * x as f32,
+                                16.0 + 32.0 * y as f32,
+                                0.0,
+                            ));
+                            base_shader.set_mat4("model", model_trans * model_shape);
+                            base_shader.set_vec4("color", color);
+                            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                        }
+                    }
+                }
+
+                for train in grid.railway.trains.iter() {
+                    for (car, &(x, y)) in train.cars.iter().zip(train.tiles.iter()) {
+                        if !visible.contains(x, y) {
+                            continue;
+                        }
+                        let color = match car.kind {
+                            CarKind::Locomotive => vec4(0.7, 0.15, 0.1, 1.0),
+                            CarKind::CargoWagon => vec4(0.5, 0.5, 0.55, 1.0),
+                        };
+                        let model = Matrix4::from_translation(cgmath::vec3(
+                            16.0 + 32.0 * x as f32,
+                            16.0 + 32.0 * y as f32,
+                            0.0,
+                        )) * Matrix4::from_nonuniform_scale(24.0, 24.0, 0.0);
+                        base_shader.set_mat4("model", model);
+                        base_shader.set_vec4("color", color);
+                        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                    }
+                }
+
+                for robot in grid.logistics.robots.iter().filter(|robot| {
+                    !robot.is_docked()
+                        && visible.contains(robot.x.round() as isize, robot.y.round() as isize)
+                }) {
+                    let color = match robot.cargo {
+                        Some(stack) => item_color(stack.item),
+                        None => vec4(0.85, 0.85, 0.8, 1.0),
                     };
+                    let model = Matrix4::from_translation(cgmath::vec3(
+                        16.0 + 32.0 * robot.x,
+                        16.0 + 32.0 * robot.y,
+                        0.0,
+                    )) * Matrix4::from_nonuniform_scale(8.0, 8.0, 0.0);
+                    base_shader.set_mat4("model", model);
+                    base_shader.set_vec4("color", color);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                }
+
+                let player = &grid.player;
+                let model = Matrix4::from_translation(cgmath::vec3(
+                    16.0 + 32.0 * player.x,
+                    16.0 + 32.0 * player.y,
+                    0.0,
+                )) * Matrix4::from_nonuniform_scale(20.0, 20.0, 0.0);
+                base_shader.set_mat4("model", model);
+                base_shader.set_vec4("color", vec4(1.0, 0.6, 0.1, 1.0));
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
 
-                    tex_shader.set_vec4(&CString::new("color").unwrap(), vec4(1.0, 1.0, 1.0, 0.4));
-                    tex_shader.set_mat4(&CString::new("model").unwrap(), model);
-                    tex_shader.set_mat2(&CString::new("tex_rot").unwrap(), tex_rot);
-                    tex_shader.set_int(&CString::new("atlas_index").unwrap(), atlas_index);
+                // Signals are red while the block they lead into is occupied
+                for &(x, y, side) in grid.railway.signals.iter() {
+                    if !visible.contains(x, y) {
+                        continue;
+                    }
+                    let (next_x, next_y) = side.offset(x, y);
+                    let occupied = grid
+                        .railway
+                        .block_at(next_x, next_y)
+                        .is_some_and(|block| grid.railway.is_block_occupied(block, None));
+                    let color = if occupied {
+                        vec4(0.9, 0.1, 0.1, 1.0)
+                    } else {
+                        vec4(0.1, 0.9, 0.1, 1.0)
+                    };
+                    let (dx, dy) = side.offset(0, 0);
+                    let model = Matrix4::from_translation(cgmath::vec3(
+                        16.0 + 32.0 * x as f32 + 14.0 * dx as f32,
+                        16.0 + 32.0 * y as f32 + 14.0 * dy as f32,
+                        0.0,
+                    )) * Matrix4::from_nonuniform_scale(8.0, 8.0, 0.0);
+                    base_shader.set_mat4("model", model);
+                    base_shader.set_vec4("color", color);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                }
 
+                if let (Some(corner), Some((hovered_x, hovered_y))) = (verify_corner, hovered_tile)
+                {
+                    let hovered = (hovered_x as isize, hovered_y as isize);
+                    let min = (corner.0.min(hovered.0), corner.1.min(hovered.1));
+                    let max = (corner.0.max(hovered.0), corner.1.max(hovered.1));
+                    let (width, height) = (max.0 - min.0 + 1, max.1 - min.1 + 1);
+                    let model = Matrix4::from_translation(cgmath::vec3(
+                        32.0 * (min.0 as f32 + width as f32 / 2.0),
+                        32.0 * (min.1 as f32 + height as f32 / 2.0),
+                        0.0,
+                    )) * Matrix4::from_nonuniform_scale(
+                        32.0 * width as f32,
+                        32.0 * height as f32,
+                        0.0,
+                    );
+                    base_shader.set_mat4("model", model);
+                    base_shader.set_vec4("color", vec4(0.3, 0.5, 1.0, 0.25));
                     gl::DrawArrays(gl::TRIANGLES, 0, 6);
                 }
 
+                if let Some(analysis) = &throughput_analysis {
+                    for belt in analysis
+                        .belts
+                        .iter()
+                        .filter(|belt| visible.contains(belt.x, belt.y))
+                    {
+                        let model = Matrix4::from_translation(cgmath::vec3(
+                            16.0 + 32.0 * belt.x as f32,
+                            16.0 + 32.0 * belt.y as f32,
+                            0.0,
+                        )) * Matrix4::from_nonuniform_scale(32.0, 32.0, 0.0);
+                        base_shader.set_mat4("model", model);
+                        base_shader.set_vec4("color", throughput_color(belt));
+                        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                    }
+                }
+
+                let ghost_entity = placeable_entity(current_placeable, current_belt.output);
+                if let (true, Some(path)) = (is_routing, &route_preview) {
+                    for (x, y, belt) in path.iter() {
+                        for (model_shape, color) in entity_shapes(&Entity::Belt(*belt)) {
+                            let model_trans = Matrix4::from_translation(cgmath::vec3(
+                                16.0 + 32.0 * *x as f32,
+                                16.0 + 32.0 * *y as f32,
+                                0.0,
+                            ));
+                            base_shader.set_mat4("model", model_trans * model_shape);
+                            base_shader.set_vec4("color", vec4(color.x, color.y, color.z, 0.4));
+                            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                        }
+                    }
+                }
+
+                if let (false, true, true, Some(ghost_entity)) =
+                    (is_routing, is_placing, mouse_in_grid, ghost_entity)
+                {
+                    for (model_shape, color) in entity_shapes(&ghost_entity) {
+                        let model_trans = Matrix4::from_translation(cgmath::vec3(
+                            16.0 + 32.0 * mouse_grid_x as f32,
+                            16.0 + 32.0 * mouse_grid_y as f32,
+                            0.0,
+                        ));
+                        base_shader.set_mat4("model", model_trans * model_shape);
+                        base_shader.set_vec4("color", vec4(color.x, color.y, color.z, 0.4));
+                        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                    }
+                }
+
+                line_va.bind();
+                base_shader.set_vec4("color", vec4(0.25, 0.15, 0.05, 1.0));
+                for &((from_x, from_y), (to_x, to_y)) in grid.power.wires.iter() {
+                    let dx = 32.0 * (to_x - from_x) as f32;
+                    let dy = 32.0 * (to_y - from_y) as f32;
+                    let model_scale =
+                        Matrix4::from_nonuniform_scale((dx * dx + dy * dy).sqrt(), 0.0, 0.0);
+                    let model_rot = Matrix4::from_angle_z(Rad(dy.atan2(dx)));
+                    let model_trans = Matrix4::from_translation(cgmath::vec3(
+                        16.0 + 32.0 * from_x as f32,
+                        16.0 + 32.0 * from_y as f32,
+                        0.0,
+                    ));
+                    base_shader.set_mat4("model", model_trans * model_rot * model_scale);
+                    gl::DrawArrays(gl::LINES, 0, 2);
+                }
+
+                // Circuit wires are drawn slightly apart so both colors stay visible
+                for &((from_x, from_y, _), (to_x, to_y, _), color) in grid.circuit.wires.iter() {
+                    let (offset, wire_color) = match color {
+                        WireColor::Red => (3.0, vec4(0.8, 0.1, 0.1, 1.0)),
+                        WireColor::Green => (-3.0, vec4(0.1, 0.7, 0.1, 1.0)),
+                    };
+                    let dx = 32.0 * (to_x - from_x) as f32;
+                    let dy = 32.0 * (to_y - from_y) as f32;
+                    let model_scale =
+                        Matrix4::from_nonuniform_scale((dx * dx + dy * dy).sqrt(), 0.0, 0.0);
+                    let model_rot = Matrix4::from_angle_z(Rad(dy.atan2(dx)));
+                    let model_trans = Matrix4::from_translation(cgmath::vec3(
+                        16.0 + offset + 32.0 * from_x as f32,
+                        16.0 + offset + 32.0 * from_y as f32,
+                        0.0,
+                    ));
+                    base_shader.set_mat4("model", model_trans * model_rot * model_scale);
+                    base_shader.set_vec4("color", wire_color);
+                    gl::DrawArrays(gl::LINES, 0, 2);
+                }
+
                 if debug_grid {
                     line_va.bind();
                     base_shader.enable();
-                    base_shader.set_mat4(&CString::new("view").unwrap(), camera.view_matrix());
+                    base_shader.set_mat4("view", camera.view_matrix());
                     base_shader.set_mat4(
-                        &CString::new("projection").unwrap(),
+                        "projection",
                         camera.projection_matrix(
                             window_size.width as f32,
                             window_size.height as f32,
                             zoom,
                         ),
                     );
-                    base_shader.set_vec3(&CString::new("color").unwrap(), vec3(0.0, 0.0, 0.0));
-                    for y in 0..=128 {
-                        let model_scale = Matrix4::from_nonuniform_scale(32.0 * 128.0, 0.0, 0.0);
-                        let model_trans =
-                            Matrix4::from_translation(cgmath::vec3(0.0, y as f32 * 32.0, 0.0));
+                    base_shader.set_vec4("color", vec4(0.0, 0.0, 0.0, 1.0));
+                    // Lines along the edges of the visible tiles
+                    let width = (visible.max_x - visible.min_x) as f32;
+                    let height = (visible.max_y - visible.min_y) as f32;
+                    for y in visible.min_y..=visible.max_y {
+                        let model_scale = Matrix4::from_nonuniform_scale(32.0 * width, 0.0, 0.0);
+                        let model_trans = Matrix4::from_translation(cgmath::vec3(
+                            visible.min_x as f32 * 32.0,
+                            y as f32 * 32.0,
+                            0.0,
+                        ));
                         let model = model_trans * model_scale;
-                        base_shader.set_mat4(&CString::new("model").unwrap(), model);
+                        base_shader.set_mat4("model", model);
                         gl::DrawArrays(gl::LINES, 0, 2);
                     }
-                    for x in 0..=128 {
+                    for x in visible.min_x..=visible.max_x {
                         let rect_rot = std::f32::consts::FRAC_PI_2;
                         let model_rot = Matrix4::from_angle_z(Rad(rect_rot));
-                        let model_scale = Matrix4::from_nonuniform_scale(32.0 * 128.0, 0.0, 0.0);
-                        let model_trans =
-                            Matrix4::from_translation(cgmath::vec3(x as f32 * 32.0, 0.0, 0.0));
+                        let model_scale = Matrix4::from_nonuniform_scale(32.0 * height, 0.0, 0.0);
+                        let model_trans = Matrix4::from_translation(cgmath::vec3(
+                            x as f32 * 32.0,
+                            visible.min_y as f32 * 32.0,
+                            0.0,
+                        ));
                         let model = model_trans * model_rot * model_scale;
-                        base_shader.set_mat4(&CString::new("model").unwrap(), model);
+                        base_shader.set_mat4("model", model);
                         gl::DrawArrays(gl::LINES, 0, 2);
                     }
                 }
+
+                text_batch.clear();
+                for y in visible.min_y..visible.max_y {
+                    for x in visible.min_x..visible.max_x {
+                        if let Some(Entity::TrainStop(stop)) = &grid.tiles[y][x] {
+                            let (width, _) = Font::measure(&stop.name, 1.0);
+                            font.draw(
+                                &mut text_batch,
+                                &stop.name,
+                                32.0 * x as f32 + 16.0 - width / 2.0,
+                                32.0 * (y + 1) as f32 + 10.0,
+                                1.0,
+                                vec4(1.0, 1.0, 1.0, 1.0),
+                            );
+                        }
+                    }
+                }
+                sprite_renderer.draw(
+                    &text_batch,
+                    &atlas_texture,
+                    camera.view_matrix(),
+                    camera.projection_matrix(
+                        window_size.width as f32,
+                        window_size.height as f32,
+                        zoom,
+                    ),
+                );
+
+                let screen_projection = cgmath::ortho(
+                    0.0,
+                    window_size.width as f32,
+                    0.0,
+                    window_size.height as f32,
+                    1.0,
+                    -1.0,
+                );
+                if let Some(window) = stats_window {
+                    quad_va.bind();
+                    base_shader.enable();
+                    base_shader.set_mat4("view", Matrix4::identity());
+                    base_shader.set_mat4("projection", screen_projection);
+                    // Kept above the toolbar
+                    let above_toolbar =
+                        Matrix4::from_translation(cgmath::vec3(0.0, TOOLBAR_HEIGHT, 0.0));
+                    for (model, color) in production_panel(&grid.stats, window) {
+                        base_shader.set_mat4("model", above_toolbar * model);
+                        base_shader.set_vec4("color", color);
+                        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                    }
+                }
+
+                let mut overlay = String::new();
+                if show_fps {
+                    overlay += &format!(
+                        "FPS: {}\nRender: {:.2}ms\nTick: {:.2}ms\nSprites: {}\n",
+                        fps,
+                        render_ms,
+                        tick_ms,
+                        sprite_batch.len()
+                    );
+                }
+                if let Some((x, y)) = hovered_tile {
+                    overlay += &format!("Tile: {}, {}", x, y);
+                    if let Some(entity) = grid.get_entity(x as isize, y as isize) {
+                        overlay += &format!(" ({})", entity.name());
+                    }
+                    if let Some(description) = describe_entity(&grid, x as isize, y as isize) {
+                        overlay += &format!("\n{}", description);
+                    }
+                    let flow = throughput_analysis
+                        .as_ref()
+                        .and_then(|analysis| analysis.get(x as isize, y as isize));
+                    if let Some(flow) = flow {
+                        overlay +=
+                            &format!("\nFlow: {:.2}/{:.2} items/s", flow.flow, flow.capacity);
+                    }
+                }
+                quad_va.bind();
+                base_shader.enable();
+                base_shader.set_mat4("view", Matrix4::identity());
+                base_shader.set_mat4("projection", screen_projection);
+                text_batch.clear();
+                draw_ui_layer(
+                    &ui.layer,
+                    &base_shader,
+                    current_belt,
+                    &font,
+                    &mut text_batch,
+                );
+                sprite_renderer.draw(
+                    &text_batch,
+                    &atlas_texture,
+                    Matrix4::identity(),
+                    screen_projection,
+                );
+
+                // The framebuffer has its first row at the bottom
+                text_batch.clear();
+                text_batch.push(Sprite {
+                    x: minimap_rect.x + minimap_rect.width / 2.0,
+                    y: minimap_rect.y + minimap_rect.height / 2.0,
+                    width: minimap_rect.width,
+                    height: minimap_rect.height,
+                    rotation: 0.0,
+                    uv: UvRect {
+                        min_x: 0.0,
+                        min_y: 1.0,
+                        max_x: 1.0,
+                        max_y: 0.0,
+                    },
+                    tint: vec4(1.0, 1.0, 1.0, 1.0),
+                });
+                sprite_renderer.draw(
+                    &text_batch,
+                    &minimap_framebuffer.texture,
+                    Matrix4::identity(),
+                    screen_projection,
+                );
+
+                quad_va.bind();
+                base_shader.enable();
+                let view = minimap.view_rect(
+                    minimap_rect,
+                    &camera,
+                    window_size.width as f32,
+                    window_size.height as f32,
+                    zoom,
+                    32.0,
+                );
+                let outline = [
+                    Rect::new(view.x, view.y, view.width, 1.0),
+                    Rect::new(view.x, view.y + view.height - 1.0, view.width, 1.0),
+                    Rect::new(view.x, view.y, 1.0, view.height),
+                    Rect::new(view.x + view.width - 1.0, view.y, 1.0, view.height),
+                ];
+                base_shader.set_vec4("color", vec4(1.0, 1.0, 1.0, 1.0));
+                for rect in outline {
+                    let model = Matrix4::from_translation(cgmath::vec3(
+                        rect.x + rect.width / 2.0,
+                        rect.y + rect.height / 2.0,
+                        0.0,
+                    )) * Matrix4::from_nonuniform_scale(rect.width, rect.height, 0.0);
+                    base_shader.set_mat4("model", model);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                }
+
+                quad_va.bind();
+                base_shader.enable();
+                text_batch.clear();
+                draw_ui_layer(
+                    &ui.tooltip,
+                    &base_shader,
+                    current_belt,
+                    &font,
+                    &mut text_batch,
+                );
+
+                // Shaders that failed to reload show their log below the overlay
+                let shader_errors = [&base_shader, &sprite_renderer.shader]
+                    .iter()
+                    .filter_map(|shader| shader.error.as_deref())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let (_, overlay_height) = Font::measure(&overlay, 2.0);
+                let top = window_size.height as f32 - 8.0;
+                let texts = [
+                    (&overlay, top, vec4(1.0, 1.0, 1.0, 1.0)),
+                    (
+                        &shader_errors,
+                        top - overlay_height - 8.0,
+                        vec4(1.0, 0.3, 0.3, 1.0),
+                    ),
+                ];
+                // Drawn twice with a shadow so it stays readable over light tiles
+                for (text, text_y, color) in texts {
+                    font.draw(
+                        &mut text_batch,
+                        text,
+                        10.0,
+                        text_y - 2.0,
+                        2.0,
+                        vec4(0.0, 0.0, 0.0, 0.8),
+                    );
+                    font.draw(&mut text_batch, text, 8.0, text_y, 2.0, color);
+                }
+                sprite_renderer.draw(
+                    &text_batch,
+                    &atlas_texture,
+                    Matrix4::identity(),
+                    screen_projection,
+                );
             }
             let dur = Instant::now() - start;
-            let ms = dur.as_nanos() as f64 / 1_000_000.0;
-            if show_fps {
-                println!("Render time: {}ms", ms);
+            render_ms = dur.as_nanos() as f64 / 1_000_000.0;
+            frame_count += 1;
+            if now - last_fps_time >= Duration::from_secs(1) {
+                fps = frame_count;
+                frame_count = 0;
+                last_fps_time = now;
             }
             gl_window.swap_buffers().unwrap();
             keyboard_state.clear_momentary_state();
@@ -320,3 +1506,701 @@ fn main() {
         }
     });
 }
+
+// `clonerio export <file> ...` draws a newly generated world into an image without
+// opening a window
+fn export_from_command_line(args: &[String]) {
+    let options = match ExportOptions::parse(args) {
+        Ok(options) => options,
+        Err(error) => {
+            println!("{}", error);
+            println!(
+                "Usage: clonerio export <file.png> [--seed N] [--scale PIXELS-PER-TILE] \
+                 [--region X Y WIDTH HEIGHT]"
+            );
+            println!("Renders a freshly generated world, nothing built in a game is included");
+            std::process::exit(1);
+        }
+    };
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    });
+
+    let mut grid = Grid::new();
+    worldgen::generate_resources(&mut grid, seed);
+    let region = options.region.unwrap_or(TileRect {
+        min_x: 0,
+        min_y: 0,
+        max_x: grid.tiles[0].len(),
+        max_y: grid.tiles.len(),
+    });
+    match export::export(&grid, region, options.scale, &options.path) {
+        Ok(()) => println!("Wrote world seed {} to {}", seed, options.path),
+        Err(error) => {
+            println!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Entities without a texture are drawn as coloured rectangles, relative to the tile center
+fn entity_shapes(entity: &Entity) -> Vec<(Matrix4<f32>, Vector4<f32>)> {
+    let square =
+        |size: f32, color: Vector4<f32>| (Matrix4::from_nonuniform_scale(size, size, 0.0), color);
+    // Thin rectangle from the tile center towards one side
+    let arm = |side: Direction, width: f32, color: Vector4<f32>| {
+        let (dx, dy) = side.offset(0, 0);
+        let (scale_x, scale_y) = if dx != 0 {
+            (16.0, width)
+        } else {
+            (width, 16.0)
+        };
+        let model_trans =
+            Matrix4::from_translation(cgmath::vec3(dx as f32 * 8.0, dy as f32 * 8.0, 0.0));
+        (
+            model_trans * Matrix4::from_nonuniform_scale(scale_x, scale_y, 0.0),
+            color,
+        )
+    };
+
+    match entity {
+        Entity::Belt(belt) => {
+            let mut shapes = match belt.underground {
+                Some(Underground::Entrance) => {
+                    vec![arm(belt.output, 24.0, vec4(0.1, 0.1, 0.1, 0.8))]
+                }
+                Some(Underground::Exit) => vec![arm(belt.input, 24.0, vec4(0.1, 0.1, 0.1, 0.8))],
+                None => Vec::new(),
+            };
+            // Faster tiers are marked with a coloured dot
+            match belt.tier {
+                BeltTier::Basic => (),
+                BeltTier::Fast => shapes.push(square(6.0, vec4(0.85, 0.2, 0.15, 1.0))),
+                BeltTier::Express => shapes.push(square(6.0, vec4(0.2, 0.5, 0.9, 1.0))),
+            }
+            // Bar across the front of each splitter half
+            if belt.splitter.is_some() {
+                let (dx, dy) = belt.output.offset(0, 0);
+                let (scale_x, scale_y) = if dx != 0 { (6.0, 32.0) } else { (32.0, 6.0) };
+                let model_trans = Matrix4::from_translation(cgmath::vec3(
+                    dx as f32 * 10.0,
+                    dy as f32 * 10.0,
+                    0.0,
+                ));
+                shapes.push((
+                    model_trans * Matrix4::from_nonuniform_scale(scale_x, scale_y, 0.0),
+                    vec4(0.2, 0.3, 0.6, 1.0),
+                ));
+            }
+            shapes
+        }
+        Entity::Chest(chest) => {
+            let mut shapes = vec![square(26.0, vec4(0.55, 0.35, 0.15, 1.0))];
+            // Logistic chests get a coloured band showing their mode
+            let band = match chest.logistic {
+                Some(LogisticMode::Provider) => Some(vec4(0.8, 0.2, 0.2, 1.0)),
+                Some(LogisticMode::Requester(_)) => Some(vec4(0.2, 0.4, 0.9, 1.0)),
+                Some(LogisticMode::Storage) => Some(vec4(0.9, 0.8, 0.2, 1.0)),
+                None => None,
+            };
+            if let Some(color) = band {
+                shapes.push((Matrix4::from_nonuniform_scale(26.0, 8.0, 0.0), color));
+            }
+            shapes
+        }
+        Entity::Lab(lab) => vec![
+            square(28.0, vec4(0.3, 0.45, 0.6, 1.0)),
+            square(
+                12.0,
+                if lab.progress.is_some() {
+                    vec4(0.4, 0.8, 1.0, 1.0)
+                } else {
+                    vec4(0.2, 0.3, 0.4, 1.0)
+                },
+            ),
+        ],
+        Entity::Roboport(_) => vec![
+            square(30.0, vec4(0.35, 0.3, 0.25, 1.0)),
+            square(14.0, vec4(0.85, 0.65, 0.2, 1.0)),
+        ],
+        Entity::Furnace(furnace) => vec![square(
+            28.0,
+            if furnace.is_burning() {
+                vec4(0.85, 0.35, 0.1, 1.0)
+            } else {
+                vec4(0.5, 0.25, 0.2, 1.0)
+            },
+        )],
+        Entity::Pole(_) => vec![square(10.0, vec4(0.4, 0.25, 0.1, 1.0))],
+        Entity::Generator(generator) => vec![square(
+            28.0,
+            if generator.is_running() {
+                vec4(0.3, 0.7, 0.3, 1.0)
+            } else {
+                vec4(0.2, 0.4, 0.2, 1.0)
+            },
+        )],
+        Entity::Drill(_) => vec![square(28.0, vec4(0.45, 0.45, 0.5, 1.0))],
+        Entity::Inserter(inserter) => {
+            let (width, height) = match inserter.direction {
+                Direction::West | Direction::East => (28.0, 8.0),
+                Direction::North | Direction::South => (8.0, 28.0),
+            };
+            vec![(
+                Matrix4::from_nonuniform_scale(width, height, 0.0),
+                vec4(0.9, 0.75, 0.1, 1.0),
+            )]
+        }
+        Entity::Pipe(pipe) => {
+            let color = fluid_color(pipe.fluid_box.fluid);
+            let mut shapes = vec![square(12.0, color)];
+            for side in Direction::ALL.iter() {
+                if pipe.is_connected(*side) {
+                    shapes.push(arm(*side, 12.0, color));
+                }
+            }
+            shapes
+        }
+        Entity::Pump(pump) => vec![
+            square(22.0, vec4(0.3, 0.3, 0.35, 1.0)),
+            arm(pump.direction, 8.0, fluid_color(pump.fluid_box.fluid)),
+        ],
+        Entity::Tank(tank) => vec![square(30.0, fluid_color(tank.fluid_box.fluid))],
+        Entity::OffshorePump(pump) => vec![
+            square(22.0, vec4(0.1, 0.3, 0.6, 1.0)),
+            arm(pump.direction, 8.0, fluid_color(pump.fluid_box.fluid)),
+        ],
+        Entity::ConstantCombinator(_) => vec![square(20.0, vec4(0.3, 0.25, 0.2, 1.0))],
+        Entity::ArithmeticCombinator(combinator) => vec![
+            square(22.0, vec4(0.35, 0.35, 0.4, 1.0)),
+            arm(combinator.direction, 6.0, vec4(0.9, 0.5, 0.2, 1.0)),
+        ],
+        Entity::DeciderCombinator(combinator) => vec![
+            square(22.0, vec4(0.35, 0.35, 0.4, 1.0)),
+            arm(combinator.direction, 6.0, vec4(0.3, 0.6, 0.9, 1.0)),
+        ],
+        Entity::Rail(rail) => {
+            let color = vec4(0.45, 0.4, 0.35, 1.0);
+            let mut shapes = vec![square(10.0, color)];
+            for &(from, to) in rail.pieces.iter() {
+                shapes.push(arm(from, 10.0, color));
+                shapes.push(arm(to, 10.0, color));
+            }
+            shapes
+        }
+        Entity::TrainStop(stop) => vec![
+            square(18.0, vec4(0.8, 0.2, 0.2, 1.0)),
+            arm(stop.rail, 4.0, vec4(0.8, 0.2, 0.2, 1.0)),
+        ],
+        Entity::Boiler(boiler) => vec![
+            square(28.0, vec4(0.6, 0.6, 0.55, 1.0)),
+            arm(boiler.direction, 8.0, fluid_color(boiler.steam.fluid)),
+            arm(
+                boiler.direction.flip(),
+                8.0,
+                fluid_color(boiler.water.fluid),
+            ),
+        ],
+    }
+}
+
+fn fluid_color(fluid: Option<Fluid>) -> Vector4<f32> {
+    match fluid {
+        Some(Fluid::Water) => vec4(0.2, 0.4, 0.9, 1.0),
+        Some(Fluid::Steam) => vec4(0.9, 0.9, 0.9, 1.0),
+        None => vec4(0.35, 0.35, 0.35, 1.0),
+    }
+}
+
+// Readout of what the entity on a tile holds or is doing, shown while hovering it
+fn describe_entity(grid: &Grid, x: isize, y: isize) -> Option<String> {
+    let description = match grid.get_entity(x, y)? {
+        Entity::Chest(chest) => {
+            let mode = match &chest.logistic {
+                Some(LogisticMode::Provider) => " (provider)".to_string(),
+                Some(LogisticMode::Requester(requests)) => format!(
+                    " (requesting {})",
+                    requests
+                        .iter()
+                        .map(|stack| format!("{} {}", stack.count, stack.item.name()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Some(LogisticMode::Storage) => " (storage)".to_string(),
+                None => String::new(),
+            };
+            format!("Contents{}: {}", mode, describe_inventory(&chest.inventory))
+        }
+        Entity::Roboport(_) => {
+            let id = grid.logistics.network_at(x, y)?;
+            let robots = grid
+                .logistics
+                .robots
+                .iter()
+                .filter(|robot| robot.home == (x, y));
+            let (docked, total) = robots.fold((0, 0), |(docked, total), robot| {
+                (docked + robot.is_docked() as usize, total + 1)
+            });
+            format!(
+                "Network {}: {} roboports, {}/{} robots docked",
+                id,
+                grid.logistics.networks[id].roboports.len(),
+                docked,
+                total
+            )
+        }
+        Entity::Pole(_) => {
+            let id = grid.power.network_at(x, y)?;
+            let network = &grid.power.networks[id];
+            format!(
+                "Network {}: {} poles, {}kW produced, {}kW demanded, {}% satisfied",
+                id,
+                network.poles.len(),
+                network.production,
+                network.demand,
+                network.satisfaction * 100 / FULL_SATISFACTION
+            )
+        }
+        Entity::Lab(lab) => {
+            let research = match grid.research.current_technology() {
+                Some(technology) => format!(
+                    "Researching {} ({}/{})",
+                    technology.name,
+                    grid.research.progress[grid.research.current.unwrap()],
+                    technology.units
+                ),
+                None => "Idle".to_string(),
+            };
+            format!("{}, {}", research, describe_inventory(&lab.inventory))
+        }
+        Entity::Furnace(furnace) => format!(
+            "Fuel: {}\nSource: {}\nResult: {}\nWater: {:.1}/{}",
+            describe_inventory(&furnace.fuel),
+            describe_inventory(&furnace.source),
+            describe_inventory(&furnace.result),
+            furnace.water.amount,
+            furnace.water.capacity
+        ),
+        Entity::TrainStop(stop) => format!("Station: {}", stop.name),
+        Entity::Rail(_) => {
+            let id = grid.railway.train_at(x, y)?;
+            let train = &grid.railway.trains[id];
+            let cargo = train
+                .cars
+                .iter()
+                .filter(|car| car.kind == CarKind::CargoWagon)
+                .map(|car| describe_inventory(&car.cargo))
+                .collect::<Vec<_>>();
+            format!(
+                "Train {}: {:?}, heading to {}, cargo [{}]",
+                id,
+                train.state,
+                train
+                    .schedule
+                    .get(train.current)
+                    .map_or("nowhere", |entry| &entry.station),
+                cargo.join("; ")
+            )
+        }
+        entity
+            if grid
+                .circuit
+                .wires
+                .iter()
+                .any(|(from, to, _)| (from.0, from.1) == (x, y) || (to.0, to.1) == (x, y)) =>
+        {
+            let output_terminal = if entity.has_terminal(Terminal::Output) {
+                Terminal::Output
+            } else {
+                Terminal::Input
+            };
+            format!(
+                "Input: {}\nOutput: {}",
+                describe_signals(&grid.circuit.signals_at((x, y, Terminal::Input))),
+                describe_signals(&entity.circuit_output(output_terminal))
+            )
+        }
+        entity if !entity.fluid_boxes().is_empty() => entity
+            .fluid_boxes()
+            .iter()
+            .map(|fluid_box| match fluid_box.fluid {
+                Some(fluid) => format!(
+                    "{:.1}/{} {}",
+                    fluid_box.amount,
+                    fluid_box.capacity,
+                    fluid.name()
+                ),
+                None => "empty".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => return None,
+    };
+    Some(description)
+}
+
+fn describe_signals(signals: &Signals) -> String {
+    if signals.is_empty() {
+        return "none".to_string();
+    }
+    signals
+        .iter()
+        .map(|(signal, value)| format!("{} {}", value, signal.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// What gets built for a placeable facing `direction`, belts and trains are placed
+// differently
+fn placeable_entity(placeable: Placeable, direction: Direction) -> Option<Entity> {
+    match placeable {
+        Placeable::Chest => Some(Entity::Chest(Chest::new())),
+        Placeable::Roboport => Some(Entity::Roboport(Roboport::new())),
+        Placeable::Lab => Some(Entity::Lab(Lab::new())),
+        Placeable::ProviderChest | Placeable::RequesterChest | Placeable::StorageChest => {
+            logistic_chest(placeable)
+        }
+        Placeable::Inserter => Some(Entity::Inserter(Inserter::new(direction))),
+        Placeable::Drill => Some(Entity::Drill(Drill::new(direction))),
+        Placeable::Furnace => Some(Entity::Furnace(Furnace::new())),
+        Placeable::Pole => Some(Entity::Pole(Pole::new())),
+        Placeable::Generator => Some(Entity::Generator(Generator::new())),
+        Placeable::Pipe => Some(Entity::Pipe(Pipe::new())),
+        Placeable::Pump => Some(Entity::Pump(Pump::new(direction))),
+        Placeable::Tank => Some(Entity::Tank(Tank::new())),
+        Placeable::OffshorePump => Some(Entity::OffshorePump(OffshorePump::new(direction))),
+        Placeable::Boiler => Some(Entity::Boiler(Boiler::new(direction))),
+        Placeable::Splitter => Some(Entity::Belt(Belt::splitter(direction, SplitterSide::Left))),
+        Placeable::Rail => Some(Entity::Rail(Rail::straight(direction))),
+        Placeable::CurvedRail => Some(Entity::Rail(Rail::curve(direction))),
+        Placeable::TrainStop => Some(Entity::TrainStop(TrainStop::new("", direction))),
+        _ => combinator(placeable, direction),
+    }
+}
+
+// Colour of a tile on the minimap, entities over resources over the ground
+fn minimap_color(grid: &Grid, x: usize, y: usize) -> Vector4<f32> {
+    match (&grid.tiles[y][x], &grid.resources[y][x]) {
+        (Some(entity), _) => minimap_entity_color(entity),
+        (None, Some(resource)) => item_color(resource.item),
+        (None, None) => vec4(0.15, 0.15, 0.3, 1.0),
+    }
+}
+
+// Body colour of the entity while idle, the minimap only redraws tiles when they are
+// built on or cleared
+fn minimap_entity_color(entity: &Entity) -> Vector4<f32> {
+    match entity {
+        Entity::Belt(_) => vec4(0.8, 0.7, 0.2, 1.0),
+        Entity::Chest(_) => vec4(0.55, 0.35, 0.15, 1.0),
+        Entity::Lab(_) => vec4(0.3, 0.45, 0.6, 1.0),
+        Entity::Roboport(_) => vec4(0.35, 0.3, 0.25, 1.0),
+        Entity::Furnace(_) => vec4(0.5, 0.25, 0.2, 1.0),
+        Entity::Pole(_) => vec4(0.4, 0.25, 0.1, 1.0),
+        Entity::Generator(_) => vec4(0.2, 0.4, 0.2, 1.0),
+        Entity::Drill(_) => vec4(0.45, 0.45, 0.5, 1.0),
+        Entity::Inserter(_) => vec4(0.9, 0.75, 0.1, 1.0),
+        Entity::Pipe(_) | Entity::Tank(_) => fluid_color(None),
+        Entity::Pump(_) => vec4(0.3, 0.3, 0.35, 1.0),
+        Entity::OffshorePump(_) => vec4(0.1, 0.3, 0.6, 1.0),
+        Entity::ConstantCombinator(_) => vec4(0.3, 0.25, 0.2, 1.0),
+        Entity::ArithmeticCombinator(_) | Entity::DeciderCombinator(_) => {
+            vec4(0.35, 0.35, 0.4, 1.0)
+        }
+        Entity::Rail(_) => vec4(0.45, 0.4, 0.35, 1.0),
+        Entity::TrainStop(_) => vec4(0.8, 0.2, 0.2, 1.0),
+        Entity::Boiler(_) => vec4(0.6, 0.6, 0.55, 1.0),
+    }
+}
+
+// Words of the variant name, OffshorePump becomes "Offshore pump"
+fn placeable_name(placeable: Placeable) -> String {
+    let mut name = String::new();
+    for (i, character) in format!("{:?}", placeable).chars().enumerate() {
+        if i > 0 && character.is_uppercase() {
+            name.push(' ');
+            name.extend(character.to_lowercase());
+        } else {
+            name.push(character);
+        }
+    }
+    name
+}
+
+// Slots for everything that can be placed along the bottom of the screen, with
+// buttons to rotate and hand craft the selection
+fn build_toolbar(
+    ui: &mut Ui<Placeable>,
+    player: &Player,
+    current_placeable: Placeable,
+    current_belt: Belt,
+    screen_width: f32,
+) -> Option<ToolbarAction> {
+    let rows = TOOLBAR.len().div_ceil(TOOLBAR_COLUMNS);
+    let width = TOOLBAR_COLUMNS as f32 * TOOLBAR_SLOT_SIZE + TOOLBAR_BUTTON_WIDTH + 12.0;
+    let x = ((screen_width - width) / 2.0).max(0.0);
+    ui.panel(Rect::new(x, 0.0, width, TOOLBAR_HEIGHT));
+
+    let mut action = None;
+    for (i, &placeable) in TOOLBAR.iter().enumerate() {
+        let (column, row) = (i % TOOLBAR_COLUMNS, i / TOOLBAR_COLUMNS);
+        let rect = Rect::new(
+            x + 4.0 + column as f32 * TOOLBAR_SLOT_SIZE,
+            4.0 + (rows - 1 - row) as f32 * TOOLBAR_SLOT_SIZE,
+            TOOLBAR_SLOT_SIZE,
+            TOOLBAR_SLOT_SIZE,
+        );
+        let item = placeable_item(placeable, current_belt);
+        let count = Some(player.inventory.count(item)).filter(|_| !player.creative);
+        let tooltip = format!("{}\nUses {}", placeable_name(placeable), item.name());
+        let selected = placeable == current_placeable;
+        if ui.slot(rect, placeable, count, selected, &tooltip) {
+            action = Some(ToolbarAction::Select(placeable));
+        }
+    }
+
+    let button_x = x + 8.0 + TOOLBAR_COLUMNS as f32 * TOOLBAR_SLOT_SIZE;
+    let button_height = (TOOLBAR_HEIGHT - 12.0) / 2.0;
+    let rotate = Rect::new(
+        button_x,
+        8.0 + button_height,
+        TOOLBAR_BUTTON_WIDTH,
+        button_height,
+    );
+    if ui.button(rotate, "Rotate") {
+        action = Some(ToolbarAction::Rotate);
+    }
+    let craft = Rect::new(button_x, 4.0, TOOLBAR_BUTTON_WIDTH, button_height);
+    if ui.button(craft, "Craft") {
+        action = Some(ToolbarAction::Craft);
+    }
+    action
+}
+
+// Draws the rectangles and icons of a UI layer in screen space and adds its labels to
+// `text_batch`, which is drawn afterwards
+unsafe fn draw_ui_layer(
+    layer: &Layer<Placeable>,
+    shader: &Shader,
+    current_belt: Belt,
+    font: &Font,
+    text_batch: &mut SpriteBatch,
+) {
+    let rect_model = |rect: &Rect| {
+        Matrix4::from_translation(cgmath::vec3(
+            rect.x + rect.width / 2.0,
+            rect.y + rect.height / 2.0,
+            0.0,
+        )) * Matrix4::from_nonuniform_scale(rect.width, rect.height, 0.0)
+    };
+    for (rect, color) in layer.rects.iter() {
+        shader.set_mat4("model", rect_model(rect));
+        shader.set_vec4("color", *color);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+    }
+
+    for (rect, placeable) in layer.icons.iter() {
+        // Entities are drawn as they are in the world, shrunk to fit
+        let shapes = match *placeable {
+            Placeable::Belt => entity_shapes(&Entity::Belt(current_belt)),
+            placeable => match placeable_entity(placeable, current_belt.output) {
+                Some(entity) => entity_shapes(&entity),
+                None => vec![(
+                    Matrix4::from_nonuniform_scale(20.0, 20.0, 0.0),
+                    item_color(placeable_item(placeable, current_belt)),
+                )],
+            },
+        };
+        let model_trans = Matrix4::from_translation(cgmath::vec3(
+            rect.x + rect.width / 2.0,
+            rect.y + rect.height / 2.0,
+            0.0,
+        )) * Matrix4::from_scale(rect.width / 32.0);
+        for (model_shape, color) in shapes {
+            shader.set_mat4("model", model_trans * model_shape);
+            shader.set_vec4("color", color);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        }
+    }
+
+    for label in layer.labels.iter() {
+        font.draw(
+            text_batch,
+            &label.text,
+            label.x,
+            label.y,
+            label.scale,
+            label.color,
+        );
+    }
+}
+
+// Combinators are placed with a default setup: a constant A = 1, A + 1 on A and
+// A > 0 giving A = 1
+fn combinator(placeable: Placeable, direction: Direction) -> Option<Entity> {
+    let a = Signal::Letter('A');
+    match placeable {
+        Placeable::ConstantCombinator => Some(Entity::ConstantCombinator(
+            ConstantCombinator::new().with_signal(a, 1),
+        )),
+        Placeable::ArithmeticCombinator => {
+            Some(Entity::ArithmeticCombinator(ArithmeticCombinator::new(
+                direction,
+                Operand::Signal(a),
+                ArithmeticOperation::Add,
+                Operand::Constant(1),
+                a,
+            )))
+        }
+        Placeable::DeciderCombinator => Some(Entity::DeciderCombinator(DeciderCombinator::new(
+            direction,
+            Condition::new(a, Comparator::Greater, Operand::Constant(0)),
+            a,
+            DeciderOutput::One,
+        ))),
+        _ => None,
+    }
+}
+
+// Camera position centered on the player
+fn player_camera_position(player: &Player) -> cgmath::Point2<f32> {
+    cgmath::point2(16.0 + 32.0 * player.x, 16.0 + 32.0 * player.y)
+}
+
+// Requester chests are placed asking for 50 iron plates
+fn logistic_chest(placeable: Placeable) -> Option<Entity> {
+    let mode = match placeable {
+        Placeable::ProviderChest => LogisticMode::Provider,
+        Placeable::RequesterChest => {
+            LogisticMode::Requester(vec![ItemStack::new(Item::IronPlate, 50)])
+        }
+        Placeable::StorageChest => LogisticMode::Storage,
+        _ => return None,
+    };
+    Some(Entity::Chest(Chest::logistic(mode)))
+}
+
+// New trains visit every train stop in turn, waiting five seconds at each
+fn default_schedule(grid: &Grid) -> Vec<ScheduleEntry> {
+    grid.tiles
+        .iter()
+        .flatten()
+        .filter_map(|tile| match tile {
+            Some(Entity::TrainStop(stop)) => {
+                Some(ScheduleEntry::new(&stop.name, WaitCondition::Time(300)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// The item used up when placing, trains are crafted starting with the locomotive
+fn placeable_item(placeable: Placeable, belt: Belt) -> Item {
+    match placeable {
+        Placeable::Belt => Item::from_name(belt.kind_name()).unwrap(),
+        Placeable::Chest => Item::Chest,
+        Placeable::Inserter => Item::Inserter,
+        Placeable::Drill => Item::Drill,
+        Placeable::Furnace => Item::Furnace,
+        Placeable::Pole => Item::Pole,
+        Placeable::Generator => Item::Generator,
+        Placeable::Pipe => Item::Pipe,
+        Placeable::Pump => Item::Pump,
+        Placeable::Tank => Item::Tank,
+        Placeable::OffshorePump => Item::OffshorePump,
+        Placeable::Boiler => Item::Boiler,
+        Placeable::Splitter => Item::Splitter,
+        Placeable::ConstantCombinator => Item::ConstantCombinator,
+        Placeable::ArithmeticCombinator => Item::ArithmeticCombinator,
+        Placeable::DeciderCombinator => Item::DeciderCombinator,
+        Placeable::Rail | Placeable::CurvedRail => Item::Rail,
+        Placeable::RailSignal => Item::RailSignal,
+        Placeable::TrainStop => Item::TrainStop,
+        Placeable::Train => Item::Locomotive,
+        Placeable::Roboport => Item::Roboport,
+        Placeable::ProviderChest => Item::ProviderChest,
+        Placeable::RequesterChest => Item::RequesterChest,
+        Placeable::StorageChest => Item::StorageChest,
+        Placeable::Lab => Item::Lab,
+    }
+}
+
+fn describe_inventory(inventory: &Inventory) -> String {
+    let contents = inventory
+        .contents()
+        .iter()
+        .map(|stack| format!("{} {}", stack.count, stack.item.name()))
+        .collect::<Vec<_>>();
+    if contents.is_empty() {
+        "empty".to_string()
+    } else {
+        contents.join(", ")
+    }
+}
+
+// Bars in the bottom left corner of the screen, two per item with the produced and
+// consumed rate relative to the highest rate
+fn production_panel(stats: &ProductionStats, window: Window) -> Vec<(Matrix4<f32>, Vector4<f32>)> {
+    let rect = |x: f32, y: f32, width: f32, height: f32, color: Vector4<f32>| {
+        (
+            Matrix4::from_translation(cgmath::vec3(x + width / 2.0, y + height / 2.0, 0.0))
+                * Matrix4::from_nonuniform_scale(width, height, 0.0),
+            color,
+        )
+    };
+
+    let rates: Vec<(f32, f32)> = Item::ALL
+        .iter()
+        .map(|&item| {
+            (
+                stats.per_minute(window, Kind::Produced, item),
+                stats.per_minute(window, Kind::Consumed, item),
+            )
+        })
+        .collect();
+    let max_rate = rates
+        .iter()
+        .map(|&(produced, consumed)| produced.max(consumed))
+        .fold(1.0, f32::max);
+
+    let mut shapes = vec![rect(
+        10.0,
+        10.0,
+        220.0,
+        Item::ALL.len() as f32 * 14.0 + 6.0,
+        vec4(0.1, 0.1, 0.1, 0.8),
+    )];
+    for (i, (item, (produced, consumed))) in Item::ALL.iter().zip(rates).enumerate() {
+        let y = 14.0 + i as f32 * 14.0;
+        shapes.push(rect(14.0, y, 8.0, 10.0, item_color(*item)));
+        shapes.push(rect(
+            26.0,
+            y + 5.0,
+            200.0 * produced / max_rate,
+            5.0,
+            vec4(0.3, 0.8, 0.3, 1.0),
+        ));
+        shapes.push(rect(
+            26.0,
+            y,
+            200.0 * consumed / max_rate,
+            5.0,
+            vec4(0.8, 0.3, 0.3, 1.0),
+        ));
+    }
+    shapes
+}
+
+// Green for belts with spare room, orange for full belts and red where items back up
+fn throughput_color(belt: &BeltFlow) -> Vector4<f32> {
+    if belt.is_bottleneck() || belt.is_blocked() {
+        vec4(1.0, 0.0, 0.0, 0.5)
+    } else if belt.at_capacity() {
+        vec4(1.0, 0.5, 0.0, 0.5)
+    } else {
+        vec4(0.0, 1.0, 0.0, 0.1 + 0.4 * belt.utilization())
+    }
+}
@@ -0,0 +1,129 @@
+use cgmath::{point2, Point2, Vector4};
+
+use crate::{camera::Camera, ui::Rect};
+
+// Screen pixels per tile of the minimap, its texture has one pixel per tile
+pub const MINIMAP_SCALE: f32 = 2.0;
+// Gap between the minimap and the edges of the screen
+const MARGIN: f32 = 8.0;
+
+// Overview of the whole grid kept in a texture. After it is first drawn only tiles
+// that changed are redrawn.
+pub struct Minimap {
+    pub columns: usize,
+    pub rows: usize,
+    drawn: bool,
+}
+
+impl Minimap {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            drawn: false,
+        }
+    }
+
+    // Tiles to redraw with their new colour, every tile the first time
+    pub fn changes(
+        &mut self,
+        changed_tiles: &[(usize, usize)],
+        color: impl Fn(usize, usize) -> Vector4<f32>,
+    ) -> Vec<(usize, usize, Vector4<f32>)> {
+        if !self.drawn {
+            self.drawn = true;
+            return (0..self.rows)
+                .flat_map(|y| (0..self.columns).map(move |x| (x, y)))
+                .map(|(x, y)| (x, y, color(x, y)))
+                .collect();
+        }
+        changed_tiles
+            .iter()
+            .map(|&(x, y)| (x, y, color(x, y)))
+            .collect()
+    }
+
+    // Where it is drawn on the screen, in the top right corner
+    pub fn rect(&self, screen_width: f32, screen_height: f32) -> Rect {
+        let width = self.columns as f32 * MINIMAP_SCALE;
+        let height = self.rows as f32 * MINIMAP_SCALE;
+        Rect::new(
+            screen_width - width - MARGIN,
+            screen_height - height - MARGIN,
+            width,
+            height,
+        )
+    }
+
+    // World position under a point of the minimap drawn at `rect`
+    pub fn world_position(&self, rect: Rect, x: f32, y: f32, tile_size: f32) -> Point2<f32> {
+        point2(
+            (x - rect.x) / MINIMAP_SCALE * tile_size,
+            (y - rect.y) / MINIMAP_SCALE * tile_size,
+        )
+    }
+
+    // The part of the world on screen, clipped to the minimap drawn at `rect`
+    pub fn view_rect(
+        &self,
+        rect: Rect,
+        camera: &Camera,
+        screen_width: f32,
+        screen_height: f32,
+        zoom: f32,
+        tile_size: f32,
+    ) -> Rect {
+        let to_minimap = MINIMAP_SCALE / tile_size;
+        let half_width = screen_width / zoom / 2.0;
+        let half_height = screen_height / zoom / 2.0;
+        let min_x = (rect.x + (camera.position.x - half_width) * to_minimap).max(rect.x);
+        let min_y = (rect.y + (camera.position.y - half_height) * to_minimap).max(rect.y);
+        let max_x =
+            (rect.x + (camera.position.x + half_width) * to_minimap).min(rect.x + rect.width);
+        let max_y =
+            (rect.y + (camera.position.y + half_height) * to_minimap).min(rect.y + rect.height);
+        Rect::new(
+            min_x,
+            min_y,
+            (max_x - min_x).max(0.0),
+            (max_y - min_y).max(0.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::vec4;
+
+    #[test]
+    fn redraws_changed_tiles_and_maps_clicks_to_the_world() {
+        let mut minimap = Minimap::new(4, 2);
+        let red = vec4(1.0, 0.0, 0.0, 1.0);
+        let blue = vec4(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(minimap.changes(&[], |_, _| red).len(), 8);
+        assert!(minimap.changes(&[], |_, _| red).is_empty());
+        let changes = minimap.changes(&[(3, 1)], |x, y| if (x, y) == (3, 1) { blue } else { red });
+        assert_eq!(changes, vec![(3, 1, blue)]);
+
+        let rect = minimap.rect(800.0, 600.0);
+        assert_eq!(rect, Rect::new(784.0, 588.0, 8.0, 4.0));
+        assert_eq!(
+            minimap.world_position(rect, 787.0, 589.0, 32.0),
+            point2(48.0, 16.0)
+        );
+
+        // A 64 by 32 pixel view at zoom 2 covers one by half a tile
+        let mut camera = Camera::new();
+        camera.position = point2(64.0, 32.0);
+        assert_eq!(
+            minimap.view_rect(rect, &camera, 64.0, 32.0, 2.0, 32.0),
+            Rect::new(787.0, 589.5, 2.0, 1.0)
+        );
+        camera.position = point2(0.0, 0.0);
+        assert_eq!(
+            minimap.view_rect(rect, &camera, 64.0, 32.0, 2.0, 32.0),
+            Rect::new(784.0, 588.0, 1.0, 0.5)
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+
+use crate::{
+    entity::Entity,
+    inventory::{Inventory, ItemStack},
+    item::Item,
+    recipe::{Recipe, RecipeCategory},
+    stats::ProductionStats,
+};
+
+pub const PLAYER_SLOTS: usize = 40;
+// Tiles the player walks each tick
+pub const PLAYER_SPEED: f32 = 0.15;
+// Furthest the player can build and remove things, in tiles from where they stand
+pub const PLAYER_REACH: f32 = 10.0;
+
+pub struct Player {
+    // Position in tiles, whole numbers being the center of a tile
+    pub x: f32,
+    pub y: f32,
+    pub inventory: Inventory,
+    // Recipes waiting to be crafted, their ingredients are taken when queued
+    pub crafting_queue: VecDeque<&'static Recipe>,
+    // Ticks spent on the first recipe in the queue
+    pub crafting_progress: u32,
+    // Builds without using items and crafts without ingredients
+    pub creative: bool,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            inventory: Inventory::new(PLAYER_SLOTS),
+            crafting_queue: VecDeque::new(),
+            crafting_progress: 0,
+            creative: false,
+        }
+    }
+
+    pub fn creative() -> Self {
+        Self {
+            creative: true,
+            ..Self::new()
+        }
+    }
+
+    // Walks for a number of ticks in the direction of (dx, dy), at the same speed in
+    // every direction
+    pub fn walk(&mut self, dx: f32, dy: f32, ticks: f32) {
+        let length = (dx * dx + dy * dy).sqrt();
+        if length > 0.0 {
+            self.x += dx / length * PLAYER_SPEED * ticks;
+            self.y += dy / length * PLAYER_SPEED * ticks;
+        }
+    }
+
+    pub fn can_reach(&self, x: isize, y: isize) -> bool {
+        let (dx, dy) = (x as f32 - self.x, y as f32 - self.y);
+        dx * dx + dy * dy <= PLAYER_REACH * PLAYER_REACH
+    }
+
+    // Stacks of the same item add up
+    pub fn has_items(&self, items: &[ItemStack]) -> bool {
+        items.iter().all(|stack| {
+            let needed: u32 = items
+                .iter()
+                .filter(|other| other.item == stack.item)
+                .map(|other| other.count)
+                .sum();
+            self.inventory.count(stack.item) >= needed
+        })
+    }
+
+    // Takes all the items or none of them, returns whether they were there
+    pub fn take_items(&mut self, items: &[ItemStack]) -> bool {
+        if self.creative {
+            return true;
+        }
+        if !self.has_items(items) {
+            return false;
+        }
+        for stack in items {
+            self.inventory.extract(stack.item, stack.count);
+        }
+        true
+    }
+
+    // Whether all of `items` fit in the inventory together
+    pub fn can_receive(&self, items: &[ItemStack]) -> bool {
+        if self.creative {
+            return true;
+        }
+        let mut inventory = self.inventory.clone();
+        items
+            .iter()
+            .all(|stack| inventory.insert(stack.item, stack.count) == stack.count)
+    }
+
+    // Whatever doesn't fit in the inventory is lost
+    pub fn give_items(&mut self, items: &[ItemStack]) {
+        if self.creative {
+            return;
+        }
+        for stack in items {
+            self.inventory.insert(stack.item, stack.count);
+        }
+    }
+
+    // Queues up to `count` crafts of a hand crafting recipe, returns how many could be
+    // paid for
+    pub fn queue_craft(
+        &mut self,
+        recipe: &'static Recipe,
+        count: u32,
+        stats: &mut ProductionStats,
+    ) -> u32 {
+        if recipe.category != RecipeCategory::Crafting {
+            return 0;
+        }
+        let ingredients = stacks(recipe.ingredients);
+        let mut queued = 0;
+        while queued < count && self.take_items(&ingredients) {
+            if !self.creative {
+                for stack in ingredients.iter() {
+                    stats.consume(stack.item, stack.count);
+                }
+            }
+            self.crafting_queue.push_back(recipe);
+            queued += 1;
+        }
+        queued
+    }
+
+    // Removes the last queued craft and gives back its ingredients
+    pub fn cancel_craft(&mut self) -> bool {
+        match self.crafting_queue.pop_back() {
+            Some(recipe) => {
+                if self.crafting_queue.is_empty() {
+                    self.crafting_progress = 0;
+                }
+                self.give_items(&stacks(recipe.ingredients));
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Works on the first queued recipe, its results wait until the inventory has room
+    pub fn update_crafting(&mut self, stats: &mut ProductionStats) {
+        let recipe = match self.crafting_queue.front() {
+            Some(recipe) => *recipe,
+            None => return,
+        };
+        self.crafting_progress = (self.crafting_progress + 1).min(recipe.ticks);
+        if self.crafting_progress < recipe.ticks
+            || !recipe
+                .results
+                .iter()
+                .all(|&(item, count)| self.inventory.can_insert(item, count))
+        {
+            return;
+        }
+
+        for &(item, count) in recipe.results.iter() {
+            self.inventory.insert(item, count);
+            stats.produce(item, count);
+        }
+        self.crafting_queue.pop_front();
+        self.crafting_progress = 0;
+    }
+}
+
+// Items used up by building the entity and given back when it is removed
+pub fn build_cost(entity: &Entity) -> Vec<ItemStack> {
+    match entity {
+        Entity::Rail(rail) => vec![ItemStack::new(Item::Rail, rail.pieces.len() as u32)],
+        _ => Item::from_name(entity.name())
+            .map(|item| ItemStack::new(item, 1))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn stacks(items: &[(Item, u32)]) -> Vec<ItemStack> {
+    items
+        .iter()
+        .map(|&(item, count)| ItemStack::new(item, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::chest::Chest,
+        grid::{Belt, Direction, Grid},
+        recipe::{crafting_recipe_for, smelting_recipe_for},
+    };
+
+    #[test]
+    fn walking_moves_the_reach_with_the_player() {
+        let mut player = Player::new();
+        assert!(player.can_reach(0, 10));
+        assert!(!player.can_reach(0, 20));
+
+        player.walk(0.0, 1.0, 10.0 / PLAYER_SPEED);
+        assert!((player.y - 10.0).abs() < 0.001);
+        assert!(player.can_reach(0, 20));
+        assert!(!player.can_reach(0, -1));
+
+        // Walking diagonally is no faster
+        player.walk(1.0, 1.0, 1.0);
+        let (dx, dy) = (player.x, player.y - 10.0);
+        assert!(((dx * dx + dy * dy).sqrt() - PLAYER_SPEED).abs() < 0.001);
+    }
+
+    #[test]
+    fn crafting_takes_ingredients_and_time() {
+        let mut stats = ProductionStats::new();
+        let mut player = Player::new();
+        player.inventory.insert(Item::IronPlate, 3);
+        let recipe = crafting_recipe_for(Item::Belt).unwrap();
+        assert_eq!(player.queue_craft(recipe, 5, &mut stats), 3);
+        assert_eq!(player.inventory.count(Item::IronPlate), 0);
+        assert_eq!(
+            player.queue_craft(smelting_recipe_for(Item::IronOre).unwrap(), 1, &mut stats),
+            0
+        );
+
+        assert!(player.cancel_craft());
+        assert_eq!(player.inventory.count(Item::IronPlate), 1);
+        for _ in 0..recipe.ticks * 2 - 1 {
+            player.update_crafting(&mut stats);
+        }
+        assert_eq!(player.inventory.count(Item::Belt), 2);
+        player.update_crafting(&mut stats);
+        assert_eq!(player.inventory.count(Item::Belt), 4);
+        assert!(player.crafting_queue.is_empty());
+    }
+
+    #[test]
+    fn building_uses_items_and_removing_gives_them_back() {
+        let mut grid = Grid::new();
+        grid.player = Player::new();
+        assert!(!grid.place_belt(0, 0, Belt::straight(Direction::East)));
+        grid.player.inventory.insert(Item::Belt, 1);
+        grid.player.inventory.insert(Item::Chest, 1);
+        // Nothing is used up building outside the grid
+        assert!(!grid.place_belt(-1, 0, Belt::straight(Direction::East)));
+        assert!(!grid.place_entity(0, 128, Entity::Chest(Chest::new())));
+        assert_eq!(grid.player.inventory.count(Item::Belt), 1);
+        assert!(grid.place_belt(0, 0, Belt::straight(Direction::East)));
+        // Turning a belt is free
+        assert!(grid.place_belt(0, 0, Belt::straight(Direction::North)));
+        assert_eq!(grid.player.inventory.count(Item::Belt), 0);
+
+        let mut chest = Chest::new();
+        chest.inventory.insert(Item::Coal, 10);
+        assert!(grid.place_entity(1, 0, Entity::Chest(chest)));
+        assert!(grid.player.inventory.is_empty());
+        grid.clear_tile(0, 0);
+        grid.clear_tile(1, 0);
+        assert_eq!(grid.player.inventory.count(Item::Belt), 1);
+        assert_eq!(grid.player.inventory.count(Item::Chest), 1);
+        assert_eq!(grid.player.inventory.count(Item::Coal), 10);
+
+        grid.player.creative = true;
+        assert!(grid.place_entity(2, 0, Entity::Chest(Chest::new())));
+        assert_eq!(grid.player.inventory.count(Item::Chest), 1);
+    }
+
+    #[test]
+    fn removing_gives_back_what_entities_hold_if_it_fits() {
+        let mut grid = Grid::new();
+        grid.player = Player::new();
+        grid.player.inventory.insert(Item::Belt, 1);
+        assert!(grid.place_belt(0, 0, Belt::straight(Direction::East)));
+        assert!(grid.drop_item(0, 0, Direction::North, Item::Coal));
+        assert!(grid.drop_item(0, 0, Direction::South, Item::IronPlate));
+
+        // With every slot taken the belt and its items have nowhere to go
+        let full = Item::Stone.stack_size() * PLAYER_SLOTS as u32;
+        grid.player.inventory.insert(Item::Stone, full);
+        assert!(!grid.clear_tile(0, 0));
+        assert!(grid.get_belt(0, 0).is_some());
+
+        grid.player
+            .inventory
+            .extract(Item::Stone, Item::Stone.stack_size() * 3);
+        assert!(grid.clear_tile(0, 0));
+        assert!(grid.get_entity(0, 0).is_none());
+        assert_eq!(grid.player.inventory.count(Item::Belt), 1);
+        assert_eq!(grid.player.inventory.count(Item::Coal), 1);
+        assert_eq!(grid.player.inventory.count(Item::IronPlate), 1);
+    }
+}
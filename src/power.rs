@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+// Satisfaction is fixed point so machine progress stays deterministic
+pub const FULL_SATISFACTION: u32 = 1000;
+// Tiles around a pole (in each direction) that it supplies with power
+pub const SUPPLY_RADIUS: isize = 2;
+// Maximum distance between two poles for a wire to connect them
+pub const WIRE_REACH: f32 = 7.5;
+
+#[derive(Clone, Debug)]
+pub struct PowerNetwork {
+    pub poles: Vec<(isize, isize)>,
+    // Power in kW produced and requested during the last tick
+    pub production: u32,
+    pub demand: u32,
+    pub satisfaction: u32,
+}
+
+impl PowerNetwork {
+    fn new() -> Self {
+        Self {
+            poles: Vec::new(),
+            production: 0,
+            demand: 0,
+            satisfaction: 0,
+        }
+    }
+}
+
+pub struct PowerGrid {
+    pub networks: Vec<PowerNetwork>,
+    pub wires: Vec<((isize, isize), (isize, isize))>,
+    // Network supplying each tile, if any
+    supply: Vec<Vec<Option<usize>>>,
+}
+
+impl PowerGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            networks: Vec::new(),
+            wires: Vec::new(),
+            supply: vec![vec![None; width]; height],
+        }
+    }
+
+    // Connects every pole to all poles within wire reach and groups them into networks.
+    // Poles are expected in row-major order, which keeps network ids stable.
+    pub fn rebuild(&mut self, poles: &[(isize, isize)]) {
+        self.wires.clear();
+        for (i, &a) in poles.iter().enumerate() {
+            for &b in &poles[i + 1..] {
+                if Self::in_reach(a, b) {
+                    self.wires.push((a, b));
+                }
+            }
+        }
+
+        let mut network_of = vec![None; poles.len()];
+        self.networks.clear();
+        for start in 0..poles.len() {
+            if network_of[start].is_some() {
+                continue;
+            }
+
+            let id = self.networks.len();
+            let mut network = PowerNetwork::new();
+            let mut queue = VecDeque::new();
+            network_of[start] = Some(id);
+            queue.push_back(start);
+            while let Some(current) = queue.pop_front() {
+                network.poles.push(poles[current]);
+                for next in 0..poles.len() {
+                    if network_of[next].is_none() && Self::in_reach(poles[current], poles[next]) {
+                        network_of[next] = Some(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            self.networks.push(network);
+        }
+
+        let network_of: Vec<usize> = network_of.into_iter().flatten().collect();
+        self.assign_supply(poles, &network_of);
+    }
+
+    // Gives every tile in reach of a pole to the network of the closest pole, or the
+    // earliest one of those as close. A pole's own tile always belongs to its network.
+    fn assign_supply(&mut self, poles: &[(isize, isize)], network_of: &[usize]) {
+        let mut distances = vec![vec![isize::MAX; self.supply[0].len()]; self.supply.len()];
+        for row in self.supply.iter_mut() {
+            for tile in row.iter_mut() {
+                *tile = None;
+            }
+        }
+        for (i, &(pole_x, pole_y)) in poles.iter().enumerate() {
+            for y in pole_y - SUPPLY_RADIUS..=pole_y + SUPPLY_RADIUS {
+                for x in pole_x - SUPPLY_RADIUS..=pole_x + SUPPLY_RADIUS {
+                    let distance = (x - pole_x).pow(2) + (y - pole_y).pow(2);
+                    let closest = match Self::tile_mut(&mut distances, x, y) {
+                        Some(closest) => closest,
+                        None => continue,
+                    };
+                    if distance < *closest {
+                        *closest = distance;
+                        self.supply[y as usize][x as usize] = Some(network_of[i]);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn network_at(&self, x: isize, y: isize) -> Option<usize> {
+        if y < 0 || x < 0 {
+            return None;
+        }
+        *self.supply.get(y as usize)?.get(x as usize)?
+    }
+
+    pub fn satisfaction_at(&self, x: isize, y: isize) -> u32 {
+        self.network_at(x, y)
+            .map_or(0, |network| self.networks[network].satisfaction)
+    }
+
+    pub fn begin_tick(&mut self) {
+        for network in self.networks.iter_mut() {
+            network.production = 0;
+            network.demand = 0;
+        }
+    }
+
+    pub fn end_tick(&mut self) {
+        for network in self.networks.iter_mut() {
+            network.satisfaction = if network.demand == 0 {
+                FULL_SATISFACTION
+            } else {
+                (network.production as u64 * FULL_SATISFACTION as u64 / network.demand as u64)
+                    .min(FULL_SATISFACTION as u64) as u32
+            };
+        }
+    }
+
+    fn tile_mut<T>(tiles: &mut [Vec<T>], x: isize, y: isize) -> Option<&mut T> {
+        if y < 0 || x < 0 {
+            return None;
+        }
+        tiles.get_mut(y as usize)?.get_mut(x as usize)
+    }
+
+    fn in_reach(a: (isize, isize), b: (isize, isize)) -> bool {
+        let dx = (a.0 - b.0) as f32;
+        let dy = (a.1 - b.1) as f32;
+        dx * dx + dy * dy <= WIRE_REACH * WIRE_REACH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{
+            chest::Chest, generator::Generator, inserter::Inserter, lab::Lab, pole::Pole, Entity,
+        },
+        grid::{Direction, Grid},
+        item::Item,
+    };
+
+    fn chest_count(grid: &Grid, x: isize, y: isize) -> u32 {
+        match grid.get_entity(x, y) {
+            Some(Entity::Chest(chest)) => chest.inventory.count(Item::Coal),
+            _ => panic!("no chest"),
+        }
+    }
+
+    #[test]
+    fn networks_merge_and_split_with_the_poles_between_them() {
+        let mut grid = Grid::new();
+        for x in [0, 6, 18, 24] {
+            assert!(grid.place_entity(x, 0, Entity::Pole(Pole::new())));
+        }
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(0, 1, Entity::Generator(generator));
+        grid.place_entity(24, 1, Entity::Lab(Lab::new()));
+        assert_eq!(grid.power.networks.len(), 2);
+
+        // A pole within reach of both chains joins them, so the lab gets power
+        grid.place_entity(12, 0, Entity::Pole(Pole::new()));
+        assert_eq!(grid.power.networks.len(), 1);
+        assert_eq!(grid.power.wires.len(), 4);
+        grid.tick();
+        assert_eq!(grid.power.satisfaction_at(24, 1), FULL_SATISFACTION);
+
+        grid.clear_tile(12, 0);
+        assert_eq!(grid.power.networks.len(), 2);
+        assert_ne!(grid.power.network_at(0, 0), grid.power.network_at(24, 0));
+        assert_eq!(grid.power.network_at(12, 0), None);
+        grid.tick();
+        assert_eq!(grid.power.satisfaction_at(0, 1), FULL_SATISFACTION);
+        assert_eq!(grid.power.satisfaction_at(24, 1), 0);
+        let lab_network = grid.power.network_at(24, 1).unwrap();
+        assert_eq!(grid.power.networks[lab_network].demand, 60);
+        assert_eq!(grid.power.networks[lab_network].production, 0);
+    }
+
+    #[test]
+    fn overlapping_tiles_go_to_the_closest_pole() {
+        let mut power = PowerGrid::new(8, 8);
+        power.assign_supply(&[(4, 2), (2, 2)], &[1, 0]);
+        assert_eq!(power.network_at(2, 2), Some(0));
+        assert_eq!(power.network_at(4, 2), Some(1));
+        assert_eq!(power.network_at(1, 2), Some(0));
+        assert_eq!(power.network_at(5, 3), Some(1));
+        // Tiles as close to both go to the pole listed first
+        assert_eq!(power.network_at(3, 2), Some(1));
+        assert_eq!(power.network_at(7, 2), None);
+    }
+
+    #[test]
+    fn machines_need_a_network_with_power() {
+        let mut grid = Grid::new();
+        for x in [0, 10, 20] {
+            let mut chest = Chest::new();
+            chest.inventory.insert(Item::Coal, 5);
+            grid.place_entity(x, 0, Entity::Chest(chest));
+            grid.place_entity(x, 1, Entity::Inserter(Inserter::new(Direction::North)));
+            grid.place_entity(x, 2, Entity::Chest(Chest::new()));
+        }
+        // The inserter on x = 10 has a pole without a generator, the one on x = 20 a
+        // pole with a running generator
+        grid.place_entity(11, 1, Entity::Pole(Pole::new()));
+        grid.place_entity(21, 1, Entity::Pole(Pole::new()));
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(22, 1, Entity::Generator(generator));
+
+        for _ in 0..100 {
+            grid.tick();
+        }
+        assert_eq!(chest_count(&grid, 0, 2), 0);
+        assert_eq!(chest_count(&grid, 10, 2), 0);
+        assert!(chest_count(&grid, 20, 2) > 0);
+    }
+}
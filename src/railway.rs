@@ -0,0 +1,460 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    entity::{rail::Rail, Entity},
+    grid::{Direction, Grid},
+    inventory::Inventory,
+};
+
+// Ticks a train takes to move one tile
+pub const TRAIN_STEP_TICKS: u32 = 8;
+pub const WAGON_SLOTS: usize = 8;
+
+type Tile = (isize, isize);
+// A rail tile and the side a train entered it through
+pub type RailPosition = (isize, isize, Direction);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CarKind {
+    Locomotive,
+    CargoWagon,
+}
+
+impl CarKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Locomotive => "locomotive",
+            Self::CargoWagon => "cargo-wagon",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Car {
+    pub kind: CarKind,
+    pub cargo: Inventory,
+}
+
+impl Car {
+    pub fn locomotive() -> Self {
+        Self {
+            kind: CarKind::Locomotive,
+            cargo: Inventory::new(0),
+        }
+    }
+
+    pub fn cargo_wagon() -> Self {
+        Self {
+            kind: CarKind::CargoWagon,
+            cargo: Inventory::new(WAGON_SLOTS),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaitCondition {
+    // Ticks spent at the station
+    Time(u32),
+    // Every cargo wagon is full
+    Full,
+    // Every cargo wagon is empty
+    Empty,
+}
+
+impl WaitCondition {
+    // Cycles between waiting five seconds, until full and until empty
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::Time(_) => Self::Full,
+            Self::Full => Self::Empty,
+            Self::Empty => Self::Time(300),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScheduleEntry {
+    pub station: String,
+    pub wait: WaitCondition,
+}
+
+impl ScheduleEntry {
+    pub fn new(station: &str, wait: WaitCondition) -> Self {
+        Self {
+            station: station.to_string(),
+            wait,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrainState {
+    Idle,
+    Moving,
+    // Held by a signal or by another train in the way
+    WaitingForSignal,
+    // The next station doesn't exist or can't be reached
+    NoPath,
+    // At the station of the current schedule entry for `ticks`
+    Stopped { ticks: u32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct Train {
+    pub cars: Vec<Car>,
+    // Tile of every car, the front car first
+    pub tiles: VecDeque<Tile>,
+    // Side the front car entered its tile through
+    pub entry: Direction,
+    pub schedule: Vec<ScheduleEntry>,
+    // Schedule entry the train is heading to or stopped at
+    pub current: usize,
+    pub state: TrainState,
+    // Ticks since the last step
+    pub progress: u32,
+}
+
+impl Train {
+    pub fn new(
+        cars: Vec<Car>,
+        tiles: VecDeque<Tile>,
+        entry: Direction,
+        schedule: Vec<ScheduleEntry>,
+    ) -> Self {
+        Self {
+            cars,
+            tiles,
+            entry,
+            schedule,
+            current: 0,
+            state: TrainState::Idle,
+            progress: 0,
+        }
+    }
+
+    pub fn front(&self) -> RailPosition {
+        let (x, y) = self.tiles[0];
+        (x, y, self.entry)
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.state, TrainState::Stopped { .. })
+    }
+
+    pub fn wait_over(&self, wait: WaitCondition, ticks: u32) -> bool {
+        let mut cargo = self
+            .cars
+            .iter()
+            .filter(|car| car.kind == CarKind::CargoWagon)
+            .map(|car| &car.cargo);
+        match wait {
+            WaitCondition::Time(time) => ticks >= time,
+            WaitCondition::Full => cargo.all(|inventory| inventory.is_full()),
+            WaitCondition::Empty => cargo.all(|inventory| inventory.is_empty()),
+        }
+    }
+}
+
+pub struct Railway {
+    pub trains: Vec<Train>,
+    // Signals sit on the edge between a rail tile and its neighbour on `side`
+    pub signals: Vec<RailPosition>,
+    // Rails split into blocks by signals
+    blocks: HashMap<Tile, usize>,
+}
+
+impl Railway {
+    pub fn new() -> Self {
+        Self {
+            trains: Vec::new(),
+            signals: Vec::new(),
+            blocks: HashMap::new(),
+        }
+    }
+
+    pub fn has_signal(&self, x: isize, y: isize, side: Direction) -> bool {
+        let (neighbour_x, neighbour_y) = side.offset(x, y);
+        self.signals.contains(&(x, y, side))
+            || self
+                .signals
+                .contains(&(neighbour_x, neighbour_y, side.flip()))
+    }
+
+    pub fn block_at(&self, x: isize, y: isize) -> Option<usize> {
+        self.blocks.get(&(x, y)).copied()
+    }
+
+    pub fn train_at(&self, x: isize, y: isize) -> Option<usize> {
+        self.trains
+            .iter()
+            .position(|train| train.tiles.contains(&(x, y)))
+    }
+
+    // Whether a train other than `except` has a car in the block
+    pub fn is_block_occupied(&self, block: usize, except: Option<usize>) -> bool {
+        self.trains.iter().enumerate().any(|(index, train)| {
+            Some(index) != except
+                && train
+                    .tiles
+                    .iter()
+                    .any(|&(x, y)| self.block_at(x, y) == Some(block))
+        })
+    }
+
+    // Cargo of a wagon on (x, y) belonging to a train stopped at a station
+    pub fn cargo_at_mut(&mut self, x: isize, y: isize) -> Option<&mut Inventory> {
+        let train = self
+            .trains
+            .iter_mut()
+            .find(|train| train.is_stopped() && train.tiles.contains(&(x, y)))?;
+        let car = train.tiles.iter().position(|&tile| tile == (x, y))?;
+        match &mut train.cars[car] {
+            Car {
+                kind: CarKind::CargoWagon,
+                cargo,
+            } => Some(cargo),
+            _ => None,
+        }
+    }
+
+    pub fn set_blocks(&mut self, blocks: HashMap<Tile, usize>) {
+        self.blocks = blocks;
+    }
+}
+
+fn rail_at(grid: &Grid, x: isize, y: isize) -> Option<&Rail> {
+    match grid.get_entity(x, y)? {
+        Entity::Rail(rail) => Some(rail),
+        _ => None,
+    }
+}
+
+// Whether the rail on (x, y) continues into the rail on its neighbour on `side`
+fn connects(grid: &Grid, x: isize, y: isize, side: Direction) -> bool {
+    let (neighbour_x, neighbour_y) = side.offset(x, y);
+    rail_at(grid, x, y).is_some_and(|rail| rail.has_side(side))
+        && rail_at(grid, neighbour_x, neighbour_y).is_some_and(|rail| rail.has_side(side.flip()))
+}
+
+// Positions a train at `position` can move to next
+pub fn next_positions(grid: &Grid, (x, y, entry): RailPosition) -> Vec<RailPosition> {
+    let rail = match rail_at(grid, x, y) {
+        Some(rail) => rail,
+        None => return Vec::new(),
+    };
+    rail.exits(entry)
+        .into_iter()
+        .filter(|&exit| connects(grid, x, y, exit))
+        .map(|exit| {
+            let (next_x, next_y) = exit.offset(x, y);
+            (next_x, next_y, exit.flip())
+        })
+        .collect()
+}
+
+// Shortest way along the rails from `start` to the tile `goal`, not including `start`.
+// Trains can't reverse so the side they entered through matters.
+pub fn find_path(grid: &Grid, start: RailPosition, goal: Tile) -> Option<Vec<RailPosition>> {
+    if (start.0, start.1) == goal {
+        return Some(Vec::new());
+    }
+
+    let mut previous = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(position) = queue.pop_front() {
+        if (position.0, position.1) == goal {
+            let mut path = vec![position];
+            while let Some(&before) = previous.get(path.last().unwrap()) {
+                if before == start {
+                    break;
+                }
+                path.push(before);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for next in next_positions(grid, position) {
+            if next != start && !previous.contains_key(&next) {
+                previous.insert(next, position);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Rail tile of the first train stop called `name`
+pub fn station_tile(grid: &Grid, name: &str) -> Option<Tile> {
+    for (y, row) in grid.tiles.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            if let Some(Entity::TrainStop(stop)) = tile {
+                let (rail_x, rail_y) = stop.rail.offset(x as isize, y as isize);
+                if stop.name == name && rail_at(grid, rail_x, rail_y).is_some() {
+                    return Some((rail_x, rail_y));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Groups connected rail tiles into blocks, rails joined across a signal end up in
+// different blocks
+pub fn blocks(grid: &Grid) -> HashMap<Tile, usize> {
+    let mut blocks = HashMap::new();
+    let mut count = 0;
+    for (y, row) in grid.tiles.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let start = (x as isize, y as isize);
+            if !matches!(tile, Some(Entity::Rail(_))) || blocks.contains_key(&start) {
+                continue;
+            }
+
+            let mut queue = VecDeque::new();
+            blocks.insert(start, count);
+            queue.push_back(start);
+            while let Some((x, y)) = queue.pop_front() {
+                for side in Direction::ALL {
+                    let next = side.offset(x, y);
+                    if connects(grid, x, y, side)
+                        && !grid.railway.has_signal(x, y, side)
+                        && !blocks.contains_key(&next)
+                    {
+                        blocks.insert(next, count);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            count += 1;
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entity::train_stop::TrainStop, item::Item};
+
+    // Loop of track around the rectangle from (0, 0) to (width, height)
+    fn build_loop(grid: &mut Grid, width: isize, height: isize) {
+        for x in 1..width {
+            assert!(grid.place_rail(x, 0, Rail::straight(Direction::East)));
+            assert!(grid.place_rail(x, height, Rail::straight(Direction::East)));
+        }
+        for y in 1..height {
+            assert!(grid.place_rail(0, y, Rail::straight(Direction::North)));
+            assert!(grid.place_rail(width, y, Rail::straight(Direction::North)));
+        }
+        assert!(grid.place_rail(0, 0, Rail::new(Direction::North, Direction::East)));
+        assert!(grid.place_rail(0, height, Rail::new(Direction::South, Direction::East)));
+        assert!(grid.place_rail(width, height, Rail::new(Direction::West, Direction::South)));
+        assert!(grid.place_rail(width, 0, Rail::new(Direction::West, Direction::North)));
+    }
+
+    fn stop(grid: &mut Grid, x: isize, y: isize, name: &str, rail: Direction) {
+        assert!(grid.place_entity(x, y, Entity::TrainStop(TrainStop::new(name, rail))));
+    }
+
+    #[test]
+    fn path_follows_the_direction_of_travel() {
+        let mut grid = Grid::new();
+        build_loop(&mut grid, 4, 3);
+
+        // Heading east along the bottom the train has to go all the way around
+        let path = find_path(&grid, (1, 0, Direction::West), (0, 1)).unwrap();
+        assert_eq!(path.len(), 12);
+        assert_eq!(path[0], (2, 0, Direction::West));
+        assert_eq!(path.last(), Some(&(0, 1, Direction::North)));
+
+        let path = find_path(&grid, (1, 0, Direction::East), (0, 1)).unwrap();
+        assert_eq!(
+            path,
+            vec![(0, 0, Direction::East), (0, 1, Direction::South)]
+        );
+
+        grid.clear_tile(4, 2);
+        assert!(find_path(&grid, (1, 0, Direction::West), (0, 1)).is_none());
+    }
+
+    #[test]
+    fn train_runs_its_schedule_and_loads_cargo() {
+        let mut grid = Grid::new();
+        build_loop(&mut grid, 6, 4);
+        stop(&mut grid, 3, 1, "Mine", Direction::South);
+        stop(&mut grid, 3, 5, "Smelter", Direction::South);
+        let schedule = vec![
+            ScheduleEntry::new("Mine", WaitCondition::Full),
+            ScheduleEntry::new("Smelter", WaitCondition::Empty),
+        ];
+        assert!(grid.place_train(
+            1,
+            4,
+            Direction::East,
+            vec![Car::locomotive(), Car::cargo_wagon()],
+            schedule
+        ));
+        assert_eq!(
+            grid.railway.trains[0].tiles,
+            VecDeque::from([(1, 4), (0, 4)])
+        );
+
+        let mut ticks = 0;
+        while !grid.railway.trains[0].is_stopped() {
+            grid.tick();
+            ticks += 1;
+            assert!(ticks < 1000);
+        }
+        assert_eq!(grid.railway.trains[0].tiles[0], (3, 0));
+
+        // The train comes in heading west, its wagon is on (4, 0)
+        assert!(!grid.drop_item(3, 0, Direction::North, Item::Coal));
+        for _ in 0..WAGON_SLOTS as u32 * Item::Coal.stack_size() {
+            assert!(grid.drop_item(4, 0, Direction::North, Item::Coal));
+        }
+        grid.tick();
+        grid.tick();
+        assert_eq!(grid.railway.trains[0].current, 1);
+        assert_eq!(grid.railway.trains[0].state, TrainState::Moving);
+
+        ticks = 0;
+        while !grid.railway.trains[0].is_stopped() {
+            grid.tick();
+            ticks += 1;
+            assert!(ticks < 1000);
+        }
+        assert_eq!(grid.railway.trains[0].tiles[0], (3, 4));
+        assert_eq!(grid.take_item(2, 4), Some(Item::Coal));
+    }
+
+    #[test]
+    fn signals_keep_trains_out_of_occupied_blocks() {
+        let mut grid = Grid::new();
+        for x in 0..12 {
+            assert!(grid.place_rail(x, 0, Rail::straight(Direction::East)));
+        }
+        stop(&mut grid, 11, 1, "End", Direction::South);
+        assert!(grid.place_rail_signal(5, 0, Direction::East));
+        assert_ne!(grid.railway.block_at(5, 0), grid.railway.block_at(6, 0));
+
+        let schedule = vec![ScheduleEntry::new("End", WaitCondition::Time(u32::MAX))];
+        assert!(grid.place_train(
+            7,
+            0,
+            Direction::East,
+            vec![Car::locomotive()],
+            schedule.clone()
+        ));
+        assert!(grid.place_train(1, 0, Direction::East, vec![Car::locomotive()], schedule));
+        for _ in 0..200 {
+            grid.tick();
+        }
+
+        // The first train blocks the end of the line, the second waits at the signal
+        assert_eq!(grid.railway.trains[0].tiles[0], (11, 0));
+        assert_eq!(grid.railway.trains[1].tiles[0], (5, 0));
+        assert_eq!(grid.railway.trains[1].state, TrainState::WaitingForSignal);
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{fluid::Fluid, item::Item};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecipeCategory {
+    Smelting,
+    Crafting,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Recipe {
+    pub name: &'static str,
+    pub category: RecipeCategory,
+    pub ingredients: &'static [(Item, u32)],
+    pub results: &'static [(Item, u32)],
+    // Fluid used up when the recipe starts, only machines with a fluid box can make these
+    pub fluid_ingredients: &'static [(Fluid, f32)],
+    // Crafting time at speed 1
+    pub ticks: u32,
+}
+
+pub static RECIPES: [Recipe; 35] = [
+    Recipe {
+        name: "iron-plate",
+        category: RecipeCategory::Smelting,
+        ingredients: &[(Item::IronOre, 1)],
+        results: &[(Item::IronPlate, 1)],
+        fluid_ingredients: &[],
+        ticks: 192,
+    },
+    Recipe {
+        name: "copper-plate",
+        category: RecipeCategory::Smelting,
+        ingredients: &[(Item::CopperOre, 1)],
+        results: &[(Item::CopperPlate, 1)],
+        fluid_ingredients: &[],
+        ticks: 192,
+    },
+    Recipe {
+        name: "stone-brick",
+        category: RecipeCategory::Smelting,
+        ingredients: &[(Item::Stone, 2)],
+        results: &[(Item::StoneBrick, 1)],
+        fluid_ingredients: &[(Fluid::Water, 10.0)],
+        ticks: 192,
+    },
+    Recipe {
+        name: "steel-plate",
+        category: RecipeCategory::Smelting,
+        ingredients: &[(Item::IronPlate, 5)],
+        results: &[(Item::SteelPlate, 1)],
+        fluid_ingredients: &[],
+        ticks: 960,
+    },
+    Recipe {
+        name: "automation-science-pack",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::CopperPlate, 1), (Item::IronPlate, 1)],
+        results: &[(Item::AutomationSciencePack, 1)],
+        fluid_ingredients: &[],
+        ticks: 300,
+    },
+    Recipe {
+        name: "logistic-science-pack",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 2), (Item::StoneBrick, 1)],
+        results: &[(Item::LogisticSciencePack, 1)],
+        fluid_ingredients: &[],
+        ticks: 360,
+    },
+    Recipe {
+        name: "belt",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 1)],
+        results: &[(Item::Belt, 2)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "fast-belt",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Belt, 1), (Item::IronPlate, 5)],
+        results: &[(Item::FastBelt, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "express-belt",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::FastBelt, 1), (Item::SteelPlate, 2)],
+        results: &[(Item::ExpressBelt, 1)],
+        fluid_ingredients: &[],
+        ticks: 60,
+    },
+    Recipe {
+        name: "underground-belt",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Belt, 5), (Item::IronPlate, 10)],
+        results: &[(Item::UndergroundBelt, 2)],
+        fluid_ingredients: &[],
+        ticks: 60,
+    },
+    Recipe {
+        name: "splitter",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::Belt, 4),
+            (Item::IronPlate, 5),
+            (Item::CopperPlate, 5),
+        ],
+        results: &[(Item::Splitter, 1)],
+        fluid_ingredients: &[],
+        ticks: 60,
+    },
+    Recipe {
+        name: "chest",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 8)],
+        results: &[(Item::Chest, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "provider-chest",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Chest, 1), (Item::CopperPlate, 3)],
+        results: &[(Item::ProviderChest, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "requester-chest",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Chest, 1), (Item::CopperPlate, 3)],
+        results: &[(Item::RequesterChest, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "storage-chest",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Chest, 1), (Item::CopperPlate, 3)],
+        results: &[(Item::StorageChest, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "inserter",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 3), (Item::CopperPlate, 2)],
+        results: &[(Item::Inserter, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "drill",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 10), (Item::StoneBrick, 5)],
+        results: &[(Item::Drill, 1)],
+        fluid_ingredients: &[],
+        ticks: 120,
+    },
+    Recipe {
+        name: "furnace",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::StoneBrick, 5)],
+        results: &[(Item::Furnace, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "pole",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 1), (Item::CopperPlate, 2)],
+        results: &[(Item::Pole, 2)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "generator",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 10), (Item::CopperPlate, 5)],
+        results: &[(Item::Generator, 1)],
+        fluid_ingredients: &[],
+        ticks: 120,
+    },
+    Recipe {
+        name: "pipe",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 1)],
+        results: &[(Item::Pipe, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "pump",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Pipe, 1), (Item::IronPlate, 2)],
+        results: &[(Item::Pump, 1)],
+        fluid_ingredients: &[],
+        ticks: 120,
+    },
+    Recipe {
+        name: "tank",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 20), (Item::SteelPlate, 5)],
+        results: &[(Item::Tank, 1)],
+        fluid_ingredients: &[],
+        ticks: 180,
+    },
+    Recipe {
+        name: "offshore-pump",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::Pipe, 1),
+            (Item::IronPlate, 2),
+            (Item::CopperPlate, 3),
+        ],
+        results: &[(Item::OffshorePump, 1)],
+        fluid_ingredients: &[],
+        ticks: 60,
+    },
+    Recipe {
+        name: "boiler",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::Furnace, 1), (Item::Pipe, 4)],
+        results: &[(Item::Boiler, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "constant-combinator",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 2), (Item::CopperPlate, 5)],
+        results: &[(Item::ConstantCombinator, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "arithmetic-combinator",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 2), (Item::CopperPlate, 10)],
+        results: &[(Item::ArithmeticCombinator, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "decider-combinator",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 2), (Item::CopperPlate, 10)],
+        results: &[(Item::DeciderCombinator, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "rail",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::StoneBrick, 1),
+            (Item::IronPlate, 1),
+            (Item::SteelPlate, 1),
+        ],
+        results: &[(Item::Rail, 2)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "train-stop",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::IronPlate, 6),
+            (Item::CopperPlate, 5),
+            (Item::SteelPlate, 3),
+        ],
+        results: &[(Item::TrainStop, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "rail-signal",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 5), (Item::CopperPlate, 1)],
+        results: &[(Item::RailSignal, 1)],
+        fluid_ingredients: &[],
+        ticks: 30,
+    },
+    Recipe {
+        name: "locomotive",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::IronPlate, 20),
+            (Item::CopperPlate, 10),
+            (Item::SteelPlate, 30),
+        ],
+        results: &[(Item::Locomotive, 1)],
+        fluid_ingredients: &[],
+        ticks: 240,
+    },
+    Recipe {
+        name: "cargo-wagon",
+        category: RecipeCategory::Crafting,
+        ingredients: &[(Item::IronPlate, 10), (Item::SteelPlate, 20)],
+        results: &[(Item::CargoWagon, 1)],
+        fluid_ingredients: &[],
+        ticks: 60,
+    },
+    Recipe {
+        name: "roboport",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::IronPlate, 45),
+            (Item::CopperPlate, 45),
+            (Item::SteelPlate, 45),
+        ],
+        results: &[(Item::Roboport, 1)],
+        fluid_ingredients: &[],
+        ticks: 600,
+    },
+    Recipe {
+        name: "lab",
+        category: RecipeCategory::Crafting,
+        ingredients: &[
+            (Item::Belt, 4),
+            (Item::IronPlate, 10),
+            (Item::CopperPlate, 10),
+        ],
+        results: &[(Item::Lab, 1)],
+        fluid_ingredients: &[],
+        ticks: 120,
+    },
+];
+
+// The smelting recipe a furnace picks when given `item` as input
+pub fn smelting_recipe_for(item: Item) -> Option<&'static Recipe> {
+    RECIPES.iter().find(|recipe| {
+        recipe.category == RecipeCategory::Smelting
+            && recipe.ingredients.len() == 1
+            && recipe.ingredients[0].0 == item
+    })
+}
+
+// The hand crafting recipe that makes `item`
+pub fn crafting_recipe_for(item: Item) -> Option<&'static Recipe> {
+    RECIPES.iter().find(|recipe| {
+        recipe.category == RecipeCategory::Crafting
+            && recipe.results.iter().any(|&(result, _)| result == item)
+    })
+}
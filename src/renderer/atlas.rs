@@ -0,0 +1,202 @@
+use std::{cmp::Reverse, collections::HashMap, fs};
+
+use image::{imageops, RgbaImage};
+
+// Width of packed atlases, they grow downwards as sprites are added
+pub const ATLAS_WIDTH: u32 = 256;
+// Empty pixels around every sprite so filtering doesn't pick up its neighbours
+const PADDING: u32 = 1;
+
+// Texture coordinates of a sprite, with y going down from the top of the atlas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+// Places rectangles in rows, tallest first, and returns their positions in the order
+// they were given along with the height of all rows. None if one is wider than `width`.
+pub fn pack(sizes: &[(u32, u32)], width: u32) -> Option<(Vec<(u32, u32)>, u32)> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| (Reverse(sizes[i].1), i));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for i in order {
+        let (rect_width, rect_height) = sizes[i];
+        if rect_width > width {
+            return None;
+        }
+        if x + rect_width > width {
+            y += row_height;
+            x = 0;
+            row_height = 0;
+        }
+        positions[i] = (x, y);
+        x += rect_width;
+        row_height = row_height.max(rect_height);
+    }
+    Some((positions, y + row_height))
+}
+
+// Sprites shown one after the other, looping
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<UvRect>,
+    // Ticks each frame is shown for when playing at speed 1
+    pub frame_ticks: u32,
+}
+
+impl Animation {
+    // The frame shown `ticks` into the animation when playing at `speed`
+    pub fn frame(&self, ticks: u64, speed: u32) -> UvRect {
+        let index = ticks * speed as u64 / self.frame_ticks as u64 % self.frames.len() as u64;
+        self.frames[index as usize]
+    }
+}
+
+pub struct Atlas {
+    sprites: HashMap<String, UvRect>,
+}
+
+impl Atlas {
+    pub fn get(&self, name: &str) -> Option<UvRect> {
+        self.sprites.get(name).copied()
+    }
+
+    pub fn sprite(&self, name: &str) -> UvRect {
+        self.get(name)
+            .unwrap_or_else(|| panic!("No sprite named {} in the atlas", name))
+    }
+
+    // Animation made of the sprites `{name}-0`, `{name}-1` and so on
+    pub fn animation(&self, name: &str, frame_ticks: u32) -> Animation {
+        let frames: Vec<UvRect> = (0..)
+            .map_while(|frame| self.get(&format!("{}-{}", name, frame)))
+            .collect();
+        if frames.is_empty() {
+            panic!("No frames of animation {} in the atlas", name);
+        }
+        Animation {
+            frames,
+            frame_ticks,
+        }
+    }
+}
+
+pub struct AtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self { images: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: &str, image: RgbaImage) {
+        self.images.push((name.to_string(), image));
+    }
+
+    // Adds every PNG in `assets/{path}`, named after the file without its extension
+    pub fn add_directory(&mut self, path: &str) {
+        let directory = format!("assets/{}", path);
+        let mut files: Vec<_> = fs::read_dir(&directory)
+            .unwrap_or_else(|_| panic!("Could not read textures in {}", directory))
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.extension().is_some_and(|extension| extension == "png"))
+            .collect();
+        files.sort();
+        for file in files {
+            let image = image::open(&file)
+                .unwrap_or_else(|_| panic!("Could not read texture {}", file.display()))
+                .to_rgba8();
+            let name = file.file_stem().unwrap().to_string_lossy();
+            self.add(&name, image);
+        }
+    }
+
+    // The atlas image, top row first, and where each sprite ended up in it
+    pub fn build(&self) -> (RgbaImage, Atlas) {
+        let sizes: Vec<(u32, u32)> = self
+            .images
+            .iter()
+            .map(|(_, image)| (image.width() + 2 * PADDING, image.height() + 2 * PADDING))
+            .collect();
+        let (positions, height) = pack(&sizes, ATLAS_WIDTH)
+            .unwrap_or_else(|| panic!("Sprites must be narrower than {} pixels", ATLAS_WIDTH));
+        let height = height.max(1);
+
+        let mut pixels = RgbaImage::new(ATLAS_WIDTH, height);
+        let mut sprites = HashMap::new();
+        for ((name, image), &(x, y)) in self.images.iter().zip(positions.iter()) {
+            let (x, y) = (x + PADDING, y + PADDING);
+            imageops::replace(&mut pixels, image, x, y);
+            sprites.insert(
+                name.clone(),
+                UvRect {
+                    min_x: x as f32 / ATLAS_WIDTH as f32,
+                    min_y: y as f32 / height as f32,
+                    max_x: (x + image.width()) as f32 / ATLAS_WIDTH as f32,
+                    max_y: (y + image.height()) as f32 / height as f32,
+                },
+            );
+        }
+
+        (pixels, Atlas { sprites })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn packs_rows_tallest_first() {
+        let (positions, height) = pack(&[(10, 5), (20, 10), (15, 8), (10, 4)], 40).unwrap();
+        assert_eq!(positions, vec![(0, 10), (0, 0), (20, 0), (10, 10)]);
+        assert_eq!(height, 15);
+        assert_eq!(pack(&[(41, 1)], 40), None);
+    }
+
+    #[test]
+    fn builds_atlas_with_sprites_by_name() {
+        let mut builder = AtlasBuilder::new();
+        builder.add("red", RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255])));
+        builder.add("blue", RgbaImage::from_pixel(2, 6, Rgba([0, 0, 255, 255])));
+        let (pixels, atlas) = builder.build();
+        assert_eq!((pixels.width(), pixels.height()), (ATLAS_WIDTH, 8));
+
+        // The taller sprite goes first, each one has a pixel of padding around it
+        let blue = atlas.sprite("blue");
+        assert_eq!((blue.min_x, blue.min_y), (1.0 / 256.0, 1.0 / 8.0));
+        assert_eq!((blue.max_x, blue.max_y), (3.0 / 256.0, 7.0 / 8.0));
+        let red = atlas.sprite("red");
+        assert_eq!((red.min_x, red.max_y), (5.0 / 256.0, 3.0 / 8.0));
+        assert_eq!(pixels.get_pixel(5, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(pixels.get_pixel(4, 1), &Rgba([0, 0, 0, 0]));
+        assert_eq!(atlas.get("green"), None);
+
+        let mut builder = AtlasBuilder::new();
+        builder.add_directory("textures");
+        assert!(builder.build().1.get("belt-straight-0").is_some());
+    }
+
+    #[test]
+    fn animations_loop_through_numbered_frames() {
+        let mut builder = AtlasBuilder::new();
+        for frame in 0..3 {
+            builder.add(&format!("spin-{}", frame), RgbaImage::new(2, 2));
+        }
+        let (_, atlas) = builder.build();
+        let animation = atlas.animation("spin", 4);
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.frame(0, 1), atlas.sprite("spin-0"));
+        assert_eq!(animation.frame(7, 1), atlas.sprite("spin-1"));
+        assert_eq!(animation.frame(12, 1), atlas.sprite("spin-0"));
+        // Twice as fast skips ahead
+        assert_eq!(animation.frame(4, 2), atlas.sprite("spin-2"));
+    }
+}
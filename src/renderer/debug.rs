@@ -1,4 +1,4 @@
-use std::{ffi::c_void, panic, ptr};
+use std::{ffi::c_void, fmt, panic, ptr};
 
 use gl::types::{GLchar, GLenum, GLsizei};
 
@@ -11,6 +11,16 @@ pub struct DebugMessage {
     message: String,
 }
 
+impl fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} {:?} ({}): {}",
+            self.severity, self.source, self.type_, self.id, self.message
+        )
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum DebugSource {
     Api,
@@ -89,8 +99,8 @@ extern "system" fn debug_callback(
     message: *const GLchar,
     user_param: *mut c_void,
 ) {
-    let user_callback = user_param as *mut Box<dyn Fn()> as *const _;
-    let user_callback: &Box<dyn Fn(DebugMessage)> = unsafe { &*user_callback };
+    let user_callback = user_param as *const Box<dyn Fn(DebugMessage)>;
+    let user_callback: &dyn Fn(DebugMessage) = unsafe { &**user_callback };
 
     let msg_src = match source {
         gl::DEBUG_SOURCE_API => DebugSource::Api,
@@ -0,0 +1,34 @@
+use super::texture::Texture;
+
+// Offscreen render target drawing into a texture
+#[derive(Debug)]
+pub struct Framebuffer {
+    pub id: u32,
+    pub texture: Texture,
+}
+
+impl Framebuffer {
+    pub unsafe fn new(width: u32, height: u32) -> Self {
+        let texture = Texture::empty(width, height);
+        let mut id = 0;
+        gl::CreateFramebuffers(1, &mut id);
+        gl::NamedFramebufferTexture(id, gl::COLOR_ATTACHMENT0, texture.id, 0);
+        let status = gl::CheckNamedFramebufferStatus(id, gl::FRAMEBUFFER);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            panic!("Framebuffer is incomplete: {:#x}", status);
+        }
+        Self { id, texture }
+    }
+
+    // Draws go to the texture until `unbind`, with the viewport covering all of it
+    pub unsafe fn bind(&self) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        gl::Viewport(0, 0, self.texture.width as i32, self.texture.height as i32);
+    }
+
+    // Back to drawing to the window, which is `width` by `height`
+    pub unsafe fn unbind(&self, width: u32, height: u32) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, width as i32, height as i32);
+    }
+}
@@ -1,5 +1,10 @@
+pub mod atlas;
 pub mod debug;
+pub mod framebuffer;
+pub mod rasterizer;
 pub mod shader;
+pub mod sprite_batch;
+pub mod text;
 pub mod texture;
 pub mod vertex_array;
 pub mod vertex_buffer;
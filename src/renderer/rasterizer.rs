@@ -0,0 +1,121 @@
+use image::{Rgba, RgbaImage};
+
+use super::sprite_batch::Sprite;
+
+// Draws sprites into `image` on the CPU, sampling and blending them the way sprite.vert
+// and sprite.frag do. The bottom left corner of the image is at `origin` in world
+// units, and each world unit is `scale` pixels.
+pub fn draw_sprites(
+    image: &mut RgbaImage,
+    atlas: &RgbaImage,
+    sprites: &[Sprite],
+    origin: (f32, f32),
+    scale: f32,
+) {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    for sprite in sprites {
+        let left = (sprite.x - sprite.width / 2.0 - origin.0) * scale;
+        let bottom = (sprite.y - sprite.height / 2.0 - origin.1) * scale;
+        let (sprite_width, sprite_height) = (sprite.width * scale, sprite.height * scale);
+        let (sin, cos) = sprite.rotation.sin_cos();
+
+        // Pixels whose centers are inside the sprite, rows counted from the bottom
+        let columns = (left - 0.5).ceil().max(0.0) as u32
+            ..(left + sprite_width - 0.5).ceil().clamp(0.0, width) as u32;
+        let rows = (bottom - 0.5).ceil().max(0.0) as u32
+            ..(bottom + sprite_height - 0.5).ceil().clamp(0.0, height) as u32;
+        for row in rows {
+            for column in columns.clone() {
+                // Position in the quad, then the texture rotated around its center
+                let u = (column as f32 + 0.5 - left) / sprite_width - 0.5;
+                let v = (row as f32 + 0.5 - bottom) / sprite_height - 0.5;
+                let rotated_u = cos * u - sin * v + 0.5;
+                let rotated_v = sin * u + cos * v + 0.5;
+                let uv = sprite.uv;
+                let texel = sample(
+                    atlas,
+                    uv.min_x + (uv.max_x - uv.min_x) * rotated_u,
+                    uv.max_y + (uv.min_y - uv.max_y) * rotated_v,
+                );
+                let tint = [sprite.tint.x, sprite.tint.y, sprite.tint.z, sprite.tint.w];
+                let source: Vec<f32> = (0..4).map(|i| tint[i] * texel[i] as f32 / 255.0).collect();
+                let pixel = image.get_pixel_mut(column, height as u32 - 1 - row);
+                blend(pixel, &source);
+            }
+        }
+    }
+}
+
+// Nearest texel, clamped to the edges
+fn sample(atlas: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let x = ((x * atlas.width() as f32) as u32).min(atlas.width() - 1);
+    let y = ((y * atlas.height() as f32) as u32).min(atlas.height() - 1);
+    *atlas.get_pixel(x, y)
+}
+
+// Source alpha over the destination, for alpha too, like the game's blend function
+fn blend(pixel: &mut Rgba<u8>, source: &[f32]) {
+    let alpha = source[3];
+    for i in 0..4 {
+        let destination = pixel[i] as f32 / 255.0;
+        let color = source[i] * alpha + destination * (1.0 - alpha);
+        pixel[i] = (color * 255.0).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::atlas::UvRect;
+    use cgmath::vec4;
+
+    #[test]
+    fn draws_rotated_tinted_sprites_like_the_shader() {
+        // Red, green, blue and white quarters, top row first
+        let red = Rgba([255, 0, 0, 255]);
+        let green = Rgba([0, 255, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        let atlas = RgbaImage::from_fn(2, 2, |x, y| {
+            [[red, green], [blue, white]][y as usize][x as usize]
+        });
+        let sprite = Sprite {
+            x: 11.0,
+            y: 21.0,
+            width: 2.0,
+            height: 2.0,
+            rotation: 0.0,
+            uv: UvRect {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 1.0,
+                max_y: 1.0,
+            },
+            tint: vec4(1.0, 1.0, 1.0, 1.0),
+        };
+
+        let mut image = RgbaImage::from_pixel(3, 2, Rgba([0, 0, 0, 255]));
+        draw_sprites(&mut image, &atlas, &[sprite], (10.0, 20.0), 1.0);
+        assert_eq!(image.get_pixel(0, 0), &red);
+        assert_eq!(image.get_pixel(1, 1), &white);
+        assert_eq!(image.get_pixel(2, 0), &Rgba([0, 0, 0, 255]));
+
+        // A quarter turn brings the bottom left of the texture to the top left, half
+        // transparent sprites are blended over what was there
+        let turned = Sprite {
+            rotation: std::f32::consts::FRAC_PI_2,
+            tint: vec4(1.0, 1.0, 1.0, 0.5),
+            ..sprite
+        };
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 255]));
+        draw_sprites(&mut image, &atlas, &[turned], (10.0, 20.0), 1.0);
+        assert_eq!(image.get_pixel(0, 0), &Rgba([0, 0, 128, 191]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([128, 0, 0, 191]));
+
+        // Scaled up, each texel covers several pixels
+        let mut image = RgbaImage::new(4, 4);
+        draw_sprites(&mut image, &atlas, &[sprite], (10.0, 20.0), 2.0);
+        assert_eq!(image.get_pixel(1, 1), &red);
+        assert_eq!(image.get_pixel(2, 3), &white);
+    }
+}
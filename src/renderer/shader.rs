@@ -1,83 +1,132 @@
-use cgmath::{Matrix, Matrix2, Matrix4, Vector3, Vector4};
+use cgmath::{Matrix, Matrix4, Vector4};
 use gl::types::*;
-use std::{
-    ffi::{CStr, CString},
-    fs, ptr, str,
-};
+use std::{cell::RefCell, collections::HashMap, ffi::CString, fs, ptr};
 
 pub struct Shader {
     id: GLuint,
+    // Uniform locations looked up so far, by name
+    locations: RefCell<HashMap<String, GLint>>,
+    // Vertex and fragment shader files in assets/shaders, None if built from source
+    files: Option<(String, String)>,
+    // Log of the last reload that failed, the previous program is still used then
+    pub error: Option<String>,
 }
 
 impl Shader {
     pub fn from_file(vs_path: &str, fs_path: &str) -> Self {
-        let vs_source = fs::read_to_string(format!("assets/shaders/{}", vs_path))
-            .expect(&format!("Could not read vertex shader {}", vs_path));
-        let fs_source = fs::read_to_string(format!("assets/shaders/{}", fs_path))
-            .expect(&format!("Could not read fragment shader {}", fs_path));
-
-        Self::from_source(&vs_source, &fs_source)
+        let program_id =
+            Self::build_files(vs_path, fs_path).unwrap_or_else(|error| panic!("{}", error));
+        Self {
+            files: Some((vs_path.to_string(), fs_path.to_string())),
+            ..Self::new(program_id)
+        }
     }
 
-    pub fn from_source(vs_source: &str, fs_source: &str) -> Self {
-        let vs_id = Self::compile_shader(vs_source, gl::VERTEX_SHADER);
-        let fs_id = Self::compile_shader(fs_source, gl::FRAGMENT_SHADER);
-        let program_id = Self::link_program(vs_id, fs_id);
-        unsafe {
-            gl::DeleteShader(vs_id);
-            gl::DeleteShader(fs_id);
+    fn new(id: GLuint) -> Self {
+        Self {
+            id,
+            locations: RefCell::new(HashMap::new()),
+            files: None,
+            error: None,
         }
+    }
 
-        Self { id: program_id }
+    // Whether the shader was built from the file with this name
+    pub fn uses(&self, file: &str) -> bool {
+        self.files
+            .as_ref()
+            .is_some_and(|(vs_path, fs_path)| vs_path == file || fs_path == file)
     }
 
-    pub fn enable(&self) {
-        unsafe {
-            gl::UseProgram(self.id);
+    // Builds the program again from its files and switches to it once it has linked.
+    // On failure the current program is kept and the log is stored in `error`.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let (vs_path, fs_path) = match &self.files {
+            Some(files) => files.clone(),
+            None => return Ok(()),
+        };
+        match Self::build_files(&vs_path, &fs_path) {
+            Ok(program_id) => {
+                unsafe {
+                    gl::DeleteProgram(self.id);
+                }
+                self.id = program_id;
+                self.locations.borrow_mut().clear();
+                self.error = None;
+                Ok(())
+            }
+            Err(error) => {
+                self.error = Some(error.clone());
+                Err(error)
+            }
         }
     }
 
-    pub fn set_mat2(&self, name: &CStr, matrix: Matrix2<f32>) {
+    fn build_files(vs_path: &str, fs_path: &str) -> Result<GLuint, String> {
+        let read = |path: &str| {
+            fs::read_to_string(format!("assets/shaders/{}", path))
+                .map_err(|error| format!("Could not read shader {}: {}", path, error))
+        };
+        Self::build(&read(vs_path)?, &read(fs_path)?)
+            .map_err(|error| format!("{} + {}: {}", vs_path, fs_path, error))
+    }
+
+    fn build(vs_source: &str, fs_source: &str) -> Result<GLuint, String> {
+        let vs_id = Self::compile_shader(vs_source, gl::VERTEX_SHADER)?;
+        let fs_id = match Self::compile_shader(fs_source, gl::FRAGMENT_SHADER) {
+            Ok(fs_id) => fs_id,
+            Err(error) => {
+                unsafe { gl::DeleteShader(vs_id) };
+                return Err(error);
+            }
+        };
+        let program_id = Self::link_program(vs_id, fs_id);
         unsafe {
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
-            gl::UniformMatrix2fv(location, 1, gl::FALSE, matrix.as_ptr());
+            gl::DeleteShader(vs_id);
+            gl::DeleteShader(fs_id);
         }
+        program_id
     }
 
-    pub fn set_mat4(&self, name: &CStr, matrix: Matrix4<f32>) {
+    pub fn enable(&self) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
-            gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr());
+            gl::UseProgram(self.id);
         }
     }
 
-    pub fn set_vec3(&self, name: &CStr, vec: Vector3<f32>) {
+    pub fn set_mat4(&self, name: &str, matrix: Matrix4<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
-            gl::Uniform3f(location, vec.x, vec.y, vec.z);
+            let location = self.location(name);
+            gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr());
         }
     }
 
-    pub fn set_vec4(&self, name: &CStr, vec: Vector4<f32>) {
+    pub fn set_vec4(&self, name: &str, vec: Vector4<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = self.location(name);
             gl::Uniform4f(location, vec.x, vec.y, vec.z, vec.w);
         }
     }
 
-    pub fn set_int(&self, name: &CStr, int: i32) {
-        unsafe {
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
-            gl::Uniform1i(location, int);
+    fn location(&self, name: &str) -> GLint {
+        if let Some(&location) = self.locations.borrow().get(name) {
+            return location;
         }
+        let c_name = CString::new(name).unwrap();
+        let location = unsafe { gl::GetUniformLocation(self.id, c_name.as_ptr()) };
+        self.locations
+            .borrow_mut()
+            .insert(name.to_string(), location);
+        location
     }
 
-    fn compile_shader(src: &str, ty: GLenum) -> GLuint {
+    fn compile_shader(src: &str, ty: GLenum) -> Result<GLuint, String> {
         let shader;
         unsafe {
             shader = gl::CreateShader(ty);
 
-            let c_str = CString::new(src.as_bytes()).unwrap();
+            let c_str = CString::new(src.as_bytes())
+                .map_err(|_| "Shader source contains a null character".to_string())?;
             gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
             gl::CompileShader(shader);
 
@@ -87,26 +136,22 @@ impl Shader {
             if status != (gl::TRUE as GLint) {
                 let mut len = 0;
                 gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buf = Vec::with_capacity(len as usize);
-                buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+                // subtract 1 to skip the trailing null character
+                let mut buf = vec![0u8; (len.max(1) as usize) - 1];
                 gl::GetShaderInfoLog(
                     shader,
                     len,
                     ptr::null_mut(),
                     buf.as_mut_ptr() as *mut GLchar,
                 );
-                panic!(
-                    "{}",
-                    str::from_utf8(&buf)
-                        .ok()
-                        .expect("ShaderInfoLog not valid utf8")
-                );
+                gl::DeleteShader(shader);
+                return Err(String::from_utf8_lossy(&buf).into_owned());
             }
         }
-        shader
+        Ok(shader)
     }
 
-    fn link_program(vs: GLuint, fs: GLuint) -> GLuint {
+    fn link_program(vs: GLuint, fs: GLuint) -> Result<GLuint, String> {
         unsafe {
             let program = gl::CreateProgram();
             gl::AttachShader(program, vs);
@@ -120,22 +165,18 @@ impl Shader {
             if status != (gl::TRUE as GLint) {
                 let mut len: GLint = 0;
                 gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buf = Vec::with_capacity(len as usize);
-                buf.set_len((len as usize) - 1); // subtract 1 to skip the trailing null character
+                // subtract 1 to skip the trailing null character
+                let mut buf = vec![0u8; (len.max(1) as usize) - 1];
                 gl::GetProgramInfoLog(
                     program,
                     len,
                     ptr::null_mut(),
                     buf.as_mut_ptr() as *mut GLchar,
                 );
-                panic!(
-                    "{}",
-                    str::from_utf8(&buf)
-                        .ok()
-                        .expect("ProgramInfoLog not valid utf8")
-                );
+                gl::DeleteProgram(program);
+                return Err(String::from_utf8_lossy(&buf).into_owned());
             }
-            program
+            Ok(program)
         }
     }
 }
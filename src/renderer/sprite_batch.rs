@@ -0,0 +1,180 @@
+use cgmath::{Matrix4, Vector4};
+
+use super::{
+    atlas::UvRect, shader::Shader, texture::Texture, vertex_array::VertexArray,
+    vertex_buffer::VertexBuffer, VertexBufferElement,
+};
+
+// Sprites drawn by a single instanced draw call, larger batches take several
+pub const MAX_SPRITES: usize = 128 * 128;
+// Floats per instance: position, size, rotation, texture coordinates and tint
+pub const SPRITE_FLOATS: usize = 13;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    // Center of the sprite in world units
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // Rotation of the texture in radians, anti-clockwise
+    pub rotation: f32,
+    pub uv: UvRect,
+    pub tint: Vector4<f32>,
+}
+
+// Sprites collected over a frame in the order they are drawn
+pub struct SpriteBatch {
+    data: Vec<f32>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    pub fn push(&mut self, sprite: Sprite) {
+        self.data.extend_from_slice(&[
+            sprite.x,
+            sprite.y,
+            sprite.width,
+            sprite.height,
+            sprite.rotation,
+            sprite.uv.min_x,
+            sprite.uv.min_y,
+            sprite.uv.max_x,
+            sprite.uv.max_y,
+            sprite.tint.x,
+            sprite.tint.y,
+            sprite.tint.z,
+            sprite.tint.w,
+        ]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / SPRITE_FLOATS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // Instance data split into the chunks that fit in one draw call
+    pub fn chunks(&self) -> impl Iterator<Item = &[f32]> {
+        self.data.chunks(MAX_SPRITES * SPRITE_FLOATS)
+    }
+}
+
+pub struct SpriteRenderer {
+    pub shader: Shader,
+    instances: VertexBuffer,
+    vertex_array: VertexArray,
+}
+
+impl SpriteRenderer {
+    // `quad` holds the position and texture coordinates of a unit quad
+    pub unsafe fn new(quad: &[f32]) -> Self {
+        let vertices = VertexBuffer::new(
+            quad,
+            vec![
+                VertexBufferElement::floats(2),
+                VertexBufferElement::floats(2),
+            ],
+        );
+        let instances = VertexBuffer::instanced(
+            MAX_SPRITES,
+            vec![
+                VertexBufferElement::floats(2),
+                VertexBufferElement::floats(2),
+                VertexBufferElement::floats(1),
+                VertexBufferElement::floats(4),
+                VertexBufferElement::floats(4),
+            ],
+        );
+        let buffers = [vertices, instances];
+        let vertex_array = VertexArray::new(&buffers);
+        let [_, instances] = buffers;
+        Self {
+            shader: Shader::from_file("sprite.vert", "sprite.frag"),
+            instances,
+            vertex_array,
+        }
+    }
+
+    pub unsafe fn draw(
+        &self,
+        batch: &SpriteBatch,
+        atlas: &Texture,
+        view: Matrix4<f32>,
+        projection: Matrix4<f32>,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+        atlas.bind_to_unit(0);
+        self.shader.enable();
+        self.shader.set_mat4("view", view);
+        self.shader.set_mat4("projection", projection);
+        self.vertex_array.bind();
+        for chunk in batch.chunks() {
+            self.instances.update(chunk);
+            gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, (chunk.len() / SPRITE_FLOATS) as i32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::vec4;
+
+    fn sprite(x: f32) -> Sprite {
+        Sprite {
+            x,
+            y: 2.0,
+            width: 32.0,
+            height: 16.0,
+            rotation: 0.5,
+            uv: UvRect {
+                min_x: 0.0,
+                min_y: 0.25,
+                max_x: 0.5,
+                max_y: 1.0,
+            },
+            tint: vec4(1.0, 0.5, 0.25, 1.0),
+        }
+    }
+
+    #[test]
+    fn batch_lays_out_instances_in_draw_order() {
+        let mut batch = SpriteBatch::new();
+        batch.push(sprite(1.0));
+        batch.push(sprite(5.0));
+        assert_eq!(batch.len(), 2);
+
+        let chunks: Vec<&[f32]> = batch.chunks().collect();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0],
+            &[
+                1.0, 2.0, 32.0, 16.0, 0.5, 0.0, 0.25, 0.5, 1.0, 1.0, 0.5, 0.25, 1.0, 5.0, 2.0,
+                32.0, 16.0, 0.5, 0.0, 0.25, 0.5, 1.0, 1.0, 0.5, 0.25, 1.0
+            ]
+        );
+
+        batch.clear();
+        assert!(batch.is_empty());
+        for _ in 0..MAX_SPRITES + 1 {
+            batch.push(sprite(0.0));
+        }
+        let sizes: Vec<usize> = batch
+            .chunks()
+            .map(|chunk| chunk.len() / SPRITE_FLOATS)
+            .collect();
+        assert_eq!(sizes, vec![MAX_SPRITES, 1]);
+    }
+}
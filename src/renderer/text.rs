@@ -0,0 +1,134 @@
+use cgmath::Vector4;
+
+use super::{
+    atlas::{Atlas, UvRect},
+    sprite_batch::{Sprite, SpriteBatch},
+};
+
+// Font pixels taken up by a character, including the gap to the next one
+pub const GLYPH_WIDTH: f32 = 6.0;
+pub const GLYPH_HEIGHT: f32 = 8.0;
+// The sheet holds the ASCII characters from space to DEL, 16 to a row
+const SHEET_COLUMNS: u32 = 16;
+const SHEET_ROWS: u32 = 6;
+const FIRST_CHARACTER: u32 = ' ' as u32;
+
+// Monospaced bitmap font, drawn from the `font` sheet in the atlas
+pub struct Font {
+    sheet: UvRect,
+}
+
+impl Font {
+    pub fn new(atlas: &Atlas) -> Self {
+        Self {
+            sheet: atlas.sprite("font"),
+        }
+    }
+
+    // Characters that aren't in the sheet are drawn as '?'
+    pub fn glyph(&self, character: char) -> UvRect {
+        let index = match character as u32 {
+            code @ 32..=127 => code - FIRST_CHARACTER,
+            _ => '?' as u32 - FIRST_CHARACTER,
+        };
+        let width = (self.sheet.max_x - self.sheet.min_x) / SHEET_COLUMNS as f32;
+        let height = (self.sheet.max_y - self.sheet.min_y) / SHEET_ROWS as f32;
+        let min_x = self.sheet.min_x + (index % SHEET_COLUMNS) as f32 * width;
+        let min_y = self.sheet.min_y + (index / SHEET_COLUMNS) as f32 * height;
+        UvRect {
+            min_x,
+            min_y,
+            max_x: min_x + width,
+            max_y: min_y + height,
+        }
+    }
+
+    // Width and height of the text with `scale` units per font pixel
+    pub fn measure(text: &str, scale: f32) -> (f32, f32) {
+        let columns = text
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        (
+            columns as f32 * GLYPH_WIDTH * scale,
+            text.lines().count() as f32 * GLYPH_HEIGHT * scale,
+        )
+    }
+
+    // Adds the text with its top left corner at (x, y), lines going down. The same
+    // sprites work in screen space and world space since both have y going up.
+    pub fn draw(
+        &self,
+        batch: &mut SpriteBatch,
+        text: &str,
+        x: f32,
+        y: f32,
+        scale: f32,
+        color: Vector4<f32>,
+    ) {
+        let (width, height) = (GLYPH_WIDTH * scale, GLYPH_HEIGHT * scale);
+        for (row, line) in text.lines().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                if character == ' ' {
+                    continue;
+                }
+                batch.push(Sprite {
+                    x: x + (column as f32 + 0.5) * width,
+                    y: y - (row as f32 + 0.5) * height,
+                    width,
+                    height,
+                    rotation: 0.0,
+                    uv: self.glyph(character),
+                    tint: color,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{atlas::AtlasBuilder, sprite_batch::SPRITE_FLOATS};
+    use cgmath::vec4;
+
+    #[test]
+    fn lays_out_glyphs_from_the_sheet() {
+        let font = Font {
+            sheet: UvRect {
+                min_x: 0.5,
+                min_y: 0.0,
+                max_x: 1.0,
+                max_y: 0.75,
+            },
+        };
+        // 'A' is the second glyph of the third row
+        let a = font.glyph('A');
+        assert_eq!((a.min_x, a.max_x), (0.5 + 0.5 / 16.0, 0.5 + 1.0 / 16.0));
+        assert_eq!((a.min_y, a.max_y), (0.25, 0.375));
+        assert_eq!(font.glyph('é'), font.glyph('?'));
+
+        let mut batch = SpriteBatch::new();
+        font.draw(
+            &mut batch,
+            "A B\nC",
+            10.0,
+            100.0,
+            2.0,
+            vec4(1.0, 1.0, 1.0, 1.0),
+        );
+        assert_eq!(batch.len(), 3);
+        let centers: Vec<(f32, f32)> = batch
+            .chunks()
+            .flat_map(|chunk| chunk.chunks(SPRITE_FLOATS))
+            .map(|sprite| (sprite[0], sprite[1]))
+            .collect();
+        assert_eq!(centers, vec![(16.0, 92.0), (40.0, 92.0), (16.0, 76.0)]);
+        assert_eq!(Font::measure("A B\nC", 2.0), (36.0, 32.0));
+
+        let mut builder = AtlasBuilder::new();
+        builder.add_directory("textures");
+        Font::new(&builder.build().1);
+    }
+}
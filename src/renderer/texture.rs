@@ -1,4 +1,4 @@
-use std::{ffi::c_void, path::Path};
+use std::ffi::c_void;
 
 use image::{DynamicImage, GenericImageView};
 
@@ -14,7 +14,7 @@ impl Texture {
         let image = if flip_vertical { image.flipv() } else { image };
 
         let width = image.width();
-        let height = image.width();
+        let height = image.height();
 
         let (data, gl_format) = match image {
             DynamicImage::ImageRgb8(image) => (image.into_raw(), gl::RGB),
@@ -45,12 +45,17 @@ impl Texture {
         Self { id, width, height }
     }
 
-    pub unsafe fn from_path<P>(path: P, flip_vertical: bool) -> Self
-    where
-        P: AsRef<Path>,
-    {
-        let image = image::open(path).expect("Couldn't open image");
-        Self::new(image, flip_vertical)
+    // Texture without contents, to be rendered into
+    pub unsafe fn empty(width: u32, height: u32) -> Self {
+        let mut id = 0;
+        gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id);
+        gl::TextureParameteri(id, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl::TextureParameteri(id, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        gl::TextureParameteri(id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TextureParameteri(id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TextureStorage2D(id, 1, gl::RGBA8, width as i32, height as i32);
+
+        Self { id, width, height }
     }
 
     pub unsafe fn bind_to_unit(&self, unit: u32) {
@@ -3,7 +3,6 @@ use super::vertex_buffer::VertexBuffer;
 #[derive(Debug)]
 pub struct VertexArray {
     id: u32,
-    elements: u32,
 }
 
 impl VertexArray {
@@ -18,6 +17,7 @@ impl VertexArray {
                 .iter()
                 .fold(0, |prev, element| prev + element.mem_size());
             gl::VertexArrayVertexBuffer(id, binding_index as u32, buffer.id, 0, stride as i32);
+            gl::VertexArrayBindingDivisor(id, binding_index as u32, buffer.divisor);
 
             let mut offset = 0;
             for element in buffer.layout.iter() {
@@ -37,9 +37,7 @@ impl VertexArray {
             }
         }
 
-        let elements = buffers.iter().min_by_key(|b| b.elements).unwrap().elements;
-
-        Self { id, elements }
+        Self { id }
     }
 
     pub unsafe fn bind(&self) {
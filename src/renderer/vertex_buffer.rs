@@ -1,4 +1,4 @@
-use std::mem;
+use std::{ffi::c_void, mem};
 
 use gl::types::{GLfloat, GLsizeiptr, GLuint};
 
@@ -7,8 +7,9 @@ use super::VertexBufferElement;
 #[derive(Debug)]
 pub struct VertexBuffer {
     pub id: GLuint,
-    pub elements: u32,
     pub layout: Vec<VertexBufferElement>,
+    // Advances once per instance instead of once per vertex when 1
+    pub divisor: u32,
 }
 
 impl VertexBuffer {
@@ -17,17 +18,33 @@ impl VertexBuffer {
         gl::CreateBuffers(1, &mut id);
         gl::NamedBufferStorage(
             id,
-            (data.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-            mem::transmute(&data[0]),
+            mem::size_of_val(data) as GLsizeiptr,
+            data.as_ptr() as *const c_void,
             gl::DYNAMIC_STORAGE_BIT,
         );
 
-        let elements =
-            data.len() as u32 / layout.iter().fold(0, |prev, element| prev + element.size);
         Self {
             id,
-            elements,
             layout,
+            divisor: 0,
         }
     }
+
+    // Per-instance data with room for `instances` instances, filled in with `update`
+    pub unsafe fn instanced(instances: usize, layout: Vec<VertexBufferElement>) -> Self {
+        let floats = layout.iter().fold(0, |prev, element| prev + element.size) as usize;
+        let mut buffer = Self::new(&vec![0.0; instances * floats], layout);
+        buffer.divisor = 1;
+        buffer
+    }
+
+    // Overwrites the start of the buffer, `data` must fit in it
+    pub unsafe fn update(&self, data: &[GLfloat]) {
+        gl::NamedBufferSubData(
+            self.id,
+            0,
+            mem::size_of_val(data) as GLsizeiptr,
+            data.as_ptr() as *const c_void,
+        );
+    }
 }
@@ -0,0 +1,350 @@
+use std::fs;
+
+use crate::{inventory::ItemStack, item::Item};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Technology {
+    pub name: String,
+    pub prerequisites: Vec<String>,
+    // Science packs a lab consumes for each unit
+    pub ingredients: Vec<ItemStack>,
+    pub units: u32,
+    // Lab ticks each unit takes at full power
+    pub unit_ticks: u32,
+    // Names of the entities that can't be placed before this is researched
+    pub unlocks: Vec<String>,
+}
+
+impl Technology {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            prerequisites: Vec::new(),
+            ingredients: Vec::new(),
+            units: 1,
+            unit_ticks: 60,
+            unlocks: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TechTree {
+    pub technologies: Vec<Technology>,
+}
+
+impl TechTree {
+    pub fn empty() -> Self {
+        Self {
+            technologies: Vec::new(),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Self {
+        let source = fs::read_to_string(format!("assets/data/{}", path))
+            .unwrap_or_else(|_| panic!("Could not read technologies {}", path));
+        Self::parse(&source).unwrap_or_else(|error| panic!("{}: {}", path, error))
+    }
+
+    // Reads technologies from blocks of `key = value` lines, each block starting with
+    // the name of the technology in brackets. Lines starting with # are comments.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut technologies: Vec<Technology> = Vec::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: String| Err(format!("line {}: {}", number + 1, message));
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                if technologies
+                    .iter()
+                    .any(|technology| technology.name == name)
+                {
+                    return error(format!("technology {} is defined twice", name));
+                }
+                technologies.push(Technology::new(name));
+                continue;
+            }
+
+            let technology = match technologies.last_mut() {
+                Some(technology) => technology,
+                None => return error("expected a [technology] first".to_string()),
+            };
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return error(format!("expected `key = value`, got `{}`", line)),
+            };
+            let list: Vec<&str> = value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .collect();
+            match key {
+                "prerequisites" => {
+                    technology.prerequisites = list.iter().map(|name| name.to_string()).collect()
+                }
+                "unlocks" => {
+                    technology.unlocks = list.iter().map(|name| name.to_string()).collect()
+                }
+                "ingredients" => {
+                    technology.ingredients.clear();
+                    for entry in list {
+                        let stack = entry.split_once(' ').and_then(|(name, count)| {
+                            Some(ItemStack::new(
+                                Item::from_name(name)?,
+                                count.trim().parse().ok()?,
+                            ))
+                        });
+                        match stack {
+                            Some(stack) if stack.item.is_science_pack() && stack.count > 0 => {
+                                technology.ingredients.push(stack)
+                            }
+                            _ => {
+                                return error(format!(
+                                    "`{}` is not a science pack and count",
+                                    entry
+                                ))
+                            }
+                        }
+                    }
+                }
+                "units" | "unit-ticks" => {
+                    let number = match value.parse::<u32>() {
+                        Ok(number) if number > 0 => number,
+                        _ => return error(format!("{} must be a positive number", key)),
+                    };
+                    if key == "units" {
+                        technology.units = number;
+                    } else {
+                        technology.unit_ticks = number;
+                    }
+                }
+                _ => return error(format!("unknown key {}", key)),
+            }
+        }
+
+        let tree = Self { technologies };
+        for technology in tree.technologies.iter() {
+            if let Some(missing) = technology
+                .prerequisites
+                .iter()
+                .find(|name| tree.find(name).is_none())
+            {
+                return Err(format!(
+                    "{} requires unknown technology {}",
+                    technology.name, missing
+                ));
+            }
+        }
+        if let Some(technology) = tree.find_cycle() {
+            return Err(format!(
+                "{} depends on itself through its prerequisites",
+                technology
+            ));
+        }
+        Ok(tree)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.technologies
+            .iter()
+            .position(|technology| technology.name == name)
+    }
+
+    // A technology that can never become available because it is its own prerequisite
+    fn find_cycle(&self) -> Option<&str> {
+        // Repeatedly marks technologies whose prerequisites are all marked
+        let mut reachable = vec![false; self.technologies.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, technology) in self.technologies.iter().enumerate() {
+                if !reachable[i]
+                    && technology
+                        .prerequisites
+                        .iter()
+                        .all(|name| self.find(name).is_some_and(|index| reachable[index]))
+                {
+                    reachable[i] = true;
+                    changed = true;
+                }
+            }
+        }
+        (0..self.technologies.len())
+            .find(|&i| !reachable[i])
+            .map(|i| self.technologies[i].name.as_str())
+    }
+}
+
+// Research state of a world
+#[derive(Clone, Debug)]
+pub struct Research {
+    pub tree: TechTree,
+    pub finished: Vec<bool>,
+    // Units done of each technology
+    pub progress: Vec<u32>,
+    pub current: Option<usize>,
+}
+
+impl Research {
+    pub fn new(tree: TechTree) -> Self {
+        Self {
+            finished: vec![false; tree.technologies.len()],
+            progress: vec![0; tree.technologies.len()],
+            current: None,
+            tree,
+        }
+    }
+
+    // Entities are locked while a technology that unlocks them isn't finished
+    pub fn is_unlocked(&self, name: &str) -> bool {
+        !self
+            .tree
+            .technologies
+            .iter()
+            .zip(self.finished.iter())
+            .any(|(technology, &finished)| {
+                !finished && technology.unlocks.iter().any(|unlock| unlock == name)
+            })
+    }
+
+    // Whether the technology can be researched now
+    pub fn is_available(&self, index: usize) -> bool {
+        !self.finished[index]
+            && self.tree.technologies[index]
+                .prerequisites
+                .iter()
+                .all(|name| self.tree.find(name).is_some_and(|i| self.finished[i]))
+    }
+
+    pub fn available(&self) -> Vec<usize> {
+        (0..self.tree.technologies.len())
+            .filter(|&index| self.is_available(index))
+            .collect()
+    }
+
+    // Switches research to the named technology, returns whether it is available
+    pub fn start(&mut self, name: &str) -> bool {
+        match self.tree.find(name) {
+            Some(index) if self.is_available(index) => {
+                self.current = Some(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn current_technology(&self) -> Option<&Technology> {
+        self.current.map(|index| &self.tree.technologies[index])
+    }
+
+    // Adds a finished unit to the current research, completing it after the last one
+    pub fn add_unit(&mut self) {
+        let index = match self.current {
+            Some(index) => index,
+            None => return,
+        };
+        self.progress[index] += 1;
+        if self.progress[index] >= self.tree.technologies[index].units {
+            self.finished[index] = true;
+            self.current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{generator::Generator, lab::Lab, pole::Pole, Entity},
+        grid::{Belt, BeltTier, Direction, Grid},
+    };
+
+    const TREE: &str = "
+        # Comments and blank lines are skipped
+        [logistics]
+        ingredients = automation-science-pack 1
+        units = 2
+        unit-ticks = 30
+        unlocks = fast-belt, splitter
+
+        [railway]
+        prerequisites = logistics
+        ingredients = automation-science-pack 1, logistic-science-pack 2
+        unlocks = rail
+    ";
+
+    #[test]
+    fn parses_technologies() {
+        let tree = TechTree::parse(TREE).unwrap();
+        assert_eq!(tree.technologies.len(), 2);
+        let railway = &tree.technologies[1];
+        assert_eq!(railway.prerequisites, vec!["logistics".to_string()]);
+        assert_eq!(
+            railway.ingredients,
+            vec![
+                ItemStack::new(Item::AutomationSciencePack, 1),
+                ItemStack::new(Item::LogisticSciencePack, 2)
+            ]
+        );
+        assert_eq!(tree.technologies[0].unit_ticks, 30);
+
+        assert!(TechTree::from_file("technologies.txt")
+            .find("logistics")
+            .is_some());
+    }
+
+    #[test]
+    fn rejects_broken_trees() {
+        let error = TechTree::parse("[a]\nunits = many").unwrap_err();
+        assert_eq!(error, "line 2: units must be a positive number");
+        assert!(TechTree::parse("[a]\ningredients = iron-plate 1").is_err());
+        assert!(TechTree::parse("[a]\nprerequisites = b").is_err());
+        assert!(TechTree::parse("[a]\nprerequisites = b\n[b]\nprerequisites = a").is_err());
+    }
+
+    #[test]
+    fn labs_research_and_unlock_entities() {
+        let mut grid = Grid::new();
+        grid.research = Research::new(TechTree::parse(TREE).unwrap());
+        let fast_belt = Belt {
+            tier: BeltTier::Fast,
+            ..Belt::straight(Direction::East)
+        };
+        assert!(!grid.place_belt(0, 0, fast_belt));
+        assert!(grid.place_belt(0, 0, Belt::straight(Direction::East)));
+        assert!(!grid.place_splitter(0, 2, Direction::North));
+        assert!(!grid.research.start("railway"));
+        assert!(grid.research.start("logistics"));
+
+        let mut generator = Generator::new();
+        generator.insert(Item::Coal);
+        grid.place_entity(5, 5, Entity::Generator(generator));
+        grid.place_entity(5, 6, Entity::Pole(Pole::new()));
+        grid.place_entity(6, 6, Entity::Lab(Lab::new()));
+        for _ in 0..3 {
+            assert!(grid.drop_item(6, 6, Direction::North, Item::AutomationSciencePack));
+        }
+        assert!(!grid.drop_item(6, 6, Direction::North, Item::IronPlate));
+
+        for _ in 0..100 {
+            grid.tick();
+        }
+        assert!(grid.research.finished[0]);
+        assert_eq!(grid.research.current, None);
+        assert!(grid.place_belt(0, 0, fast_belt));
+        assert!(grid.place_splitter(0, 2, Direction::North));
+        assert!(grid.research.start("railway"));
+        match grid.get_entity(6, 6) {
+            Some(Entity::Lab(lab)) => {
+                assert_eq!(lab.inventory.count(Item::AutomationSciencePack), 1)
+            }
+            _ => panic!(),
+        }
+    }
+}
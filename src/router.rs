@@ -0,0 +1,295 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::grid::{Belt, Direction, Grid, Underground, MAX_UNDERGROUND_DISTANCE};
+
+// Small extra cost for turning so straight paths are preferred among equally long ones
+const TURN_COST: u32 = 1;
+// Cost of a single belt tile
+const TILE_COST: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    x: isize,
+    y: isize,
+    // Direction items travel in when entering the tile
+    direction: Direction,
+    // Set for the first tile and underground exits, which can't turn
+    straight: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Candidate {
+    estimate: u32,
+    cost: u32,
+    // Index of the visit in the search's list of visits
+    visit: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed to make the max-heap pop the lowest estimate first
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| other.cost.cmp(&self.cost))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Belt(Direction),
+    Hop(Direction),
+}
+
+// A node reached through a particular path. Visits are never changed once added, so
+// the path leading up to one stays the same even when a cheaper way to its node is
+// found later.
+#[derive(Clone, Copy, Debug)]
+struct Visit {
+    node: Node,
+    // The visit before this one and the step taken from it
+    previous: Option<(usize, Step)>,
+}
+
+// Finds the shortest belt path from `start` to `goal` over empty tiles, with items
+// entering the goal tile travelling in `goal_direction`. Returns the belts to place in
+// order from the start.
+pub fn route(
+    grid: &Grid,
+    start: (isize, isize),
+    goal: (isize, isize),
+    goal_direction: Direction,
+    allow_underground: bool,
+) -> Option<Vec<(isize, isize, Belt)>> {
+    let is_free = |x: isize, y: isize| grid.in_bounds(x, y) && grid.get_entity(x, y).is_none();
+    if !is_free(start.0, start.1) || !is_free(goal.0, goal.1) {
+        return None;
+    }
+
+    let heuristic =
+        |x: isize, y: isize| ((x - goal.0).abs() + (y - goal.1).abs()) as u32 * TILE_COST;
+
+    let mut open = BinaryHeap::new();
+    let mut costs = HashMap::new();
+    let mut visits = Vec::new();
+    for direction in Direction::ALL.iter() {
+        let node = Node {
+            x: start.0,
+            y: start.1,
+            direction: *direction,
+            straight: true,
+        };
+        costs.insert(node, 0);
+        open.push(Candidate {
+            estimate: heuristic(start.0, start.1),
+            cost: 0,
+            visit: visits.len(),
+        });
+        visits.push(Visit {
+            node,
+            previous: None,
+        });
+    }
+
+    while let Some(Candidate { cost, visit, .. }) = open.pop() {
+        let node = visits[visit].node;
+        if costs.get(&node).is_some_and(|&best| cost > best) {
+            continue;
+        }
+
+        if (node.x, node.y) == goal && node.direction == goal_direction {
+            return Some(build_path(&visits, visit, goal_direction));
+        }
+
+        let mut neighbours = Vec::new();
+        for output in Direction::ALL.iter() {
+            if *output == node.direction.flip() || (node.straight && *output != node.direction) {
+                continue;
+            }
+
+            let (x, y) = output.offset(node.x, node.y);
+            if is_free(x, y) && !path_contains(&visits, visit, x, y) {
+                let turn_cost = if *output == node.direction {
+                    0
+                } else {
+                    TURN_COST
+                };
+                let next = Node {
+                    x,
+                    y,
+                    direction: *output,
+                    straight: false,
+                };
+                neighbours.push((next, Step::Belt(*output), TILE_COST + turn_cost));
+            }
+        }
+
+        // Underground entrance on this tile, going straight, with the exit further ahead
+        if allow_underground && (node.x, node.y) != start && !node.straight {
+            let direction = node.direction;
+            let (mut x, mut y) = direction.offset(node.x, node.y);
+            for distance in 2..=MAX_UNDERGROUND_DISTANCE {
+                let (next_x, next_y) = direction.offset(x, y);
+                x = next_x;
+                y = next_y;
+                if is_free(x, y) && !path_contains(&visits, visit, x, y) {
+                    let next = Node {
+                        x,
+                        y,
+                        direction,
+                        straight: true,
+                    };
+                    neighbours.push((
+                        next,
+                        Step::Hop(direction),
+                        distance as u32 * TILE_COST + TURN_COST,
+                    ));
+                }
+            }
+        }
+
+        for (next, step, step_cost) in neighbours {
+            let next_cost = cost + step_cost;
+            if costs.get(&next).map_or(true, |&best| next_cost < best) {
+                costs.insert(next, next_cost);
+                open.push(Candidate {
+                    estimate: next_cost + heuristic(next.x, next.y),
+                    cost: next_cost,
+                    visit: visits.len(),
+                });
+                visits.push(Visit {
+                    node: next,
+                    previous: Some((visit, step)),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+// Whether the path leading up to and including `visit` already uses the tile (x, y)
+fn path_contains(visits: &[Visit], visit: usize, x: isize, y: isize) -> bool {
+    let mut current = Some(visit);
+    while let Some(index) = current {
+        let node = visits[index].node;
+        if (node.x, node.y) == (x, y) {
+            return true;
+        }
+        current = visits[index].previous.map(|(previous, _)| previous);
+    }
+    false
+}
+
+fn build_path(
+    visits: &[Visit],
+    goal: usize,
+    goal_direction: Direction,
+) -> Vec<(isize, isize, Belt)> {
+    let mut path = Vec::new();
+    let mut visit = goal;
+    let mut output = goal_direction;
+    let mut entrance = false;
+    loop {
+        let node = visits[visit].node;
+        let previous = visits[visit].previous;
+        let belt = if entrance {
+            Belt::underground(output, Underground::Entrance)
+        } else if let Some((_, Step::Hop(..))) = previous {
+            Belt::underground(output, Underground::Exit)
+        } else {
+            Belt {
+                input: node.direction.flip(),
+                output,
+                ..Belt::new()
+            }
+        };
+        path.push((node.x, node.y, belt));
+
+        match previous {
+            Some((previous, Step::Belt(direction))) => {
+                output = direction;
+                entrance = false;
+                visit = previous;
+            }
+            Some((previous, Step::Hop(direction))) => {
+                output = direction;
+                entrance = true;
+                visit = previous;
+            }
+            None => break,
+        }
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{chest::Chest, Entity};
+
+    fn tiles(path: &[(isize, isize, Belt)]) -> Vec<(isize, isize)> {
+        path.iter().map(|&(x, y, _)| (x, y)).collect()
+    }
+
+    #[test]
+    fn straight_path_goes_directly_to_the_goal() {
+        let grid = Grid::new();
+        let path = route(&grid, (0, 0), (4, 0), Direction::East, false).unwrap();
+        assert_eq!(tiles(&path), (0..5).map(|x| (x, 0)).collect::<Vec<_>>());
+        assert!(path
+            .iter()
+            .all(|(_, _, belt)| belt.input == Direction::West && belt.output == Direction::East));
+    }
+
+    #[test]
+    fn path_goes_around_obstacles_without_crossing_itself() {
+        let mut grid = Grid::new();
+        grid.place_entity(2, 0, Entity::Chest(Chest::new()));
+        let path = route(&grid, (0, 0), (4, 0), Direction::East, false).unwrap();
+        let path_tiles = tiles(&path);
+        assert_eq!(path_tiles.len(), 7);
+        assert_eq!(path_tiles[0], (0, 0));
+        assert_eq!(path_tiles[6], (4, 0));
+        assert!(!path_tiles.contains(&(2, 0)));
+        for (i, tile) in path_tiles.iter().enumerate() {
+            assert!(!path_tiles[i + 1..].contains(tile));
+        }
+
+        // Each belt feeds the next one
+        for pair in path.windows(2) {
+            let ((x, y, belt), (next_x, next_y, next_belt)) = (pair[0], pair[1]);
+            assert_eq!(belt.output.offset(x, y), (next_x, next_y));
+            assert_eq!(next_belt.input, belt.output.flip());
+        }
+        assert_eq!(path.last().unwrap().2.output, Direction::East);
+    }
+
+    #[test]
+    fn underground_belts_hop_over_walls() {
+        let mut grid = Grid::new();
+        for y in 0..grid.tiles.len() as isize {
+            grid.place_entity(2, y, Entity::Chest(Chest::new()));
+        }
+        assert!(route(&grid, (0, 1), (3, 1), Direction::East, false).is_none());
+
+        let path = route(&grid, (0, 1), (3, 1), Direction::East, true).unwrap();
+        assert_eq!(tiles(&path), vec![(0, 1), (1, 1), (3, 1)]);
+        assert_eq!(path[1].2.underground, Some(Underground::Entrance));
+        assert_eq!(path[2].2.underground, Some(Underground::Exit));
+        assert!(path
+            .iter()
+            .all(|(_, _, belt)| belt.output == Direction::East));
+    }
+}
@@ -0,0 +1,106 @@
+use cgmath::{vec4, Deg, Rad, Vector4};
+
+use crate::{
+    grid::{Belt, Direction, Turn, BELT_SPEED},
+    item::Item,
+    renderer::{
+        atlas::{Animation, Atlas, UvRect},
+        sprite_batch::Sprite,
+    },
+};
+
+// Each belt frame moves the texture an eighth of a tile, which basic belts move
+// items in 4 ticks
+pub const BELT_FRAME_TICKS: u32 = 4;
+
+pub struct BeltAnimations {
+    pub left: Animation,
+    pub straight: Animation,
+    pub right: Animation,
+}
+
+impl BeltAnimations {
+    pub fn new(atlas: &Atlas) -> Self {
+        Self {
+            left: atlas.animation("belt-left", BELT_FRAME_TICKS),
+            straight: atlas.animation("belt-straight", BELT_FRAME_TICKS),
+            right: atlas.animation("belt-right", BELT_FRAME_TICKS),
+        }
+    }
+}
+
+// Belt textures point away from the input side, turns have their own animations
+pub fn belt_sprite(
+    animations: &BeltAnimations,
+    ticks: u64,
+    x: isize,
+    y: isize,
+    belt: &Belt,
+    tint: Vector4<f32>,
+) -> Sprite {
+    let animation = match belt.turn() {
+        Turn::Left => &animations.left,
+        Turn::Forward => &animations.straight,
+        Turn::Right => &animations.right,
+    };
+    let angle = match belt.input {
+        Direction::West => 90.0,
+        Direction::North => 180.0,
+        Direction::East => 270.0,
+        Direction::South => 0.0,
+    };
+    Sprite {
+        x: 16.0 + 32.0 * x as f32,
+        y: 16.0 + 32.0 * y as f32,
+        width: 32.0,
+        height: 32.0,
+        rotation: Rad::from(Deg(angle)).0,
+        uv: animation.frame(ticks, belt.tier.speed() / BELT_SPEED),
+        tint,
+    }
+}
+
+// Items on the belt at tile (x, y), `progress` of the way from where they were last
+// tick to where they are now
+pub fn belt_item_sprites(
+    belt: &Belt,
+    x: usize,
+    y: usize,
+    item_uv: UvRect,
+    progress: f32,
+) -> Vec<Sprite> {
+    let mut sprites = Vec::new();
+    for (lane, items) in belt.lanes.iter().enumerate() {
+        for lane_item in items.items() {
+            let position = lane_item.position as f32 - lane_item.moved as f32 * (1.0 - progress);
+            let (dx, dy) = belt.lane_point(lane, position);
+            sprites.push(Sprite {
+                x: 32.0 * (x as f32 + 0.5 + dx),
+                y: 32.0 * (y as f32 + 0.5 + dy),
+                width: 12.0,
+                height: 12.0,
+                rotation: 0.0,
+                uv: item_uv,
+                tint: item_color(lane_item.item),
+            });
+        }
+    }
+    sprites
+}
+
+pub fn item_color(item: Item) -> Vector4<f32> {
+    match item {
+        Item::Coal => vec4(0.1, 0.1, 0.1, 1.0),
+        Item::Stone => vec4(0.65, 0.55, 0.4, 1.0),
+        Item::IronOre => vec4(0.4, 0.5, 0.65, 1.0),
+        Item::CopperOre => vec4(0.8, 0.45, 0.2, 1.0),
+        Item::IronPlate => vec4(0.7, 0.75, 0.8, 1.0),
+        Item::CopperPlate => vec4(0.95, 0.6, 0.4, 1.0),
+        Item::SteelPlate => vec4(0.5, 0.55, 0.6, 1.0),
+        Item::StoneBrick => vec4(0.7, 0.4, 0.3, 1.0),
+        Item::AutomationSciencePack => vec4(0.85, 0.15, 0.15, 1.0),
+        Item::LogisticSciencePack => vec4(0.2, 0.75, 0.2, 1.0),
+        // Entity items only show up on belts as a generic crate
+        _ => vec4(0.6, 0.5, 0.3, 1.0),
+    }
+}
@@ -0,0 +1,287 @@
+use crate::item::Item;
+
+// Number of samples kept for each window, older samples are overwritten
+pub const SAMPLES: usize = 60;
+
+const TICKS_PER_SECOND: u32 = 60;
+const ITEM_COUNT: usize = Item::ALL.len();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    FiveSeconds,
+    OneMinute,
+    TenMinutes,
+    OneHour,
+}
+
+impl Window {
+    pub const ALL: [Window; 4] = [
+        Window::FiveSeconds,
+        Window::OneMinute,
+        Window::TenMinutes,
+        Window::OneHour,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::FiveSeconds => "5s",
+            Self::OneMinute => "1m",
+            Self::TenMinutes => "10m",
+            Self::OneHour => "1h",
+        }
+    }
+
+    pub fn ticks(&self) -> u32 {
+        match *self {
+            Self::FiveSeconds => 5 * TICKS_PER_SECOND,
+            Self::OneMinute => 60 * TICKS_PER_SECOND,
+            Self::TenMinutes => 600 * TICKS_PER_SECOND,
+            Self::OneHour => 3600 * TICKS_PER_SECOND,
+        }
+    }
+
+    pub fn ticks_per_sample(&self) -> u32 {
+        self.ticks() / SAMPLES as u32
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Produced,
+    Consumed,
+}
+
+#[derive(Clone, Debug)]
+pub struct RingBuffer {
+    samples: Vec<u32>,
+    // Slot the next sample is written to
+    next: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: vec![0; capacity],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, sample: u32) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % self.samples.len();
+        self.len = (self.len + 1).min(self.samples.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Samples from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let start = (self.next + self.samples.len() - self.len) % self.samples.len();
+        (0..self.len).map(move |i| self.samples[(start + i) % self.samples.len()])
+    }
+
+    pub fn sum(&self) -> u32 {
+        self.iter().sum()
+    }
+}
+
+// Samples of a single window, one buffer per item and kind
+#[derive(Clone, Debug)]
+struct Series {
+    window: Window,
+    buffers: [Vec<RingBuffer>; 2],
+    // Counts of the sample still being collected
+    pending: [[u32; ITEM_COUNT]; 2],
+}
+
+impl Series {
+    fn new(window: Window) -> Self {
+        Self {
+            window,
+            buffers: [
+                vec![RingBuffer::new(SAMPLES); ITEM_COUNT],
+                vec![RingBuffer::new(SAMPLES); ITEM_COUNT],
+            ],
+            pending: [[0; ITEM_COUNT]; 2],
+        }
+    }
+}
+
+// Items produced and consumed over time, for every item and window
+#[derive(Clone, Debug)]
+pub struct ProductionStats {
+    pub tick: u64,
+    current: [[u32; ITEM_COUNT]; 2],
+    series: Vec<Series>,
+}
+
+impl ProductionStats {
+    pub fn new() -> Self {
+        Self {
+            tick: 0,
+            current: [[0; ITEM_COUNT]; 2],
+            series: Window::ALL
+                .iter()
+                .map(|&window| Series::new(window))
+                .collect(),
+        }
+    }
+
+    pub fn produce(&mut self, item: Item, count: u32) {
+        self.current[Kind::Produced as usize][item.index()] += count;
+    }
+
+    pub fn consume(&mut self, item: Item, count: u32) {
+        self.current[Kind::Consumed as usize][item.index()] += count;
+    }
+
+    // Moves the counts of this tick into every window, closing samples that are complete
+    pub fn end_tick(&mut self) {
+        self.tick += 1;
+        for series in self.series.iter_mut() {
+            for kind in 0..2 {
+                for item in 0..ITEM_COUNT {
+                    series.pending[kind][item] += self.current[kind][item];
+                }
+            }
+
+            if self.tick % series.window.ticks_per_sample() as u64 == 0 {
+                for kind in 0..2 {
+                    for item in 0..ITEM_COUNT {
+                        series.buffers[kind][item].push(series.pending[kind][item]);
+                        series.pending[kind][item] = 0;
+                    }
+                }
+            }
+        }
+        self.current = [[0; ITEM_COUNT]; 2];
+    }
+
+    // Completed samples of a window, oldest first
+    pub fn samples(&self, window: Window, kind: Kind, item: Item) -> Vec<u32> {
+        self.series[window.index()].buffers[kind as usize][item.index()]
+            .iter()
+            .collect()
+    }
+
+    // Items counted within the window, including the sample still being collected
+    pub fn total(&self, window: Window, kind: Kind, item: Item) -> u32 {
+        let series = &self.series[window.index()];
+        series.buffers[kind as usize][item.index()].sum()
+            + series.pending[kind as usize][item.index()]
+    }
+
+    // Average items per minute over the window, or over the time passed if shorter
+    pub fn per_minute(&self, window: Window, kind: Kind, item: Item) -> f32 {
+        let series = &self.series[window.index()];
+        let buffer = &series.buffers[kind as usize][item.index()];
+        let ticks = buffer.len() as u64 * window.ticks_per_sample() as u64
+            + self.tick % window.ticks_per_sample() as u64;
+        if ticks == 0 {
+            return 0.0;
+        }
+        self.total(window, kind, item) as f32 * (60 * TICKS_PER_SECOND) as f32 / ticks as f32
+    }
+
+    // All completed samples of a window, one row per sample with the produced and
+    // consumed count of every item
+    pub fn to_csv(&self, window: Window) -> String {
+        let mut csv = String::from("seconds");
+        for item in Item::ALL.iter() {
+            csv.push_str(&format!(",{0}_produced,{0}_consumed", item.name()));
+        }
+        csv.push('\n');
+
+        let sample_seconds = window.ticks_per_sample() as f32 / TICKS_PER_SECOND as f32;
+        let series = &self.series[window.index()];
+        let len = series.buffers[0][0].len();
+        let columns: Vec<Vec<u32>> = Item::ALL
+            .iter()
+            .flat_map(|&item| [Kind::Produced, Kind::Consumed].map(|kind| (kind, item)))
+            .map(|(kind, item)| self.samples(window, kind, item))
+            .collect();
+        for row in 0..len {
+            // Start of the sample, relative to now
+            csv.push_str(&format!("{:.2}", -((len - row) as f32) * sample_seconds));
+            for column in columns.iter() {
+                csv.push_str(&format!(",{}", column[row]));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer_keeps_newest_samples() {
+        let mut buffer = RingBuffer::new(3);
+        for sample in 1..=5 {
+            buffer.push(sample);
+        }
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(buffer.sum(), 12);
+    }
+
+    #[test]
+    fn counts_are_sampled_per_window() {
+        let mut stats = ProductionStats::new();
+        for tick in 0..Window::OneMinute.ticks() {
+            if tick % 60 == 0 {
+                stats.produce(Item::IronPlate, 1);
+                stats.consume(Item::IronOre, 1);
+            }
+            stats.end_tick();
+        }
+
+        let five_seconds = stats.samples(Window::FiveSeconds, Kind::Produced, Item::IronPlate);
+        assert_eq!(five_seconds.len(), SAMPLES);
+        assert_eq!(
+            stats.total(Window::FiveSeconds, Kind::Produced, Item::IronPlate),
+            5
+        );
+        assert_eq!(
+            stats.total(Window::OneMinute, Kind::Consumed, Item::IronOre),
+            60
+        );
+        assert_eq!(
+            stats.per_minute(Window::OneMinute, Kind::Produced, Item::IronPlate),
+            60.0
+        );
+        assert_eq!(
+            stats.per_minute(Window::OneHour, Kind::Produced, Item::IronPlate),
+            60.0
+        );
+        assert_eq!(
+            stats.total(Window::OneMinute, Kind::Produced, Item::Coal),
+            0
+        );
+    }
+
+    #[test]
+    fn csv_has_a_row_per_sample() {
+        let mut stats = ProductionStats::new();
+        stats.produce(Item::Coal, 2);
+        for _ in 0..Window::FiveSeconds.ticks_per_sample() * 2 {
+            stats.end_tick();
+        }
+
+        let csv = stats.to_csv(Window::FiveSeconds);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("seconds,coal_produced,coal_consumed"));
+        assert!(lines[1].starts_with("-0.17,2,0"));
+        assert!(lines[2].starts_with("-0.08,0,0"));
+    }
+}
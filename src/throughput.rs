@@ -0,0 +1,461 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    entity::{drill::DRILL_MINING_TICKS, inserter::INSERTER_SWING_TICKS, Entity},
+    grid::{Belt, Grid, Underground, BELT_SPEED, ITEM_SPACING, LEFT_LANE, RIGHT_LANE},
+};
+
+pub const TICKS_PER_SECOND: f32 = 60.0;
+// Items per second a single fully compressed lane of a basic belt moves
+pub const LANE_RATE: f32 = TICKS_PER_SECOND * BELT_SPEED as f32 / ITEM_SPACING as f32;
+// Items per second of a drill and an inserter running at full speed. Inserters swing
+// once to pick an item up and once more after dropping it.
+pub const DRILL_RATE: f32 = TICKS_PER_SECOND / DRILL_MINING_TICKS as f32;
+pub const INSERTER_RATE: f32 = TICKS_PER_SECOND / (2 * INSERTER_SWING_TICKS) as f32;
+
+const EPSILON: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    // Index of the belt items are handed to and, for items entering it from the side,
+    // the one lane they all end up on
+    Belt(usize, Option<usize>),
+    // Chests take everything they are given
+    Chest,
+    Nothing,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BeltFlow {
+    pub x: isize,
+    pub y: isize,
+    // Items per second offered to the belt by sources and upstream belts
+    pub supply: f32,
+    // Items per second the belt actually carries
+    pub flow: f32,
+    pub capacity: f32,
+    // Items per second on the left and right lane, each carrying half the capacity
+    pub lane_flow: [f32; 2],
+}
+
+impl BeltFlow {
+    pub fn utilization(&self) -> f32 {
+        self.flow / self.capacity
+    }
+
+    pub fn at_capacity(&self) -> bool {
+        self.flow >= self.capacity - EPSILON
+    }
+
+    // Moving items while more are waiting to get on, either because the belt is full
+    // or because whatever it feeds can't take more
+    pub fn is_bottleneck(&self) -> bool {
+        self.flow > EPSILON && self.supply > self.flow + EPSILON
+    }
+
+    // Offered items but unable to move any of them
+    pub fn is_blocked(&self) -> bool {
+        self.flow < EPSILON && self.supply > EPSILON
+    }
+}
+
+pub struct Analysis {
+    pub belts: Vec<BeltFlow>,
+    index: HashMap<(isize, isize), usize>,
+}
+
+impl Analysis {
+    pub fn get(&self, x: isize, y: isize) -> Option<&BeltFlow> {
+        self.index.get(&(x, y)).map(|&i| &self.belts[i])
+    }
+
+    pub fn bottlenecks(&self) -> impl Iterator<Item = &BeltFlow> {
+        self.belts.iter().filter(|belt| belt.is_bottleneck())
+    }
+
+    pub fn report(&self) -> String {
+        let at_capacity = self.belts.iter().filter(|belt| belt.at_capacity()).count();
+        let mut report = format!(
+            "# {} belts, {} at capacity, {} bottlenecks\n",
+            self.belts.len(),
+            at_capacity,
+            self.bottlenecks().count()
+        );
+        report.push_str("x,y,supply,flow,capacity,utilization,left_flow,right_flow,status\n");
+        for belt in self.belts.iter() {
+            let status = if belt.is_bottleneck() {
+                "bottleneck"
+            } else if belt.at_capacity() {
+                "full"
+            } else if belt.is_blocked() {
+                "blocked"
+            } else if belt.flow < EPSILON {
+                "idle"
+            } else {
+                "ok"
+            };
+            report.push_str(&format!(
+                "{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{}\n",
+                belt.x,
+                belt.y,
+                belt.supply,
+                belt.flow,
+                belt.capacity,
+                belt.utilization(),
+                belt.lane_flow[LEFT_LANE],
+                belt.lane_flow[RIGHT_LANE],
+                status
+            ));
+        }
+        report
+    }
+}
+
+// Computes the theoretical items per second on every belt, with drills and inserters
+// putting items on belts as sources, and inserters taking items off belts and chests
+// at the end of a line as sinks. Machines are assumed to run at full speed. Each lane
+// is tracked on its own since sources and side loading only fill one of them.
+pub fn analyze(grid: &Grid) -> Analysis {
+    let height = grid.tiles.len() as isize;
+    let width = grid.tiles[0].len() as isize;
+
+    let mut positions = Vec::new();
+    let mut index = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(belt) = grid.get_belt(x, y) {
+                index.insert((x, y), positions.len());
+                positions.push((x, y, belt));
+            }
+        }
+    }
+
+    // Faster tiers move proportionally more items
+    let lane_rates: Vec<f32> = positions
+        .iter()
+        .map(|(_, _, belt)| LANE_RATE * belt.tier.speed() as f32 / BELT_SPEED as f32)
+        .collect();
+
+    let targets: Vec<Vec<Target>> = positions
+        .iter()
+        .map(|&(x, y, belt)| targets(grid, &index, x, y, belt))
+        .collect();
+
+    let mut sources = vec![[0.0f32; 2]; positions.len()];
+    let mut sinks = vec![0.0f32; positions.len()];
+    for y in 0..height {
+        for x in 0..width {
+            match grid.get_entity(x, y) {
+                Some(Entity::Drill(drill)) if grid.get_resource(x, y).is_some() => {
+                    if let Some(&i) = index.get(&drill.output.offset(x, y)) {
+                        sources[i][positions[i].2.drop_lane(drill.output)] += DRILL_RATE;
+                    }
+                }
+                Some(Entity::Inserter(inserter)) => {
+                    let pick = inserter.direction.flip().offset(x, y);
+                    let drop = inserter.direction.offset(x, y);
+                    if grid.get_entity(pick.0, pick.1).is_none()
+                        || !grid.get_entity(drop.0, drop.1).is_some_and(takes_items)
+                    {
+                        continue;
+                    }
+                    if let Some(&i) = index.get(&pick) {
+                        sinks[i] += INSERTER_RATE;
+                    }
+                    // Inserters moving items between belts count as both
+                    if let Some(&i) = index.get(&drop) {
+                        sources[i][positions[i].2.drop_lane(inserter.direction)] += INSERTER_RATE;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Order belts so each one comes before the belt it feeds. Belts in loops have no
+    // such order and are handled last.
+    let mut feeders = vec![0; positions.len()];
+    for target in targets.iter().flatten() {
+        if let Target::Belt(next, _) = *target {
+            feeders[next] += 1;
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..positions.len()).filter(|&i| feeders[i] == 0).collect();
+    let mut order = Vec::with_capacity(positions.len());
+    let mut ordered = vec![false; positions.len()];
+    while let Some(i) = queue.pop_front() {
+        order.push(i);
+        ordered[i] = true;
+        for target in targets[i].iter() {
+            if let Target::Belt(next, _) = *target {
+                feeders[next] -= 1;
+                if feeders[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    order.extend((0..positions.len()).filter(|&i| !ordered[i]));
+
+    // How many items per second each lane could get rid of, going backwards from the
+    // ends of the lines
+    let mut demand = vec![[0.0f32; 2]; positions.len()];
+    let accepts = |demand: &[[f32; 2]], target: Target, lane: usize| match target {
+        Target::Belt(next, Some(side_lane)) => demand[next][side_lane],
+        Target::Belt(next, None) => demand[next][lane],
+        Target::Chest => f32::INFINITY,
+        Target::Nothing => 0.0,
+    };
+    for &i in order.iter().rev() {
+        for lane in 0..2 {
+            let downstream: f32 = targets[i]
+                .iter()
+                .map(|&target| accepts(&demand, target, lane))
+                .sum();
+            demand[i][lane] = (sinks[i] + downstream).min(lane_rates[i]);
+        }
+    }
+
+    let mut supply = sources;
+    let mut flows = vec![[0.0f32; 2]; positions.len()];
+    for &i in order.iter() {
+        // Inserters take items off whichever lane has them
+        let offered = [
+            supply[i][0].min(lane_rates[i]),
+            supply[i][1].min(lane_rates[i]),
+        ];
+        let taken = split(sinks[i], &offered);
+        // Both lanes of a belt joining another from the side end up on the same lane
+        let mut side_loaded = vec![0.0f32; targets[i].len()];
+        for lane in 0..2 {
+            let limits: Vec<f32> = targets[i]
+                .iter()
+                .zip(side_loaded.iter())
+                .map(|(&target, &given)| accepts(&demand, target, lane) - given)
+                .collect();
+            let flow = offered[lane].min(taken[lane] + limits.iter().sum::<f32>());
+            flows[i][lane] = flow;
+            let shares = split(flow - taken[lane], &limits);
+            for ((target, share), given) in targets[i].iter().zip(shares).zip(&mut side_loaded) {
+                match *target {
+                    Target::Belt(next, Some(side_lane)) => {
+                        supply[next][side_lane] += share;
+                        *given += share;
+                    }
+                    Target::Belt(next, None) => supply[next][lane] += share,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    let belts = positions
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y, _))| BeltFlow {
+            x,
+            y,
+            supply: supply[i][0] + supply[i][1],
+            flow: flows[i][0] + flows[i][1],
+            capacity: 2.0 * lane_rates[i],
+            lane_flow: flows[i],
+        })
+        .collect();
+
+    Analysis { belts, index }
+}
+
+// Splits `amount` evenly between outputs like a splitter does, handing whatever an
+// output can't take to the others
+fn split(amount: f32, limits: &[f32]) -> Vec<f32> {
+    let mut shares = vec![0.0; limits.len()];
+    let mut remaining = amount;
+    let mut open: Vec<usize> = (0..limits.len()).collect();
+    while remaining > EPSILON && !open.is_empty() {
+        let share = remaining / open.len() as f32;
+        for &i in open.iter() {
+            let given = share.min(limits[i] - shares[i]);
+            shares[i] += given;
+            remaining -= given;
+        }
+        open.retain(|&i| limits[i] - shares[i] > EPSILON);
+    }
+    shares
+}
+
+// Where items leaving a belt go, following the same connections the simulation uses
+fn targets(
+    grid: &Grid,
+    index: &HashMap<(isize, isize), usize>,
+    x: isize,
+    y: isize,
+    belt: Belt,
+) -> Vec<Target> {
+    let mut fronts = vec![if belt.underground == Some(Underground::Entrance) {
+        let (exit_x, exit_y) = grid.belt_target(x, y, belt);
+        if (exit_x, exit_y) == (x, y) {
+            return vec![Target::Nothing];
+        }
+        (grid.get_belt(exit_x, exit_y), (exit_x, exit_y))
+    } else {
+        grid.belt_in_front_of(x, y, belt)
+    }];
+    // Splitters also output in front of their other half
+    for &(front_x, front_y) in grid.belt_targets(x, y, belt).iter().skip(1) {
+        fronts.push((grid.get_belt(front_x, front_y), (front_x, front_y)));
+    }
+
+    fronts
+        .into_iter()
+        .map(|(front_belt, (front_x, front_y))| match front_belt {
+            Some(front_belt) => match belt.target_lane(&front_belt, LEFT_LANE) {
+                Some((lane, position)) => {
+                    let side_lane = if position == 0 { None } else { Some(lane) };
+                    Target::Belt(index[&(front_x, front_y)], side_lane)
+                }
+                None => Target::Nothing,
+            },
+            None => match grid.get_entity(front_x, front_y) {
+                Some(Entity::Chest(_)) => Target::Chest,
+                _ => Target::Nothing,
+            },
+        })
+        .collect()
+}
+
+// Whether inserters have somewhere to put the items they pick up
+fn takes_items(entity: &Entity) -> bool {
+    matches!(
+        entity,
+        Entity::Belt(_)
+            | Entity::Chest(_)
+            | Entity::Furnace(_)
+            | Entity::Generator(_)
+            | Entity::Boiler(_)
+            | Entity::Lab(_)
+            | Entity::Rail(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{chest::Chest, drill::Drill, inserter::Inserter},
+        grid::{Direction, Resource},
+        item::Item,
+    };
+
+    fn line(grid: &mut Grid, y: isize, from: isize, to: isize) {
+        for x in from..=to {
+            grid.place_belt(x, y, Belt::straight(Direction::East));
+        }
+    }
+
+    fn add_drill(grid: &mut Grid, x: isize, y: isize) {
+        grid.resources[y as usize][x as usize] = Some(Resource {
+            item: Item::IronOre,
+            amount: 1000,
+        });
+        grid.place_entity(x, y, Entity::Drill(Drill::new(Direction::North)));
+    }
+
+    #[test]
+    fn dead_end_line_carries_nothing() {
+        let mut grid = Grid::new();
+        line(&mut grid, 1, 0, 3);
+        add_drill(&mut grid, 0, 0);
+
+        let analysis = analyze(&grid);
+        assert_eq!(analysis.get(0, 1).unwrap().supply, DRILL_RATE);
+        assert!(analysis.belts.iter().all(|belt| belt.flow == 0.0));
+    }
+
+    #[test]
+    fn drills_feed_chest_at_end_of_line() {
+        let mut grid = Grid::new();
+        line(&mut grid, 1, 0, 3);
+        grid.place_entity(4, 1, Entity::Chest(Chest::new()));
+        add_drill(&mut grid, 0, 0);
+        add_drill(&mut grid, 2, 0);
+
+        let analysis = analyze(&grid);
+        assert_eq!(analysis.get(1, 1).unwrap().flow, DRILL_RATE);
+        assert_eq!(analysis.get(3, 1).unwrap().flow, 2.0 * DRILL_RATE);
+        assert_eq!(analysis.bottlenecks().count(), 0);
+    }
+
+    #[test]
+    fn inserter_takes_items_off_the_line() {
+        let mut grid = Grid::new();
+        line(&mut grid, 1, 0, 3);
+        add_drill(&mut grid, 0, 0);
+        add_drill(&mut grid, 1, 0);
+        grid.place_entity(3, 2, Entity::Inserter(Inserter::new(Direction::North)));
+        grid.place_entity(3, 3, Entity::Chest(Chest::new()));
+
+        let analysis = analyze(&grid);
+        assert_eq!(analysis.get(3, 1).unwrap().flow, INSERTER_RATE);
+        assert_eq!(analysis.get(0, 1).unwrap().flow, DRILL_RATE);
+    }
+
+    #[test]
+    fn side_loading_is_limited_to_one_lane() {
+        let mut grid = Grid::new();
+        line(&mut grid, 0, 0, 3);
+        grid.place_entity(4, 0, Entity::Chest(Chest::new()));
+        for y in (1..=20).rev() {
+            grid.place_belt(1, y, Belt::straight(Direction::South));
+            add_drill(&mut grid, 0, y);
+            add_drill(&mut grid, 2, y);
+        }
+        for y in 1..=20 {
+            if let Some(Entity::Drill(drill)) = grid.get_entity_mut(0, y) {
+                drill.output = Direction::East;
+            }
+            if let Some(Entity::Drill(drill)) = grid.get_entity_mut(2, y) {
+                drill.output = Direction::West;
+            }
+        }
+
+        let analysis = analyze(&grid);
+        assert_eq!(analysis.get(1, 0).unwrap().flow, LANE_RATE);
+        let feeder = analysis.get(1, 1).unwrap();
+        assert_eq!(feeder.flow, LANE_RATE);
+        assert!(feeder.is_bottleneck());
+        assert!(!feeder.at_capacity());
+        assert!(analysis.get(1, 20).unwrap().supply == 2.0 * DRILL_RATE);
+    }
+
+    #[test]
+    fn each_lane_is_capped_on_its_own() {
+        let mut grid = Grid::new();
+        line(&mut grid, 1, 0, 20);
+        grid.place_entity(21, 1, Entity::Chest(Chest::new()));
+        // All drills drop on the same lane
+        for x in 0..20 {
+            add_drill(&mut grid, x, 0);
+        }
+
+        let analysis = analyze(&grid);
+        let end = analysis.get(20, 1).unwrap();
+        assert_eq!(end.lane_flow[LEFT_LANE], LANE_RATE);
+        assert_eq!(end.lane_flow[RIGHT_LANE], 0.0);
+        assert!(!end.at_capacity());
+        assert!(analysis.bottlenecks().count() > 0);
+    }
+
+    #[test]
+    fn inserters_without_anywhere_to_drop_take_nothing() {
+        let mut grid = Grid::new();
+        line(&mut grid, 1, 0, 3);
+        add_drill(&mut grid, 0, 0);
+        grid.place_entity(3, 2, Entity::Inserter(Inserter::new(Direction::North)));
+
+        let analysis = analyze(&grid);
+        assert_eq!(analysis.get(3, 1).unwrap().flow, 0.0);
+
+        grid.place_entity(3, 3, Entity::Chest(Chest::new()));
+        assert_eq!(analyze(&grid).get(3, 1).unwrap().flow, DRILL_RATE);
+    }
+}
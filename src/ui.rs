@@ -0,0 +1,250 @@
+use cgmath::{vec4, Vector4};
+
+use crate::renderer::text::{Font, GLYPH_HEIGHT};
+
+// Screen pixels from the bottom left corner, y going up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    pub fn shrink(&self, amount: f32) -> Self {
+        Self::new(
+            self.x + amount,
+            self.y + amount,
+            self.width - 2.0 * amount,
+            self.height - 2.0 * amount,
+        )
+    }
+}
+
+// Text with its top left corner at (x, y)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub scale: f32,
+    pub color: Vector4<f32>,
+}
+
+// Shapes and text drawn in order, rectangles first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer<Icon> {
+    pub rects: Vec<(Rect, Vector4<f32>)>,
+    pub icons: Vec<(Rect, Icon)>,
+    pub labels: Vec<Label>,
+}
+
+impl<Icon> Layer<Icon> {
+    fn new() -> Self {
+        Self {
+            rects: Vec::new(),
+            icons: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.rects.clear();
+        self.icons.clear();
+        self.labels.clear();
+    }
+}
+
+const TEXT_SCALE: f32 = 2.0;
+const PANEL_COLOR: Vector4<f32> = vec4(0.1, 0.1, 0.1, 0.8);
+const SLOT_COLOR: Vector4<f32> = vec4(0.25, 0.25, 0.25, 1.0);
+const HOVERED_COLOR: Vector4<f32> = vec4(0.4, 0.4, 0.4, 1.0);
+const SELECTED_COLOR: Vector4<f32> = vec4(0.9, 0.6, 0.1, 1.0);
+
+// Immediate mode UI. Widgets are declared every frame before the world handles input,
+// so it knows whether the mouse was used, and are drawn later from the collected
+// layers. `Icon` is whatever the game draws inside hotbar slots.
+pub struct Ui<Icon> {
+    pub mouse_x: f32,
+    pub mouse_y: f32,
+    // The left button went down this frame
+    clicked: bool,
+    // Over a widget declared this frame
+    hovered: bool,
+    // A press that started on the UI keeps the mouse until it is released
+    captured: bool,
+    pub layer: Layer<Icon>,
+    // Drawn after everything else
+    pub tooltip: Layer<Icon>,
+}
+
+impl<Icon> Ui<Icon> {
+    pub fn new() -> Self {
+        Self {
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            clicked: false,
+            hovered: false,
+            captured: false,
+            layer: Layer::new(),
+            tooltip: Layer::new(),
+        }
+    }
+
+    // Starts a frame, `clicked` when the left button went down and `held` while it is down
+    pub fn begin(&mut self, mouse_x: f32, mouse_y: f32, clicked: bool, held: bool) {
+        self.mouse_x = mouse_x;
+        self.mouse_y = mouse_y;
+        self.clicked = clicked;
+        self.captured &= held;
+        self.hovered = false;
+        self.layer.clear();
+        self.tooltip.clear();
+    }
+
+    // Whether the world should leave the mouse alone, valid once all widgets are declared
+    pub fn wants_mouse(&mut self) -> bool {
+        if self.hovered && self.clicked {
+            self.captured = true;
+        }
+        self.hovered || self.captured
+    }
+
+    fn hover(&mut self, rect: Rect) -> bool {
+        let hovered = rect.contains(self.mouse_x, self.mouse_y);
+        self.hovered |= hovered;
+        hovered
+    }
+
+    // Returns whether it was clicked
+    pub fn panel(&mut self, rect: Rect) -> bool {
+        let hovered = self.hover(rect);
+        self.layer.rects.push((rect, PANEL_COLOR));
+        hovered && self.clicked
+    }
+
+    pub fn label(&mut self, text: &str, x: f32, y: f32, color: Vector4<f32>) {
+        self.layer.labels.push(Label {
+            text: text.to_string(),
+            x,
+            y,
+            scale: TEXT_SCALE,
+            color,
+        });
+    }
+
+    // Returns whether it was clicked
+    pub fn button(&mut self, rect: Rect, text: &str) -> bool {
+        let hovered = self.hover(rect);
+        let color = if hovered { HOVERED_COLOR } else { SLOT_COLOR };
+        self.layer.rects.push((rect, color));
+        let (width, height) = Font::measure(text, TEXT_SCALE);
+        self.label(
+            text,
+            rect.x + (rect.width - width) / 2.0,
+            rect.y + (rect.height + height) / 2.0,
+            vec4(1.0, 1.0, 1.0, 1.0),
+        );
+        hovered && self.clicked
+    }
+
+    // Hotbar slot showing an icon and optionally a count in its corner, returns whether
+    // it was clicked
+    pub fn slot(
+        &mut self,
+        rect: Rect,
+        icon: Icon,
+        count: Option<u32>,
+        selected: bool,
+        tooltip: &str,
+    ) -> bool {
+        let hovered = self.hover(rect);
+        if selected {
+            self.layer.rects.push((rect, SELECTED_COLOR));
+        }
+        let color = if hovered { HOVERED_COLOR } else { SLOT_COLOR };
+        self.layer.rects.push((rect.shrink(2.0), color));
+        self.layer.icons.push((rect.shrink(4.0), icon));
+        if let Some(count) = count {
+            let text = count.to_string();
+            let (width, height) = Font::measure(&text, 1.0);
+            self.layer.labels.push(Label {
+                text,
+                x: rect.x + rect.width - width - 3.0,
+                y: rect.y + height + 3.0,
+                scale: 1.0,
+                color: vec4(1.0, 1.0, 1.0, 1.0),
+            });
+        }
+        if hovered {
+            self.show_tooltip(tooltip);
+        }
+        hovered && self.clicked
+    }
+
+    // Shown above and to the right of the mouse, replacing any earlier tooltip
+    pub fn show_tooltip(&mut self, text: &str) {
+        let (width, height) = Font::measure(text, TEXT_SCALE);
+        let padding = GLYPH_HEIGHT / 2.0;
+        let rect = Rect::new(
+            self.mouse_x + 12.0,
+            self.mouse_y + 12.0,
+            width + 2.0 * padding,
+            height + 2.0 * padding,
+        );
+        self.tooltip.clear();
+        self.tooltip.rects.push((rect, PANEL_COLOR));
+        self.tooltip.labels.push(Label {
+            text: text.to_string(),
+            x: rect.x + padding,
+            y: rect.y + rect.height - padding,
+            scale: TEXT_SCALE,
+            color: vec4(1.0, 1.0, 1.0, 1.0),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widgets_take_the_mouse_from_the_world() {
+        let mut ui: Ui<u32> = Ui::new();
+        let slot = Rect::new(10.0, 10.0, 40.0, 40.0);
+
+        ui.begin(20.0, 20.0, true, true);
+        assert!(ui.slot(slot, 7, Some(3), true, "Belt"));
+        assert!(ui.wants_mouse());
+        assert_eq!(ui.layer.icons, vec![(slot.shrink(4.0), 7)]);
+        assert_eq!(ui.layer.labels[0].text, "3");
+        assert_eq!(ui.tooltip.labels[0].text, "Belt");
+
+        // Dragging off the slot with the button held still belongs to the UI
+        ui.begin(200.0, 200.0, false, true);
+        assert!(!ui.slot(slot, 7, None, false, "Belt"));
+        assert!(ui.wants_mouse());
+        assert!(ui.tooltip.labels.is_empty());
+
+        ui.begin(200.0, 200.0, false, false);
+        assert!(!ui.panel(slot));
+        assert!(!ui.wants_mouse());
+        ui.begin(200.0, 200.0, true, true);
+        assert!(!ui.button(slot, "Ok"));
+        assert!(!ui.wants_mouse());
+    }
+}
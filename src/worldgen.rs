@@ -0,0 +1,160 @@
+use crate::{
+    grid::{Grid, Resource},
+    item::Item,
+};
+
+// Ores generated as patches, each gets its own noise field
+const ORES: [Item; 4] = [Item::IronOre, Item::CopperOre, Item::Coal, Item::Stone];
+
+// Size of a noise cell in tiles, roughly the size of a patch
+const PATCH_SCALE: f32 = 24.0;
+// Noise values above this become ore
+const PATCH_THRESHOLD: f32 = 0.4;
+const MIN_AMOUNT: f32 = 200.0;
+const MAX_AMOUNT: f32 = 2000.0;
+
+pub fn generate_resources(grid: &mut Grid, seed: u64) {
+    let fields = ORES
+        .iter()
+        .enumerate()
+        .map(|(i, ore)| (*ore, Perlin::new(seed.wrapping_add(i as u64 * 7919))))
+        .collect::<Vec<_>>();
+
+    for y in 0..grid.resources.len() {
+        for x in 0..grid.resources[y].len() {
+            let best = fields
+                .iter()
+                .map(|(ore, noise)| {
+                    let value = noise.fractal(x as f32 / PATCH_SCALE, y as f32 / PATCH_SCALE, 3);
+                    (*ore, value)
+                })
+                .filter(|(_, value)| *value > PATCH_THRESHOLD)
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+            grid.resources[y][x] = best.map(|(item, value)| {
+                let richness = ((value - PATCH_THRESHOLD) / (1.0 - PATCH_THRESHOLD)).min(1.0);
+                Resource {
+                    item,
+                    amount: (MIN_AMOUNT + richness * (MAX_AMOUNT - MIN_AMOUNT)) as u32,
+                }
+            });
+        }
+    }
+}
+
+// Classic 2D gradient noise with a permutation table shuffled from a seed
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            state = splitmix64(state);
+            let j = (state % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let mut permutation = [0u8; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i % 256];
+        }
+
+        Self { permutation }
+    }
+
+    // Noise value in roughly [-1, 1]
+    pub fn get(&self, x: f32, y: f32) -> f32 {
+        let cell_x = x.floor();
+        let cell_y = y.floor();
+        let xi = (cell_x as i32 & 255) as usize;
+        let yi = (cell_y as i32 & 255) as usize;
+        let xf = x - cell_x;
+        let yf = y - cell_y;
+
+        let p = &self.permutation;
+        let aa = p[p[xi] as usize + yi];
+        let ab = p[p[xi] as usize + yi + 1];
+        let ba = p[p[xi + 1] as usize + yi];
+        let bb = p[p[xi + 1] as usize + yi + 1];
+
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let x1 = lerp(gradient(aa, xf, yf), gradient(ba, xf - 1.0, yf), u);
+        let x2 = lerp(
+            gradient(ab, xf, yf - 1.0),
+            gradient(bb, xf - 1.0, yf - 1.0),
+            u,
+        );
+        lerp(x1, x2, v) * std::f32::consts::SQRT_2
+    }
+
+    // Sum of octaves with halving amplitude, normalized to roughly [-1, 1]
+    pub fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for _ in 0..octaves {
+            total += self.get(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+pub fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patches(seed: u64) -> Vec<Vec<Option<Resource>>> {
+        let mut grid = Grid::new();
+        generate_resources(&mut grid, seed);
+        grid.resources
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_patches() {
+        let first = patches(42);
+        assert!(first.iter().flatten().any(|resource| resource.is_some()));
+        assert_eq!(first, patches(42));
+        assert_ne!(first, patches(43));
+    }
+}